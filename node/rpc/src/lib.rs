@@ -50,8 +50,10 @@ pub struct MiningStats {
 	rate: f64,
 }
 
+mod nonce;
 mod task;
-pub use task::{Task, TaskApiServer};
+pub use nonce::{resync_offchain_nonce, ResyncError};
+pub use task::{LocalTaskInfo, Task, TaskApiServer, TaskInfo};
 
 #[cfg(test)]
 mod test {
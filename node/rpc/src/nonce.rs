@@ -0,0 +1,121 @@
+use creditcoin_node_runtime::{AccountId, Index};
+use pallet_offchain_task_scheduler::ocw::{lock_key, nonce_key, LOCK_DEADLINE};
+use parity_scale_codec::{Decode, Encode};
+use sp_core::offchain::OffchainStorage;
+use sp_offchain::STORAGE_PREFIX;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ResyncError {
+	/// The offchain worker is submitting a transaction, try again later.
+	Locked,
+	Codec(parity_scale_codec::Error),
+}
+
+impl core::fmt::Display for ResyncError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			Self::Locked => f.write_str("the offchain nonce is locked by the offchain worker"),
+			Self::Codec(e) => write!(f, "invalid offchain nonce: {e}"),
+		}
+	}
+}
+
+fn now() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+}
+
+/// Takes the lock the offchain worker holds while it uses the nonce. It is a `StorageLock<Time>`
+/// in the runtime, a deadline in milliseconds that other holders may take over once it's passed.
+fn try_lock<S: OffchainStorage>(storage: &mut S, key: &[u8]) -> Result<(), ResyncError> {
+	let now = now();
+	let current = storage.get(STORAGE_PREFIX, key);
+	if let Some(deadline) = current.as_ref().and_then(|v| u64::decode(&mut &v[..]).ok()) {
+		if now <= deadline {
+			return Err(ResyncError::Locked);
+		}
+	}
+
+	let deadline = now.saturating_add(LOCK_DEADLINE).encode();
+	if storage.compare_and_set(STORAGE_PREFIX, key, current.as_deref(), &deadline) {
+		Ok(())
+	} else {
+		Err(ResyncError::Locked)
+	}
+}
+
+/// Moves the offchain nonce of `account` back to `next_index` when it's ahead of it, which
+/// happens when a transaction the offchain worker submitted was dropped from the pool. Returns
+/// the nonce that was replaced, `None` if there was nothing to do.
+pub fn resync_offchain_nonce<S: OffchainStorage>(
+	storage: &mut S,
+	account: &AccountId,
+	next_index: Index,
+) -> Result<Option<Index>, ResyncError> {
+	let lock = lock_key(account);
+	try_lock(storage, &lock)?;
+
+	let key = nonce_key(account);
+	let result = storage
+		.get(STORAGE_PREFIX, &key)
+		.map(|v| Index::decode(&mut &v[..]))
+		.transpose()
+		.map_err(ResyncError::Codec)
+		.map(|nonce| {
+			let ahead = nonce.filter(|&nonce| nonce > next_index)?;
+			storage.set(STORAGE_PREFIX, &key, &next_index.encode());
+			Some(ahead)
+		});
+
+	storage.remove(STORAGE_PREFIX, &lock);
+	result
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::offchain::storage::InMemOffchainStorage;
+
+	fn nonce(storage: &InMemOffchainStorage, account: &AccountId) -> Option<Index> {
+		storage
+			.get(STORAGE_PREFIX, &nonce_key(account))
+			.map(|v| Index::decode(&mut &v[..]).unwrap())
+	}
+
+	#[test]
+	fn nonce_ahead_of_the_next_index_is_reset() {
+		let mut storage = InMemOffchainStorage::default();
+		let account = AccountId::new([1; 32]);
+		storage.set(STORAGE_PREFIX, &nonce_key(&account), &9u32.encode());
+
+		assert_eq!(resync_offchain_nonce(&mut storage, &account, 5), Ok(Some(9)));
+		assert_eq!(nonce(&storage, &account), Some(5));
+		assert_eq!(storage.get(STORAGE_PREFIX, &lock_key(&account)), None);
+
+		assert_eq!(resync_offchain_nonce(&mut storage, &account, 7), Ok(None));
+		assert_eq!(nonce(&storage, &account), Some(5));
+	}
+
+	#[test]
+	fn missing_nonce_is_left_to_the_offchain_worker() {
+		let mut storage = InMemOffchainStorage::default();
+		let account = AccountId::new([1; 32]);
+
+		assert_eq!(resync_offchain_nonce(&mut storage, &account, 5), Ok(None));
+		assert_eq!(nonce(&storage, &account), None);
+	}
+
+	#[test]
+	fn held_lock_is_respected_until_it_expires() {
+		let mut storage = InMemOffchainStorage::default();
+		let account = AccountId::new([1; 32]);
+		storage.set(STORAGE_PREFIX, &nonce_key(&account), &9u32.encode());
+
+		storage.set(STORAGE_PREFIX, &lock_key(&account), &(now() + 10_000).encode());
+		assert_eq!(resync_offchain_nonce(&mut storage, &account, 5), Err(ResyncError::Locked));
+		assert_eq!(nonce(&storage, &account), Some(9));
+
+		storage.set(STORAGE_PREFIX, &lock_key(&account), &(now() - 1).encode());
+		assert_eq!(resync_offchain_nonce(&mut storage, &account, 5), Ok(Some(9)));
+	}
+}
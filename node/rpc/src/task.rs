@@ -2,11 +2,20 @@ use super::Error;
 use core::marker::PhantomData;
 use core::str::FromStr;
 use creditcoin_node_runtime as runtime;
+use frame_system_rpc_runtime_api::AccountNonceApi;
+use pallet_offchain_task_scheduler::{
+	registry::{TaskKindIndex, TaskRegistry},
+	tasks::{self, TaskAttempts},
+};
+use parity_scale_codec::{Decode, Encode};
 use sc_rpc::DenyUnsafe;
+use sc_transaction_pool_api::{InPoolTransaction, TransactionPool, TransactionTag};
+use sp_api::ApiExt;
 use sp_blockchain::HeaderBackend;
+use sp_core::offchain::{storage::InMemOffchainStorage, OffchainStorage};
 use sp_runtime::traits;
 use std::sync::Arc;
-use task_scheduler_runtime_api::TaskApi;
+use task_scheduler_runtime_api::{PendingTask, TaskApi};
 
 use jsonrpsee::{
 	core::{async_trait, Error as JsonRpseeError, RpcResult},
@@ -18,45 +27,195 @@ use jsonrpsee::{
 };
 
 type AccountId = <runtime::Runtime as frame_system::Config>::AccountId;
+type BlockNumber = runtime::BlockNumber;
+type Hash = runtime::Hash;
+type Index = runtime::Index;
+
+/// A task pending in the scheduler, as seen by this node.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskInfo {
+	pub id: String,
+	/// The task variant, e.g. `VerifyTransfer`.
+	pub kind: String,
+	/// The index the task's kind is registered under.
+	pub kind_index: TaskKindIndex,
+	/// The decoded task.
+	pub payload: String,
+	/// The block at which the task is next due.
+	pub deadline: BlockNumber,
+	pub scheduled_at: Option<BlockNumber>,
+	pub reschedules: u32,
+	/// Only available when the node has access to its offchain storage.
+	pub local: Option<LocalTaskInfo>,
+}
+
+/// The local authority's view of a task, read from its offchain storage.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalTaskInfo {
+	/// The block until which this node holds the task's lock, if it does.
+	pub locked_until: Option<BlockNumber>,
+	pub attempts: u32,
+	pub last_attempt: Option<BlockNumber>,
+	pub last_error: Option<String>,
+}
 
 #[rpc(client, server)]
 pub trait TaskApi<AccountId> {
 	#[method(name = "task_getOffchainNonceKey")]
 	async fn offchain_nonce_key(&self, account_id: String) -> RpcResult<Vec<u8>>;
+
+	#[method(name = "task_getPendingTasks")]
+	async fn pending_tasks(&self) -> RpcResult<Vec<TaskInfo>>;
+
+	#[method(name = "task_getTask")]
+	async fn task(&self, id: String) -> RpcResult<Option<TaskInfo>>;
+
+	/// Moves the account's offchain nonce back to its next index if it's ahead, returns the
+	/// nonce it replaced. The next index counts the account's transactions ready in the pool,
+	/// like `system_accountNextIndex`, so their nonces aren't handed out again.
+	#[method(name = "task_resyncNonce")]
+	async fn resync_nonce(&self, account_id: String) -> RpcResult<Option<Index>>;
 }
 
-pub struct Task<C, B> {
+pub struct Task<C, P, B, S = InMemOffchainStorage> {
 	client: Arc<C>,
+	pool: Arc<P>,
 	deny_unsafe: DenyUnsafe,
+	offchain_storage: Option<S>,
 	_p: PhantomData<B>,
 }
 
-impl<C, B> Task<C, B> {
-	pub fn new(client: Arc<C>, deny_unsafe: DenyUnsafe) -> Self {
-		Self { deny_unsafe, client, _p: Default::default() }
+impl<C, P, B> Task<C, P, B> {
+	pub fn new(client: Arc<C>, pool: Arc<P>, deny_unsafe: DenyUnsafe) -> Self {
+		Self { deny_unsafe, client, pool, offchain_storage: None, _p: Default::default() }
+	}
+}
+
+impl<C, P, B, S> Task<C, P, B, S> {
+	pub fn with_offchain_storage<S2>(self, offchain_storage: Option<S2>) -> Task<C, P, B, S2> {
+		let Self { client, pool, deny_unsafe, _p, .. } = self;
+		Task { client, pool, deny_unsafe, offchain_storage, _p }
+	}
+}
+
+impl<C, P, B, S: OffchainStorage> Task<C, P, B, S> {
+	fn local_view(&self, id: &Hash, scheduled_at: Option<BlockNumber>) -> Option<LocalTaskInfo> {
+		let storage = self.offchain_storage.as_ref()?;
+		let get = |key: Vec<u8>| storage.get(sp_offchain::STORAGE_PREFIX, &key);
+
+		// the lock holds a block-and-time deadline, only the block number is of interest here
+		let locked_until = get(tasks::storage_key(id, &scheduled_at))
+			.and_then(|v| <(BlockNumber, u64)>::decode(&mut &v[..]).ok())
+			.map(|(block, _)| block);
+		let attempts = get(tasks::attempts_key(id))
+			.and_then(|v| TaskAttempts::<BlockNumber>::decode(&mut &v[..]).ok());
+
+		Some(match attempts {
+			Some(TaskAttempts { attempts, last_attempt, last_error }) => LocalTaskInfo {
+				locked_until,
+				attempts,
+				last_attempt: Some(last_attempt),
+				last_error: Some(String::from_utf8_lossy(&last_error).into_owned()),
+			},
+			None => {
+				LocalTaskInfo { locked_until, attempts: 0, last_attempt: None, last_error: None }
+			},
+		})
+	}
+
+	fn task_info(&self, pending: PendingTask<BlockNumber, Hash, runtime::Task>) -> TaskInfo {
+		let PendingTask { id, deadline, scheduled_at, reschedules, task } = pending;
+		TaskInfo {
+			id: format!("{id:?}"),
+			kind: task.kind_name().to_owned(),
+			kind_index: task.kind(),
+			payload: format!("{task:?}"),
+			deadline,
+			scheduled_at,
+			reschedules,
+			local: self.local_view(&id, scheduled_at),
+		}
+	}
+}
+
+fn parse_account(account_id: &str) -> RpcResult<AccountId> {
+	AccountId::from_str(account_id).map_err(|e| {
+		JsonRpseeError::Call(CallError::Custom(ErrorObject::owned(
+			ErrorCode::InvalidParams.code(),
+			"Not a valid hex-string or SS58 address.",
+			Some(format!("{e:?}")),
+		)))
+	})
+}
+
+/// Moves `nonce` past the account's transactions in `provided`, the tags of the transactions
+/// ready in the pool, in order. Each provides the account's tag for the nonce it uses.
+fn next_index(
+	provided: impl IntoIterator<Item = Vec<TransactionTag>>,
+	account: &AccountId,
+	nonce: Index,
+) -> Index {
+	let mut next = nonce;
+	let mut tag = (account, next).encode();
+	for tags in provided {
+		if tags.first() == Some(&tag) {
+			next = next.saturating_add(1);
+			tag = (account, next).encode();
+		}
 	}
+	next
+}
+
+/// The `TaskApi` version that added `pending_tasks` and `pending_task`.
+const PENDING_TASKS_API_VERSION: u32 = 2;
+
+fn runtime_error(message: &str, e: impl core::fmt::Debug) -> JsonRpseeError {
+	JsonRpseeError::Call(CallError::Custom(ErrorObject::owned(
+		ErrorCode::ServerError(Error::RuntimeError.into()).code(),
+		message,
+		Some(format!("{e:?}")),
+	)))
+}
+
+/// Fails with `MethodNotFound` when the runtime at `at` is older than `version` of the `TaskApi`.
+fn ensure_api_version<C, B>(api: &C::Api, at: B::Hash, version: u32) -> RpcResult<()>
+where
+	C: sp_api::ProvideRuntimeApi<B>,
+	C::Api: TaskApi<B, AccountId, BlockNumber, Hash, runtime::Task>,
+	B: traits::Block,
+{
+	let supported = api
+		.api_version::<dyn TaskApi<B, AccountId, BlockNumber, Hash, runtime::Task>>(at)
+		.map_err(|e| runtime_error("Unable to query the task API version.", e))?;
+	if supported.map_or(true, |supported| supported < version) {
+		return Err(JsonRpseeError::Call(CallError::Custom(ErrorObject::owned(
+			ErrorCode::MethodNotFound.code(),
+			"The runtime doesn't support this call yet.",
+			Some(format!("TaskApi version {supported:?}, {version} needed")),
+		))));
+	}
+	Ok(())
 }
 
 #[async_trait]
-impl<C, B> TaskApiServer<AccountId> for Task<C, B>
+impl<C, P, B, S> TaskApiServer<AccountId> for Task<C, P, B, S>
 where
 	C: sp_api::ProvideRuntimeApi<B>,
 	C: HeaderBackend<B>,
 	C: Send + Sync + 'static,
-	C::Api: TaskApi<B, AccountId>,
+	C::Api: TaskApi<B, AccountId, BlockNumber, Hash, runtime::Task>,
+	C::Api: AccountNonceApi<B, AccountId, Index>,
 	B: traits::Block,
+	P: TransactionPool + 'static,
+	S: OffchainStorage + 'static,
 {
 	async fn offchain_nonce_key(&self, account_id: String) -> RpcResult<Vec<u8>> {
 		self.deny_unsafe.check_if_safe()?;
 		let api = self.client.runtime_api();
 		let at = self.client.info().best_hash;
-		let account_id = AccountId::from_str(&account_id).map_err(|e| {
-			JsonRpseeError::Call(CallError::Custom(ErrorObject::owned(
-				ErrorCode::InvalidParams.code(),
-				"Not a valid hex-string or SS58 address.",
-				Some(format!("{e:?}")),
-			)))
-		})?;
+		let account_id = parse_account(&account_id)?;
 
 		api.offchain_nonce_key(at, &account_id).map_err(|e| {
 			JsonRpseeError::Call(CallError::Custom(ErrorObject::owned(
@@ -66,17 +225,100 @@ where
 			)))
 		})
 	}
+
+	async fn pending_tasks(&self) -> RpcResult<Vec<TaskInfo>> {
+		self.deny_unsafe.check_if_safe()?;
+		let api = self.client.runtime_api();
+		let at = self.client.info().best_hash;
+
+		ensure_api_version::<C, B>(&api, at, PENDING_TASKS_API_VERSION)?;
+		let tasks = api
+			.pending_tasks(at)
+			.map_err(|e| runtime_error("Unable to query pending tasks.", e))?;
+		Ok(tasks.into_iter().map(|task| self.task_info(task)).collect())
+	}
+
+	async fn task(&self, id: String) -> RpcResult<Option<TaskInfo>> {
+		self.deny_unsafe.check_if_safe()?;
+		let api = self.client.runtime_api();
+		let at = self.client.info().best_hash;
+		let id = Hash::from_str(&id).map_err(|e| {
+			JsonRpseeError::Call(CallError::Custom(ErrorObject::owned(
+				ErrorCode::InvalidParams.code(),
+				"Not a valid task id.",
+				Some(format!("{e:?}")),
+			)))
+		})?;
+
+		ensure_api_version::<C, B>(&api, at, PENDING_TASKS_API_VERSION)?;
+		let task = api
+			.pending_task(at, id)
+			.map_err(|e| runtime_error("Unable to query task.", e))?;
+		Ok(task.map(|task| self.task_info(task)))
+	}
+
+	async fn resync_nonce(&self, account_id: String) -> RpcResult<Option<Index>> {
+		self.deny_unsafe.check_if_safe()?;
+		let account_id = parse_account(&account_id)?;
+		let mut storage = self.offchain_storage.clone().ok_or_else(|| {
+			JsonRpseeError::Call(CallError::Custom(ErrorObject::owned(
+				ErrorCode::ServerError(Error::StorageError.into()).code(),
+				"Offchain storage is not available.",
+				None::<()>,
+			)))
+		})?;
+
+		let api = self.client.runtime_api();
+		let at = self.client.info().best_hash;
+		let on_chain = api
+			.account_nonce(at, account_id.clone())
+			.map_err(|e| runtime_error("Unable to query account nonce.", e))?;
+		// transactions still ready in the pool will be included, resyncing behind them would
+		// reuse their nonces
+		let ready = self.pool.ready().map(|tx| tx.provides().to_vec());
+		let nonce = next_index(ready, &account_id, on_chain);
+
+		let resynced =
+			crate::resync_offchain_nonce(&mut storage, &account_id, nonce).map_err(|e| {
+				JsonRpseeError::Call(CallError::Custom(ErrorObject::owned(
+					ErrorCode::ServerError(Error::StorageError.into()).code(),
+					"Unable to resync offchain nonce.",
+					Some(e.to_string()),
+				)))
+			})?;
+		if let Some(old) = resynced {
+			log::warn!(target: "task", "Offchain nonce of {account_id} moved back from {old} to {nonce}");
+		}
+		Ok(resynced)
+	}
 }
 
 #[cfg(test)]
 pub mod test {
 	use super::*;
 	use creditcoin_node_runtime::Block;
+	use sc_transaction_pool::{BasicPool, FullChainApi};
+
+	//$ ./node key inspect //Alice
+	const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+	type TestClient = test_client::Client<test_client::Backend>;
+	type TestPool = BasicPool<FullChainApi<TestClient, Block>, Block>;
+
+	fn task_rpc(
+		client: Arc<TestClient>,
+		deny_unsafe: DenyUnsafe,
+	) -> Task<TestClient, TestPool, Block> {
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let pool =
+			BasicPool::new_full(Default::default(), true.into(), None, spawner, client.clone());
+		Task::new(client, pool, deny_unsafe)
+	}
 
 	#[tokio::test]
 	async fn offchain_nonce_key_works() {
 		let client = Arc::new(test_client::new());
-		let t = Task::<_, Block>::new(client, DenyUnsafe::No);
+		let t = task_rpc(client, DenyUnsafe::No);
 		//$ ./node key inspect //Alice
 		t.offchain_nonce_key("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".into())
 			.await
@@ -91,7 +333,7 @@ pub mod test {
 	#[tokio::test]
 	async fn offchain_nonce_key_should_error_when_input_is_not_a_valid_hex_string() {
 		let client = Arc::new(test_client::new());
-		let t = Task::<_, Block>::new(client, DenyUnsafe::No);
+		let t = task_rpc(client, DenyUnsafe::No);
 
 		match t.offchain_nonce_key("0xThisIsNotValid".into()).await {
 			Err(e) => {
@@ -103,4 +345,99 @@ pub mod test {
 			Ok(_) => panic!("This is not expected"),
 		}
 	}
+
+	#[tokio::test]
+	async fn pending_tasks_is_empty_at_genesis() {
+		let client = Arc::new(test_client::new());
+		let t = task_rpc(client, DenyUnsafe::No);
+
+		assert_eq!(t.pending_tasks().await.unwrap(), vec![]);
+	}
+
+	#[tokio::test]
+	async fn task_should_error_when_id_is_not_a_hash() {
+		let client = Arc::new(test_client::new());
+		let t = task_rpc(client, DenyUnsafe::No);
+
+		let e = t.task("0xThisIsNotValid".into()).await.unwrap_err();
+		assert!(e.to_string().contains("Not a valid task id."));
+	}
+
+	#[tokio::test]
+	async fn unknown_task_is_none() {
+		let client = Arc::new(test_client::new());
+		let t = task_rpc(client, DenyUnsafe::No);
+
+		assert_eq!(t.task(format!("{:?}", Hash::repeat_byte(1))).await.unwrap(), None);
+	}
+
+	#[tokio::test]
+	async fn resync_nonce_needs_offchain_storage() {
+		let client = Arc::new(test_client::new());
+		let t = task_rpc(client, DenyUnsafe::No);
+
+		let e = t.resync_nonce(ALICE.into()).await.unwrap_err();
+		assert!(e.to_string().contains("Offchain storage is not available."));
+	}
+
+	#[tokio::test]
+	async fn resync_nonce_moves_it_back_to_the_on_chain_nonce() {
+		let client = Arc::new(test_client::new());
+		let account = AccountId::from_str(ALICE).unwrap();
+		let mut storage = InMemOffchainStorage::default();
+		let key = pallet_offchain_task_scheduler::ocw::nonce_key(&account);
+		storage.set(sp_offchain::STORAGE_PREFIX, &key, &3u32.encode());
+
+		let t =
+			task_rpc(client.clone(), DenyUnsafe::Yes).with_offchain_storage(Some(storage.clone()));
+		assert!(t.resync_nonce(ALICE.into()).await.is_err());
+
+		let t = task_rpc(client, DenyUnsafe::No).with_offchain_storage(Some(storage));
+		assert_eq!(t.resync_nonce(ALICE.into()).await.unwrap(), Some(3));
+	}
+
+	#[test]
+	fn next_index_skips_the_ready_transactions_of_the_account() {
+		let account = AccountId::from_str(ALICE).unwrap();
+		let other = AccountId::new([1; 32]);
+		let ready = vec![
+			vec![(&account, 3 as Index).encode()],
+			vec![(&other, 3 as Index).encode()],
+			vec![(&account, 4 as Index).encode()],
+		];
+
+		assert_eq!(next_index(ready.clone(), &account, 3), 5);
+		assert_eq!(next_index(ready.clone(), &other, 3), 4);
+		assert_eq!(next_index(ready, &account, 6), 6);
+	}
+
+	#[test]
+	fn local_view_reads_lock_and_attempts() {
+		let client = Arc::new(test_client::new());
+		let mut storage = InMemOffchainStorage::default();
+		let id = Hash::repeat_byte(1);
+		storage.set(
+			sp_offchain::STORAGE_PREFIX,
+			&tasks::storage_key(&id, &Some(3 as BlockNumber)),
+			&(7 as BlockNumber, 0u64).encode(),
+		);
+		let attempts = TaskAttempts {
+			attempts: 2,
+			last_attempt: 5 as BlockNumber,
+			last_error: b"Timeout".to_vec(),
+		};
+		storage.set(sp_offchain::STORAGE_PREFIX, &tasks::attempts_key(&id), &attempts.encode());
+
+		let t = task_rpc(client, DenyUnsafe::No).with_offchain_storage(Some(storage));
+
+		assert_eq!(
+			t.local_view(&id, Some(3)),
+			Some(LocalTaskInfo {
+				locked_until: Some(7),
+				attempts: 2,
+				last_attempt: Some(5),
+				last_error: Some("Timeout".into()),
+			})
+		);
+	}
 }
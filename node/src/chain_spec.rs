@@ -1,7 +1,7 @@
 use creditcoin_node_runtime::{
 	pallet_staking_substrate, AccountId, BabeConfig, BalancesConfig, CreditcoinConfig,
 	DifficultyConfig, GenesisConfig, ImOnlineId, Perbill, PosSwitchConfig, SessionConfig,
-	Signature, StakingConfig, SudoConfig, SystemConfig, TaskSchedulerConfig,
+	Signature, StakingConfig, SudoConfig, SystemConfig, TaskSchedulerConfig, TaskSchedulerId,
 	TransactionPaymentConfig, CTC, WASM_BINARY,
 };
 
@@ -48,7 +48,7 @@ where
 	AccountPublic::from(get_from_seed::<TPublic>(seed)).into_account()
 }
 
-type AuthorityKeys = (AccountId, AccountId, GrandpaId, BabeId, ImOnlineId);
+type AuthorityKeys = (AccountId, AccountId, GrandpaId, BabeId, ImOnlineId, TaskSchedulerId);
 
 pub fn get_authority_keys_from_seed(seed: &str) -> AuthorityKeys {
 	(
@@ -57,6 +57,7 @@ pub fn get_authority_keys_from_seed(seed: &str) -> AuthorityKeys {
 		get_from_seed::<GrandpaId>(seed),
 		get_from_seed::<BabeId>(seed),
 		get_from_seed::<ImOnlineId>(seed),
+		get_from_seed::<TaskSchedulerId>(seed),
 	)
 }
 
@@ -64,8 +65,9 @@ fn make_session_keys(
 	grandpa: GrandpaId,
 	babe: BabeId,
 	im_online: ImOnlineId,
+	gots: TaskSchedulerId,
 ) -> creditcoin_node_runtime::SessionKeys {
-	creditcoin_node_runtime::SessionKeys { grandpa, babe, im_online }
+	creditcoin_node_runtime::SessionKeys { grandpa, babe, im_online, gots }
 }
 
 fn chain_properties() -> serde_json::Map<String, serde_json::Value> {
@@ -257,8 +259,8 @@ fn testnet_genesis(
 			keys: initial_authorities
 				.iter()
 				.cloned()
-				.map(|(stash, _acct, grandpa, babe, im_online)| {
-					(stash.clone(), stash, make_session_keys(grandpa, babe, im_online))
+				.map(|(stash, _acct, grandpa, babe, im_online, gots)| {
+					(stash.clone(), stash, make_session_keys(grandpa, babe, im_online, gots))
 				})
 				.collect(),
 		},
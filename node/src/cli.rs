@@ -24,6 +24,18 @@ fn parse_rpc_pair(input: &str) -> Result<(String, String), String> {
 	Ok((name, uri))
 }
 
+fn parse_rpc_quorum(input: &str) -> Result<(String, u32), String> {
+	let (name, quorum) = parse_rpc_pair(input)?;
+	let quorum = quorum.parse().map_err(|e| format!("invalid rpc quorum: {e}"))?;
+	Ok((name, quorum))
+}
+
+fn parse_rpc_chain_id(input: &str) -> Result<(String, u64), String> {
+	let (name, chain_id) = parse_rpc_pair(input)?;
+	let chain_id = chain_id.parse().map_err(|e| format!("invalid rpc chain id: {e}"))?;
+	Ok((name, chain_id))
+}
+
 mod parse_tests {
 	#[test]
 	fn parse_rpc_pair_quoted() {
@@ -56,12 +68,28 @@ pub struct Cli {
 
 	#[clap(long, value_parser(parse_rpc_pair))]
 	/// If the node is an oracle authority, the RPC URL to use for a given external chain.
+	/// Repeat the mapping for a chain to configure fallback endpoints, in order of preference.
 	pub rpc_mapping: Option<Vec<(String, String)>>,
 
+	#[clap(long, value_parser(parse_rpc_quorum))]
+	/// The number of RPC endpoints of a given external chain that have to agree before a
+	/// transfer is verified, e.g. `ethereum=2`. Without it the first reachable endpoint is used.
+	pub rpc_quorum: Option<Vec<(String, u32)>>,
+
+	#[clap(long, value_parser(parse_rpc_chain_id))]
+	/// The chain ID the RPC endpoints of a given external chain have to report, e.g.
+	/// `luniverse=1234`. Overrides the built-in chain ID, chains without either aren't checked.
+	pub rpc_chain_id: Option<Vec<(String, u64)>>,
+
 	#[clap(long)]
 	/// An authority account ID to monitor the nonce of (must be an account actively running as an authority on this node), or
 	/// `auto` to find the authority account automatically.
 	pub monitor_nonce: Option<NonceMonitorTarget>,
+
+	#[clap(long, requires = "monitor_nonce")]
+	/// Reset the monitored authority's offchain nonce to its next index once it has been ahead
+	/// of it for this many blocks, without the authority's transactions being included.
+	pub nonce_resync_after: Option<u32>,
 }
 
 #[derive(Debug, Clone)]
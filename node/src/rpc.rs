@@ -32,7 +32,7 @@ pub struct GrandpaDeps<B> {
 }
 
 /// Full client dependencies.
-pub struct FullDeps<C, P, B> {
+pub struct FullDeps<C, P, B, S> {
 	/// The client instance to use.
 	pub client: Arc<C>,
 	/// Transaction pool instance.
@@ -41,14 +41,16 @@ pub struct FullDeps<C, P, B> {
 	pub deny_unsafe: DenyUnsafe,
 	/// Metrics about mining.
 	pub mining_metrics: primitives::metrics::MiningMetrics,
+	/// The node's offchain storage, if offchain workers are enabled.
+	pub offchain_storage: Option<S>,
 
 	/// GRANDPA specific dependencies.
 	pub grandpa: GrandpaDeps<B>,
 }
 
 /// Instantiate all full RPC extensions.
-pub fn create_full<C, P, B>(
-	deps: FullDeps<C, P, B>,
+pub fn create_full<C, P, B, S>(
+	deps: FullDeps<C, P, B, S>,
 ) -> Result<RpcModule<()>, Box<dyn std::error::Error + Send + Sync>>
 where
 	C: ProvideRuntimeApi<Block>,
@@ -57,10 +59,17 @@ where
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: BlockBuilder<Block>,
-	C::Api: task_scheduler_runtime_api::TaskApi<Block, AccountId>,
+	C::Api: task_scheduler_runtime_api::TaskApi<
+		Block,
+		AccountId,
+		BlockNumber,
+		Hash,
+		creditcoin_node_runtime::Task,
+	>,
 	P: TransactionPool + 'static,
 	B: sc_client_api::Backend<Block> + Send + Sync + 'static,
 	B::State: sc_client_api::StateBackend<sp_runtime::traits::HashFor<Block>>,
+	S: sp_core::offchain::OffchainStorage + 'static,
 {
 	use creditcoin_node_rpc::{Creditcoin, CreditcoinApiServer, Task, TaskApiServer};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
@@ -72,6 +81,7 @@ where
 		pool,
 		deny_unsafe,
 		mining_metrics,
+		offchain_storage,
 		grandpa:
 			GrandpaDeps {
 				finality_provider,
@@ -82,10 +92,14 @@ where
 			},
 	} = deps;
 
-	module.merge(System::new(client.clone(), pool, deny_unsafe).into_rpc())?;
+	module.merge(System::new(client.clone(), pool.clone(), deny_unsafe).into_rpc())?;
 	module.merge(TransactionPayment::new(client.clone()).into_rpc())?;
 	module.merge(Creditcoin::new(mining_metrics).into_rpc())?;
-	module.merge(Task::new(client, deny_unsafe).into_rpc())?;
+	module.merge(
+		Task::new(client, pool, deny_unsafe)
+			.with_offchain_storage(offchain_storage)
+			.into_rpc(),
+	)?;
 	module.merge(
 		Grandpa::new(
 			subscription_executor,
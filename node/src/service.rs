@@ -2,6 +2,7 @@
 
 mod consensus_switcher;
 mod nonce_monitor;
+mod rpc_chain_id;
 
 use crate::cli::Cli;
 use babe::BabeVerifier;
@@ -34,7 +35,7 @@ use sp_runtime::{
 	OpaqueExtrinsic,
 	{offchain::DbExternalities, traits::IdentifyAccount},
 };
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 use tokio::sync::Notify;
 
 use self::consensus_switcher::{
@@ -46,7 +47,8 @@ use self::consensus_switcher::{
 pub struct ExecutorDispatch;
 
 impl sc_executor::NativeExecutionDispatch for ExecutorDispatch {
-	type ExtendHostFunctions = frame_benchmarking::benchmarking::HostFunctions;
+	type ExtendHostFunctions =
+		(frame_benchmarking::benchmarking::HostFunctions, primitives::vrf::HostFunctions);
 
 	fn dispatch(method: &str, data: &[u8]) -> Option<Vec<u8>> {
 		creditcoin_node_runtime::api::dispatch(method, data)
@@ -289,7 +291,14 @@ pub fn decode_mining_key(
 /// Builds a new service for a full client.
 pub fn new_full(mut config: Configuration, cli: Cli) -> Result<TaskManager, ServiceError> {
 	let Cli {
-		rpc_mapping, mining_key, mining_threads, monitor_nonce: monitor_nonce_account, ..
+		rpc_mapping,
+		rpc_quorum,
+		rpc_chain_id,
+		mining_key,
+		mining_threads,
+		monitor_nonce: monitor_nonce_account,
+		nonce_resync_after,
+		..
 	} = cli;
 
 	let sc_service::PartialComponents {
@@ -354,15 +363,63 @@ pub fn new_full(mut config: Configuration, cli: Cli) -> Result<TaskManager, Serv
 		if let Some(mapping) = rpc_mapping {
 			let storage = backend.offchain_storage().unwrap();
 			let mut offchain_db = sc_offchain::OffchainDb::new(storage);
-			for (chain, uri) in mapping {
-				let mut key = Vec::from(chain.as_bytes());
-				key.extend("-rpc-uri".bytes());
+			let mut uris: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+			for (chain, uri) in &mapping {
+				uris.entry(chain).or_default().push(uri);
+			}
+			let quorums: BTreeMap<&str, u32> = rpc_quorum
+				.iter()
+				.flatten()
+				.map(|(chain, quorum)| (chain.as_str(), *quorum))
+				.collect();
+			let chain_ids: BTreeMap<String, u64> = rpc_chain_id.into_iter().flatten().collect();
+			for (chain, uris) in uris {
+				let key = |suffix: &str| [chain.as_bytes(), suffix.as_bytes()].concat();
 				offchain_db.local_storage_set(
 					sp_core::offchain::StorageKind::PERSISTENT,
-					&key,
-					&uri.encode(),
+					&key("-rpc-uri"),
+					&uris[0].encode(),
 				);
+				offchain_db.local_storage_set(
+					sp_core::offchain::StorageKind::PERSISTENT,
+					&key("-rpc-uris"),
+					&uris.encode(),
+				);
+				if let Some(quorum) = quorums.get(chain).filter(|&&q| q as usize > uris.len()) {
+					log::error!(
+						"--rpc-quorum {chain}={quorum} exceeds the {} RPC endpoints configured for \
+						 it, offchain workers will require all of them to agree",
+						uris.len()
+					);
+				}
+				match quorums.get(chain) {
+					Some(quorum) => offchain_db.local_storage_set(
+						sp_core::offchain::StorageKind::PERSISTENT,
+						&key("-rpc-quorum"),
+						&quorum.encode(),
+					),
+					None => offchain_db.local_storage_clear(
+						sp_core::offchain::StorageKind::PERSISTENT,
+						&key("-rpc-quorum"),
+					),
+				}
+				match chain_ids.get(chain) {
+					Some(chain_id) => offchain_db.local_storage_set(
+						sp_core::offchain::StorageKind::PERSISTENT,
+						&key("-rpc-chain-id"),
+						&chain_id.encode(),
+					),
+					None => offchain_db.local_storage_clear(
+						sp_core::offchain::StorageKind::PERSISTENT,
+						&key("-rpc-chain-id"),
+					),
+				}
 			}
+			task_manager.spawn_handle().spawn(
+				"rpc-chain-id-check",
+				None,
+				rpc_chain_id::check_chain_ids(mapping, chain_ids),
+			);
 		}
 	}
 
@@ -384,6 +441,7 @@ pub fn new_full(mut config: Configuration, cli: Cli) -> Result<TaskManager, Serv
 		let pool = transaction_pool.clone();
 
 		let mining_metrics = mining_metrics.clone();
+		let offchain_storage = backend.offchain_storage();
 
 		let justification_stream = grandpa_link.justification_stream();
 		let shared_voter_state = shared_voter_state.clone();
@@ -394,6 +452,7 @@ pub fn new_full(mut config: Configuration, cli: Cli) -> Result<TaskManager, Serv
 				pool: pool.clone(),
 				deny_unsafe,
 				mining_metrics: mining_metrics.clone(),
+				offchain_storage: offchain_storage.clone(),
 				grandpa: crate::rpc::GrandpaDeps {
 					justification_stream: justification_stream.clone(),
 					shared_voter_state: shared_voter_state.clone(),
@@ -429,8 +488,10 @@ pub fn new_full(mut config: Configuration, cli: Cli) -> Result<TaskManager, Serv
 				nonce_monitor::task(nonce_monitor::TaskArgs {
 					registry,
 					monitor_target,
+					resync_after: nonce_resync_after,
 					handlers: rpc_handlers,
 					backend,
+					pool: transaction_pool.clone(),
 					keystore: keystore_container.keystore(),
 				})
 			});
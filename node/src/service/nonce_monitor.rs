@@ -1,12 +1,14 @@
 use std::{convert::TryInto, time::Duration};
 
-use creditcoin_node_runtime::AccountId;
+use creditcoin_node_runtime::{AccountId, BlockNumber, Index};
 use futures::join;
 use jsonrpsee::types::Response as RpcResponse;
-use parity_scale_codec::Decode;
+use parity_scale_codec::{Decode, DecodeAll};
 use sc_client_api::Backend;
 use sc_service::{Arc, RpcHandlers};
+use sc_transaction_pool_api::{InPoolTransaction, TransactionPool, TransactionTag};
 use serde::de::DeserializeOwned;
+use sp_blockchain::HeaderBackend;
 use sp_keystore::CryptoStore;
 use sp_runtime::{
 	app_crypto::Ss58Codec, offchain::OffchainStorage, traits::IdentifyAccount, MultiSigner,
@@ -16,7 +18,7 @@ use thiserror::Error;
 
 use crate::cli::NonceMonitorTarget;
 
-use super::FullBackend;
+use super::{FullBackend, FullPool};
 
 #[derive(Debug, Error)]
 #[error("{0}")]
@@ -123,6 +125,8 @@ type Keystore = Arc<dyn CryptoStore>;
 async fn get_authority_account(
 	target: NonceMonitorTarget,
 	keystore: &Keystore,
+	handlers: &RpcHandlers,
+	backend: &FullBackend,
 ) -> Result<Option<AccountId>, Error> {
 	Ok(match target {
 		NonceMonitorTarget::Auto => {
@@ -130,38 +134,121 @@ async fn get_authority_account(
 				.keys(sp_runtime::KeyTypeId(*b"gots"))
 				.await
 				.map_err(|e| Error::KeyStore(e.to_string()))?;
-			keys.into_iter()
-				.next()
+			let accounts = keys
+				.into_iter()
 				.map(|key| {
-					Ok::<_, Error>(MultiSigner::Sr25519(sp_core::sr25519::Public::from_raw(
-						key.1.try_into().map_err(|e| {
-							Error::Signer(format!(
-								"Invalid authority account from public key: {}",
-								hex::encode(e)
-							))
-						})?,
-					)))
+					Ok::<_, Error>(
+						MultiSigner::Sr25519(sp_core::sr25519::Public::from_raw(
+							key.1.try_into().map_err(|e| {
+								Error::Signer(format!(
+									"Invalid authority account from public key: {}",
+									hex::encode(e)
+								))
+							})?,
+						))
+						.into_account(),
+					)
 				})
-				.transpose()?
-				.map(|signer| signer.into_account())
+				.collect::<Result<Vec<_>, _>>()?;
+
+			// after a key rotation the keystore holds both keys, follow the one with a nonce
+			for acct in &accounts {
+				let key = get_off_chain_nonce_key(handlers, acct).await?;
+				if get_off_chain_nonce(backend, &key).await?.is_some() {
+					return Ok(Some(acct.clone()));
+				}
+			}
+			accounts.into_iter().next()
 		},
 
 		NonceMonitorTarget::Account(acct) => Some(acct),
 	})
 }
 
+/// The account's transactions in the pool, by whether they can be included.
+#[derive(Clone, Copy, Default)]
+struct PoolTransactions {
+	ready: usize,
+	future: usize,
+}
+
+impl PoolTransactions {
+	fn of(pool: &FullPool, acct: &AccountId) -> Self {
+		// the nonce check provides the account's tag for every nonce it uses
+		let is_from = |tags: &[TransactionTag]| {
+			tags.iter().any(|tag| {
+				<(AccountId, Index)>::decode_all(&mut &tag[..])
+					.map_or(false, |(who, _)| &who == acct)
+			})
+		};
+		PoolTransactions {
+			ready: pool.ready().filter(|tx| is_from(tx.provides())).count(),
+			future: pool.futures().iter().filter(|tx| is_from(tx.provides())).count(),
+		}
+	}
+}
+
+/// The offchain nonce being ahead of the next index, which only stays that way if the pool
+/// dropped a transaction and the following ones can't be included.
+struct Gap {
+	account: AccountId,
+	next_index: u64,
+	since: BlockNumber,
+}
+
+impl Gap {
+	/// Whether the gap has been there for `blocks` blocks without the account's transactions
+	/// moving forward, updating it with the latest nonces otherwise. While the account has
+	/// transactions in the pool that can be included the gap may still close, it is only stuck
+	/// once all that's left are transactions waiting for a missing nonce, or none at all.
+	fn is_stuck(
+		gap: &mut Option<Gap>,
+		account: &AccountId,
+		(on, off): (u64, Option<u64>),
+		pool: PoolTransactions,
+		best: BlockNumber,
+		blocks: BlockNumber,
+	) -> bool {
+		if off.map_or(true, |off| off <= on) {
+			*gap = None;
+			return false;
+		}
+		match gap {
+			Some(gap) if &gap.account == account && gap.next_index == on && pool.ready == 0 => {
+				best.saturating_sub(gap.since) >= blocks
+			},
+			_ => {
+				*gap = Some(Gap { account: account.clone(), next_index: on, since: best });
+				false
+			},
+		}
+	}
+}
+
+fn resync(backend: &FullBackend, acct: &AccountId, next_index: u64) -> Result<Option<u32>, String> {
+	let mut storage = backend
+		.offchain_storage()
+		.expect("offchain storage must be accessible in a creditcoin node; qed");
+	let next_index = next_index.try_into().map_err(|_| format!("invalid nonce {next_index}"))?;
+	creditcoin_node_rpc::resync_offchain_nonce(&mut storage, acct, next_index)
+		.map_err(|e| e.to_string())
+}
+
 const POLL_INTERVAL: Duration = Duration::from_secs(30);
 
 pub(super) struct TaskArgs {
 	pub(super) registry: Registry,
 	pub(super) monitor_target: NonceMonitorTarget,
+	/// Resets a stuck offchain nonce after this many blocks, if set.
+	pub(super) resync_after: Option<BlockNumber>,
 	pub(super) handlers: RpcHandlers,
 	pub(super) backend: Arc<FullBackend>,
+	pub(super) pool: Arc<FullPool>,
 	pub(super) keystore: Keystore,
 }
 
 pub(super) async fn task(
-	TaskArgs { registry, monitor_target, handlers, backend, keystore }: TaskArgs,
+	TaskArgs { registry, monitor_target, resync_after, handlers, backend, pool, keystore }: TaskArgs,
 ) {
 	let offchain_gauge = register_u64_gauge(
 		&registry,
@@ -173,31 +260,39 @@ pub(super) async fn task(
 		"authority_onchain_nonce",
 		"the nonce for the authority in onchain storage",
 	);
+	let resync_counter = register_u64_gauge(
+		&registry,
+		"authority_offchain_nonce_resyncs",
+		"the number of times the offchain nonce of the authority was reset to its next index",
+	);
+	let mut gap = None;
 
-	let nonce_account =
-		loop {
-			match get_authority_account(monitor_target.clone(), &keystore).await {
-				Ok(Some(acct)) => break acct,
+	loop {
+		// looked up every time so the monitor follows the authority when its key is rotated
+		let nonce_account =
+			match get_authority_account(monitor_target.clone(), &keystore, &handlers, &backend)
+				.await
+			{
+				Ok(Some(acct)) => acct,
 				Ok(None) => {
 					log::info!("No authority account found");
-					tokio::time::sleep(POLL_INTERVAL * 2).await
+					tokio::time::sleep(POLL_INTERVAL * 2).await;
+					continue;
 				},
 				Err(e) => {
 					log::error!("Encountered error when trying to get authority account for monitoring: {e}");
 					return;
 				},
-			}
-		};
+			};
 
-	let key = match get_off_chain_nonce_key(&handlers, &nonce_account).await {
-		Ok(key) => key,
-		Err(e) => {
-			log::error!("Failed to get key for the offchain nonce of {nonce_account}: {e}");
-			return;
-		},
-	};
+		let key = match get_off_chain_nonce_key(&handlers, &nonce_account).await {
+			Ok(key) => key,
+			Err(e) => {
+				log::error!("Failed to get key for the offchain nonce of {nonce_account}: {e}");
+				return;
+			},
+		};
 
-	loop {
 		let (onchain, offchain) = join!(
 			get_on_chain_nonce(&handlers, &nonce_account),
 			get_off_chain_nonce(&backend, &key)
@@ -208,6 +303,26 @@ pub(super) async fn task(
 				log::info!("Onchain: {}, offchain: {:?}", on, off);
 				offchain_gauge.set(off.unwrap_or(on));
 				onchain_gauge.set(on);
+
+				let best = backend.blockchain().info().best_number;
+				let in_pool = PoolTransactions::of(&pool, &nonce_account);
+				let stuck = resync_after.map_or(false, |blocks| {
+					Gap::is_stuck(&mut gap, &nonce_account, (on, off), in_pool, best, blocks)
+				});
+				if stuck {
+					match resync(&backend, &nonce_account, on) {
+						Ok(Some(old)) => {
+							log::warn!(
+								"Offchain nonce of {nonce_account} was stuck at {old} with {} transactions waiting in the pool, reset it to {on}",
+								in_pool.future
+							);
+							resync_counter.inc();
+							gap = None;
+						},
+						Ok(None) => gap = None,
+						Err(e) => log::error!("Failed to resync the offchain nonce: {e}"),
+					}
+				}
 			},
 			(Err(e), Err(e2)) => {
 				log::error!("Errors during nonce monitoring: {e} ; {e2}");
@@ -219,3 +334,43 @@ pub(super) async fn task(
 		tokio::time::sleep(POLL_INTERVAL).await;
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{Gap, PoolTransactions};
+	use creditcoin_node_runtime::AccountId;
+
+	#[test]
+	fn gap_is_stuck_when_the_next_index_does_not_move() {
+		let account = AccountId::new([1; 32]);
+		let waiting = PoolTransactions { ready: 0, future: 1 };
+		let mut gap = None;
+
+		assert!(!Gap::is_stuck(&mut gap, &account, (5, Some(7)), waiting, 10, 3));
+		assert!(!Gap::is_stuck(&mut gap, &account, (5, Some(7)), waiting, 12, 3));
+		assert!(Gap::is_stuck(&mut gap, &account, (5, Some(7)), waiting, 13, 3));
+
+		// the pool made progress, start over
+		assert!(!Gap::is_stuck(&mut gap, &account, (6, Some(7)), waiting, 14, 3));
+		assert!(!Gap::is_stuck(&mut gap, &account, (6, Some(7)), waiting, 16, 3));
+		assert!(Gap::is_stuck(&mut gap, &account, (6, Some(7)), waiting, 17, 3));
+
+		assert!(!Gap::is_stuck(&mut gap, &account, (7, Some(7)), waiting, 30, 3));
+		assert!(gap.is_none());
+		assert!(!Gap::is_stuck(&mut gap, &account, (7, None), waiting, 30, 3));
+	}
+
+	#[test]
+	fn gap_is_not_stuck_while_the_account_has_ready_transactions() {
+		let account = AccountId::new([1; 32]);
+		let ready = PoolTransactions { ready: 1, future: 1 };
+		let mut gap = None;
+
+		assert!(!Gap::is_stuck(&mut gap, &account, (5, Some(7)), ready, 10, 3));
+		assert!(!Gap::is_stuck(&mut gap, &account, (5, Some(7)), ready, 13, 3));
+		// the pool has taken what it could, the remaining transactions wait for a nonce
+		let waiting = PoolTransactions { ready: 0, future: 1 };
+		assert!(!Gap::is_stuck(&mut gap, &account, (5, Some(7)), waiting, 15, 3));
+		assert!(Gap::is_stuck(&mut gap, &account, (5, Some(7)), waiting, 16, 3));
+	}
+}
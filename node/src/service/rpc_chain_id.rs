@@ -0,0 +1,88 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use creditcoin_node_runtime::Blockchain;
+use hyper::{body::Buf, Body, Client, Method, Request};
+use serde::Deserialize;
+use thiserror::Error;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Error)]
+enum Error {
+	#[error("request failed: {0}")]
+	Http(#[from] hyper::Error),
+	#[error("invalid request: {0}")]
+	Request(#[from] hyper::http::Error),
+	#[error("invalid response: {0}")]
+	Serde(#[from] serde_json::Error),
+	#[error("invalid chain id {0:?}")]
+	ChainId(String),
+	#[error("request timed out")]
+	Timeout,
+}
+
+#[derive(Deserialize)]
+struct ChainIdResponse {
+	result: String,
+}
+
+fn known_blockchain(name: &str) -> Option<Blockchain> {
+	[Blockchain::Ethereum, Blockchain::Rinkeby, Blockchain::Luniverse, Blockchain::Bitcoin]
+		.into_iter()
+		.find(|chain| chain.as_bytes() == name.as_bytes())
+}
+
+async fn fetch_chain_id(uri: &str) -> Result<u64, Error> {
+	let connector = hyper_rustls::HttpsConnectorBuilder::new()
+		.with_native_roots()
+		.https_or_http()
+		.enable_http1()
+		.build();
+	let client = Client::builder().build::<_, Body>(connector);
+
+	let request = Request::builder()
+		.method(Method::POST)
+		.uri(uri)
+		.header("Content-Type", "application/json")
+		.body(Body::from(r#"{"jsonrpc":"2.0","method":"eth_chainId","params":[],"id":1}"#))?;
+
+	let response = tokio::time::timeout(TIMEOUT, async {
+		let response = client.request(request).await?;
+		hyper::body::aggregate(response).await
+	})
+	.await
+	.map_err(|_| Error::Timeout)??;
+
+	let ChainIdResponse { result } = serde_json::from_reader(response.reader())?;
+	let hex = result.trim_start_matches("0x");
+	u64::from_str_radix(hex, 16).map_err(|_| Error::ChainId(result.clone()))
+}
+
+/// Warns about every endpoint in the `--rpc-mapping` that reports a different chain ID than the
+/// chain it was configured for. The offchain workers refuse to use such endpoints, so this only
+/// makes the misconfiguration visible at startup. URIs aren't logged since they often embed API keys.
+/// `chain_ids` are the `--rpc-chain-id` overrides of the built-in chain IDs.
+pub async fn check_chain_ids(mapping: Vec<(String, String)>, chain_ids: BTreeMap<String, u64>) {
+	for (name, uri) in mapping {
+		let Some(expected) = chain_ids
+			.get(&name)
+			.copied()
+			.or_else(|| known_blockchain(&name).and_then(|chain| chain.expected_chain_id()))
+		else {
+			continue;
+		};
+
+		match fetch_chain_id(&uri).await {
+			Ok(chain_id) if chain_id == expected => {
+				log::debug!("RPC endpoint for {name} reports the expected chain id {chain_id}");
+			},
+			Ok(chain_id) => log::warn!(
+				"RPC endpoint for {name} reports chain id {chain_id} but {expected} was expected; \
+				 offchain workers will not use it"
+			),
+			Err(e) => {
+				log::warn!("Failed to check the chain id of the RPC endpoint for {name}: {e}")
+			},
+		}
+	}
+}
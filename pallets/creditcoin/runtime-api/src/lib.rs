@@ -0,0 +1,10 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_scale_codec::Codec;
+
+sp_api::decl_runtime_apis! {
+	pub trait CreditcoinApi<Hash: Codec, FailedTransfer: Codec> {
+		/// Why the verification of a transfer failed, while it's kept on chain.
+		fn failed_transfer(id: Hash) -> Option<FailedTransfer>;
+	}
+}
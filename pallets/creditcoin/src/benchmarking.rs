@@ -1,15 +1,16 @@
 #![cfg(feature = "runtime-benchmarks")]
 use super::*;
 use crate::benchmarking::alloc::format;
-use crate::helpers::{extensions::IntoBounded, EVMAddress, PublicToAddress};
+use crate::helpers::{eth_proof, extensions::IntoBounded, EVMAddress, PublicToAddress};
 use crate::migrations::Migrate;
 use crate::test_utils::{
 	fake_address_id, fake_ask_id, fake_bid_id, fake_loan_terms, fake_offer_id, insert_fake_ask,
 	insert_fake_bid, insert_fake_offer,
 };
-use crate::types::{Blockchain, OwnershipProof};
+use crate::types::{Blockchain, Id, OwnershipProof};
 use crate::Pallet as Creditcoin;
 use crate::{AskOrderId, LoanTerms};
+use ethereum_types::BigEndianHash;
 use frame_benchmarking::{account, benchmarks, whitelist_account, Zero};
 use frame_support::{pallet_prelude::*, traits::Currency};
 use frame_system::pallet_prelude::*;
@@ -17,9 +18,10 @@ use frame_system::Config as SystemConfig;
 use frame_system::Pallet as System;
 use frame_system::RawOrigin;
 use pallet_balances::Pallet as Balances;
+use pallet_offchain_task_scheduler::tasks::TaskScheduler as _;
 use pallet_timestamp::Config as TimestampConfig;
 use pallet_timestamp::Pallet as Timestamp;
-use sp_core::ecdsa;
+use sp_core::{ecdsa, H160};
 use sp_io::crypto::{ecdsa_generate, ecdsa_sign};
 use sp_runtime::traits::IdentifyAccount;
 use sp_runtime::traits::One;
@@ -183,20 +185,35 @@ benchmarks! {
 		let deal_id = generate_deal::<T>(true,0u8).unwrap();
 		let deadline = T::BlockNumber::one();
 		// pending task does not matter
-		let transfer = generate_transfer::<T>(deal_id,false,false,0u8);
-		let task_output = crate::TaskOutput::from(transfer);
-	}: persist_task_output(RawOrigin::Signed(authority), deadline, task_output)
+		let (transfer_id, mut transfer) = generate_transfer::<T>(deal_id,false,false,0u8);
+		// with evidence, so that its re-verification is scheduled
+		transfer.evidence = Some(crate::TransferEvidence {
+			block_number: 1,
+			block_hash: Default::default(),
+			transaction_index: 0,
+			log_index: None,
+		});
+		let task_output = crate::TaskOutput::from((transfer_id, transfer));
+	}: persist_task_output(RawOrigin::Signed(authority), deadline, task_output, None)
 
 	fail_transfer {
 		<Timestamp<T>>::set_timestamp(1u32.into());
 		let authority = authority_account::<T>(true);
 		<Creditcoin<T>>::add_authority(RawOrigin::Root.into(), authority.clone()).unwrap();
+		let lender: T::AccountId = lender_account::<T>(true);
 		let deal_id = generate_deal::<T>(true,0u8).unwrap();
-		let (transfer_id, _)= generate_transfer::<T>(deal_id,false,true,0u8);
+		let (transfer_id, transfer) = generate_transfer::<T>(deal_id.clone(),false,false,0u8);
+		let deadline = T::TaskScheduler::deadline();
+		<Creditcoin<T>>::register_funding_transfer(
+			RawOrigin::Signed(lender).into(),
+			transfer.kind,
+			deal_id,
+			transfer.tx_id,
+		)
+		.unwrap();
 		let cause = crate::ocw::VerificationFailureCause::TaskFailed;
-		let deadline = T::BlockNumber::one();
 		let task_id = crate::TaskId::from(transfer_id);
-	}: fail_task(RawOrigin::Signed(authority), deadline, task_id, cause)
+	}: fail_task(RawOrigin::Signed(authority), deadline, task_id, cause, None)
 
 	fund_deal_order {
 		<Timestamp<T>>::set_timestamp(1u32.into());
@@ -285,6 +302,96 @@ benchmarks! {
 		let signature = ecdsa_sign(ktypeid, &pkey, &message).expect("ecdsa signature");
 		let proof = OwnershipProof::EthSign(signature);
 	}: _(RawOrigin::Signed(who), Blockchain::Ethereum, address, proof)
+
+	register_contract_address {
+		let who: T::AccountId = lender_account::<T>(false);
+		let pkey = ecdsa_generate(KeyTypeId(*b"dumy"), Some("//contract".as_bytes().to_vec()));
+		let address = EVMAddress::from_public(&pkey);
+		let proof = OwnershipProof::Erc1271([7u8; 65].to_vec().try_into().unwrap());
+	}: register_address_v2(RawOrigin::Signed(who), Blockchain::Ethereum, address, proof)
+
+	set_legacy_signing_deadline {
+		let root = RawOrigin::Root;
+		let deadline: Option<T::BlockNumber> = Some(100u32.into());
+	}: _(root, deadline)
+
+	set_attestation_threshold {
+		let root = RawOrigin::Root;
+	}: _(root, 2)
+
+	bond_authority {
+		let authority = authority_account::<T>(true);
+		let bond = funded_bond::<T>(&authority);
+	}: _(RawOrigin::Signed(authority), bond)
+
+	unbond_authority {
+		let authority = authority_account::<T>(true);
+		let bond = funded_bond::<T>(&authority);
+		<Creditcoin<T>>::bond_authority(RawOrigin::Signed(authority.clone()).into(), bond).unwrap();
+	}: _(RawOrigin::Signed(authority))
+
+	slash_authority {
+		let authority = authority_account::<T>(true);
+		let bond = funded_bond::<T>(&authority);
+		<Creditcoin<T>>::bond_authority(RawOrigin::Signed(authority.clone()).into(), bond).unwrap();
+		let task_id = T::Hash::default();
+	}: _(RawOrigin::Root, authority, task_id, bond)
+
+	rotate_authority {
+		let root = RawOrigin::Root;
+		let old = authority_account::<T>(false);
+		let new = lender_account::<T>(false);
+		<Creditcoin<T>>::add_authority(root.clone().into(), old.clone()).unwrap();
+		let bond = funded_bond::<T>(&old);
+		<Creditcoin<T>>::bond_authority(RawOrigin::Signed(old.clone()).into(), bond).unwrap();
+		funded_bond::<T>(&new);
+	}: _(root, old, new, None)
+
+	cancel_transfer {
+		<Timestamp<T>>::set_timestamp(1u32.into());
+		let lender: T::AccountId = lender_account::<T>(true);
+		let deal_id = generate_deal::<T>(true,0u8).unwrap();
+		let (transfer_id,transfer) = generate_transfer::<T>(deal_id.clone(),false,false,0u8);
+		<Creditcoin<T>>::register_funding_transfer(
+			RawOrigin::Signed(lender.clone()).into(),
+			transfer.kind,
+			deal_id,
+			transfer.tx_id,
+		)
+		.unwrap();
+	}: _(RawOrigin::Signed(lender),transfer_id)
+
+	request_header_attestation {
+		let who: T::AccountId = lender_account::<T>(true);
+	}: _(RawOrigin::Signed(who), Blockchain::Ethereum, 42)
+
+	verify_transfer_proof {
+		<Timestamp<T>>::set_timestamp(1u32.into());
+		let lender: T::AccountId = lender_account::<T>(true);
+		let deal_id = generate_deal::<T>(true,0u8).unwrap();
+		let order = try_get_id!(DealOrders<T>, &deal_id, NonExistentDealOrder).unwrap();
+		let from = Creditcoin::<T>::addresses(&order.lender_address_id).unwrap().value;
+		let to = Creditcoin::<T>::addresses(&order.borrower_address_id).unwrap().value;
+		let nonce = T::HashIntoNonce::from(deal_id.hash()).into_uint();
+		let input = eth_proof::test_utils::ethless_input(
+			H160::from_slice(&from),
+			H160::from_slice(&to),
+			order.terms.amount,
+			nonce,
+		);
+		let contract = H160::repeat_byte(0xaa);
+		let (proof, header_hash, tx_hash) =
+			eth_proof::test_utils::transfer_proof(1, 1, contract, &input);
+		<Creditcoin<T>>::register_funding_transfer(
+			RawOrigin::Signed(lender.clone()).into(),
+			TransferKind::Ethless(contract.as_bytes().into_bounded()),
+			deal_id,
+			tx_hash.as_bytes().into_bounded(),
+		)
+		.unwrap();
+		let transfer_id = TransferId::new::<T>(&order.blockchain, tx_hash.as_bytes());
+		AttestedHeaders::<T>::insert(HeaderId::new::<T>(&order.blockchain, 1), header_hash);
+	}: _(RawOrigin::Signed(lender), transfer_id, proof)
 }
 
 fn generate_funded_deal<T: Config>(
@@ -378,6 +485,13 @@ fn generate_locked_deal<T: Config>(
 	Ok((deal_id, transfer_id))
 }
 
+/// Funds `who` and returns a bond it can afford.
+fn funded_bond<T: Config>(who: &T::AccountId) -> BalanceFor<T> {
+	let bond = <Balances<T> as Currency<T::AccountId>>::minimum_balance() * 10u32.into();
+	<Balances<T> as Currency<T::AccountId>>::make_free_balance_be(who, bond * 10u32.into());
+	bond
+}
+
 fn borrower_account<T: Config>(whitelist: bool) -> T::AccountId {
 	let borrower = account("borrower", 1, 1);
 	if whitelist {
@@ -605,6 +719,7 @@ pub(crate) fn generate_fake_unverified_transfer<T: SystemConfig + TimestampConfi
 			)),
 			tx_id: format!("{seed:03x}").as_bytes().into_bounded(),
 			timestamp: None,
+			evidence: None,
 		},
 	}
 }
@@ -1,9 +1,18 @@
+pub(crate) mod eth_proof;
 mod external_address;
+mod failed_transfer;
+mod register_contract_address;
 mod register_transfer;
+mod reverify_transfer;
+pub mod signing;
+mod task_outputs;
+mod transfer_proof;
 
 pub use external_address::{address_is_well_formed, generate_external_address};
 #[cfg(any(test, feature = "runtime-benchmarks"))]
 pub use external_address::{EVMAddress, PublicToAddress};
+pub use signing::{verify_signed_payload, SigningDomain};
+pub use task_outputs::TaskOutputCalls;
 
 use crate::{
 	pallet::*,
@@ -12,6 +21,7 @@ use crate::{
 };
 use frame_support::ensure;
 use frame_system::pallet_prelude::*;
+use pallet_offchain_task_scheduler::incentives::AuthorityIncentives;
 use sp_std::prelude::*;
 
 #[allow(unused_macros)]
@@ -102,6 +112,13 @@ impl<T: Config> Pallet<T> {
 		UsedGuids::<T>::insert(guid, ());
 		Ok(())
 	}
+
+	/// Pays the task reward to the authorities whose votes completed a task.
+	pub(crate) fn reward_agreeing(agreeing: &[T::AccountId]) {
+		for authority in agreeing {
+			T::TaskScheduler::reward(authority);
+		}
+	}
 }
 
 pub fn non_paying_error<T: Config>(
@@ -152,33 +169,36 @@ pub mod extensions {
 
 use sp_io::crypto::secp256k1_ecdsa_recover_compressed;
 
-/// Try to extract an external address for a particular blockchain through a signature and an account id which acts as a message.
+/// Try to extract an external address for a particular blockchain through a signature and a signed payload which acts as a message.
+/// The payload is either the encoded account id (legacy) or its domain-separated form, see [`signing::domain_separated_payload`].
 /// This function supports the older and insecure EthSign signing method and the new PersonalSign standard that is supported by Metamask.
 pub fn try_extract_address<T: Config>(
 	ownership_proof: OwnershipProof,
-	account_id: &[u8],
+	payload: &[u8],
 	blockchain: &Blockchain,
 	address: &ExternalAddress,
 ) -> Result<ExternalAddress, crate::Error<T>> {
 	match ownership_proof {
 		// Old insecure signing method
 		OwnershipProof::EthSign(signature) => {
-			extract_public_key_eth_sign(signature.into(), account_id, blockchain, address)
+			extract_public_key_eth_sign(signature.into(), payload, blockchain, address)
 		},
 		// New Way
 		OwnershipProof::PersonalSign(signature) => {
-			extract_public_key_personal_sign(signature.into(), account_id, blockchain, address)
+			extract_public_key_personal_sign(signature.into(), payload, blockchain, address)
 		},
+		// Nothing to recover, the contract has to be asked offchain
+		OwnershipProof::Erc1271(_) => Err(Error::OwnershipNotSatisfied),
 	}
 }
 
 fn extract_public_key_eth_sign<T: Config>(
 	signature: [u8; 65],
-	account_id: &[u8],
+	payload: &[u8],
 	blockchain: &Blockchain,
 	address: &ExternalAddress,
 ) -> Result<ExternalAddress, Error<T>> {
-	let message = sp_io::hashing::sha2_256(account_id);
+	let message = sp_io::hashing::sha2_256(payload);
 	let message = &sp_io::hashing::blake2_256(message.as_ref());
 
 	match secp256k1_ecdsa_recover_compressed(&signature, message) {
@@ -208,11 +228,11 @@ pub fn eth_message(message: &[u8; 32]) -> [u8; 32] {
 
 pub fn extract_public_key_personal_sign<T: Config>(
 	signature: [u8; 65],
-	account_id: &[u8],
+	payload: &[u8],
 	blockchain: &Blockchain,
 	address: &ExternalAddress,
 ) -> Result<ExternalAddress, Error<T>> {
-	let message = sp_io::hashing::blake2_256(account_id);
+	let message = sp_io::hashing::blake2_256(payload);
 	let message = eth_message(&message);
 
 	match secp256k1_ecdsa_recover_compressed(&signature, &message) {
@@ -0,0 +1,447 @@
+//! Decoding of Ethereum block headers, transactions and receipts, and verification of the
+//! Merkle-Patricia trie proofs that tie transactions and receipts to a header.
+
+use ethereum_types::{H160, H256};
+use rlp::Rlp;
+use sp_io::hashing::keccak_256;
+use sp_std::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ProofError {
+	/// The proof ran out of nodes before reaching the value.
+	MissingNode,
+	/// A node doesn't hash to the reference its parent holds for it.
+	HashMismatch,
+	/// A node, header, transaction or receipt isn't valid RLP of the expected shape.
+	Malformed,
+	/// The trie holds no value under the key.
+	Absent,
+	/// The transaction or receipt is of a type that isn't supported.
+	UnsupportedType,
+}
+
+impl From<rlp::DecoderError> for ProofError {
+	fn from(_: rlp::DecoderError) -> Self {
+		ProofError::Malformed
+	}
+}
+
+/// The fields of a block header that proofs are checked against.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct EthHeader {
+	pub hash: H256,
+	pub number: u64,
+	pub transactions_root: H256,
+	pub receipts_root: H256,
+	/// Seconds since the epoch.
+	pub timestamp: u64,
+}
+
+impl EthHeader {
+	pub(crate) fn decode(raw: &[u8]) -> Result<Self, ProofError> {
+		let fields = Rlp::new(raw);
+		if !fields.is_list() || fields.item_count()? < 15 {
+			return Err(ProofError::Malformed);
+		}
+		Ok(Self {
+			hash: H256(keccak_256(raw)),
+			transactions_root: fields.val_at(4)?,
+			receipts_root: fields.val_at(5)?,
+			number: fields.val_at(8)?,
+			timestamp: fields.val_at(11)?,
+		})
+	}
+}
+
+/// The parts of a transaction a transfer is checked against.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct EthCall {
+	/// `None` for contract creations.
+	pub to: Option<H160>,
+	pub input: Vec<u8>,
+}
+
+/// Decodes legacy, EIP-2930 and EIP-1559 transactions, as they are stored in the trie.
+pub(crate) fn decode_transaction(raw: &[u8]) -> Result<EthCall, ProofError> {
+	let (fields, to, input) = match raw.first() {
+		Some(&kind) if kind >= 0xc0 => (Rlp::new(raw), 3, 5),
+		Some(&1) => (Rlp::new(&raw[1..]), 4, 6),
+		Some(&2) => (Rlp::new(&raw[1..]), 5, 7),
+		Some(_) => return Err(ProofError::UnsupportedType),
+		None => return Err(ProofError::Malformed),
+	};
+	let to = fields.at(to)?;
+	let to = if to.is_empty() { None } else { Some(to.as_val()?) };
+	Ok(EthCall { to, input: fields.val_at(input)? })
+}
+
+/// A log of a receipt, as the contract emitted it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ReceiptLog {
+	pub address: H160,
+	/// The event signature followed by the indexed parameters.
+	pub topics: Vec<H256>,
+	pub data: Vec<u8>,
+}
+
+fn receipt_fields(raw: &[u8]) -> Result<Rlp, ProofError> {
+	match raw.first() {
+		Some(&kind) if kind >= 0xc0 => Ok(Rlp::new(raw)),
+		Some(&kind) if kind <= 0x7f => Ok(Rlp::new(&raw[1..])),
+		_ => Err(ProofError::Malformed),
+	}
+}
+
+/// Whether the transaction of the receipt succeeded. Receipts from before Byzantium hold a
+/// state root instead of a status and aren't supported.
+pub(crate) fn receipt_succeeded(raw: &[u8]) -> Result<bool, ProofError> {
+	match receipt_fields(raw)?.at(0)?.data()? {
+		[] => Ok(false),
+		[1] => Ok(true),
+		_ => Err(ProofError::UnsupportedType),
+	}
+}
+
+/// The logs of the receipt, in the order they were emitted.
+pub(crate) fn receipt_logs(raw: &[u8]) -> Result<Vec<ReceiptLog>, ProofError> {
+	receipt_fields(raw)?
+		.at(3)?
+		.iter()
+		.map(|log| {
+			Ok(ReceiptLog {
+				address: log.val_at(0)?,
+				topics: log.list_at(1)?,
+				data: log.val_at(2)?,
+			})
+		})
+		.collect()
+}
+
+/// The key of the transaction or receipt at `index` in its block's tries.
+pub(crate) fn trie_key(index: u64) -> Vec<u8> {
+	rlp::encode(&index).to_vec()
+}
+
+fn nibbles(bytes: &[u8]) -> Vec<u8> {
+	bytes.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect()
+}
+
+/// Decodes the hex-prefix encoded path of a leaf or extension node, and whether it's a leaf.
+fn decode_path(encoded: &[u8]) -> Result<(Vec<u8>, bool), ProofError> {
+	let (&first, rest) = encoded.split_first().ok_or(ProofError::Malformed)?;
+	let flags = first >> 4;
+	if flags > 3 {
+		return Err(ProofError::Malformed);
+	}
+	let mut path = if flags & 1 == 1 { vec![first & 0x0f] } else { Vec::new() };
+	path.extend(nibbles(rest));
+	Ok((path, flags & 2 == 2))
+}
+
+fn next_node<'a, N: AsRef<[u8]>>(
+	nodes: &mut impl Iterator<Item = &'a N>,
+	hash: &H256,
+) -> Result<&'a [u8], ProofError>
+where
+	N: 'a,
+{
+	let node = nodes.next().ok_or(ProofError::MissingNode)?.as_ref();
+	if keccak_256(node) != hash.0 {
+		return Err(ProofError::HashMismatch);
+	}
+	Ok(node)
+}
+
+fn value_of(item: Rlp) -> Result<Vec<u8>, ProofError> {
+	match item.data()? {
+		[] => Err(ProofError::Absent),
+		value => Ok(value.to_vec()),
+	}
+}
+
+/// Walks `proof`, the trie nodes from the root down, to the value stored under `key` in the
+/// trie with the given `root`.
+pub(crate) fn verify_proof<N: AsRef<[u8]>>(
+	root: &H256,
+	key: &[u8],
+	proof: &[N],
+) -> Result<Vec<u8>, ProofError> {
+	let path = nibbles(key);
+	let mut path = path.as_slice();
+	let mut nodes = proof.iter();
+	let mut node = next_node(&mut nodes, root)?;
+
+	loop {
+		let fields = Rlp::new(node);
+		let child = match fields.item_count()? {
+			17 => match path.split_first() {
+				None => return value_of(fields.at(16)?),
+				Some((&nibble, rest)) => {
+					path = rest;
+					fields.at(nibble as usize)?
+				},
+			},
+			2 => {
+				let (node_path, leaf) = decode_path(fields.at(0)?.data()?)?;
+				if leaf {
+					if node_path.as_slice() != path {
+						return Err(ProofError::Absent);
+					}
+					return value_of(fields.at(1)?);
+				}
+				path = path.strip_prefix(node_path.as_slice()).ok_or(ProofError::Absent)?;
+				fields.at(1)?
+			},
+			_ => return Err(ProofError::Malformed),
+		};
+
+		// nodes shorter than a hash are embedded in their parent
+		node = if child.is_list() {
+			child.as_raw()
+		} else {
+			match child.data()? {
+				[] => return Err(ProofError::Absent),
+				hash if hash.len() == 32 => next_node(&mut nodes, &H256::from_slice(hash))?,
+				_ => return Err(ProofError::Malformed),
+			}
+		};
+	}
+}
+
+#[cfg(any(test, feature = "runtime-benchmarks"))]
+pub(crate) mod test_utils {
+	use super::*;
+	use crate::ocw::tasks::verify_transfer::ethless_transfer_function_abi;
+	use crate::TransferProof;
+	use ethabi::Token;
+	use rlp::RlpStream;
+	use sp_core::U256;
+
+	/// A leaf node under `path`, given in nibbles.
+	pub(crate) fn leaf(path: &[u8], value: &[u8]) -> Vec<u8> {
+		let mut encoded = vec![if path.len() % 2 == 1 { 0x30 | path[0] } else { 0x20 }];
+		let rest = if path.len() % 2 == 1 { &path[1..] } else { path };
+		encoded.extend(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+
+		let mut stream = RlpStream::new_list(2);
+		stream.append(&encoded).append(value);
+		stream.out().to_vec()
+	}
+
+	/// A trie holding only `value` at `index`, with the proof for it.
+	pub(crate) fn single_value_trie(index: u64, value: &[u8]) -> (H256, Vec<Vec<u8>>) {
+		let node = leaf(&nibbles(&trie_key(index)), value);
+		(H256(keccak_256(&node)), vec![node])
+	}
+
+	/// A post-London header with the given roots.
+	pub(crate) fn header(
+		number: u64,
+		timestamp: u64,
+		transactions_root: H256,
+		receipts_root: H256,
+	) -> Vec<u8> {
+		let mut stream = RlpStream::new_list(16);
+		stream
+			.append(&H256::repeat_byte(1))
+			.append(&H256::repeat_byte(2))
+			.append(&H160::repeat_byte(3))
+			.append(&H256::repeat_byte(4))
+			.append(&transactions_root)
+			.append(&receipts_root)
+			.append(&vec![0u8; 256])
+			.append(&0u8)
+			.append(&number)
+			.append(&30_000_000u64)
+			.append(&21_000u64)
+			.append(&timestamp)
+			.append_empty_data()
+			.append(&H256::repeat_byte(5))
+			.append(&vec![0u8; 8])
+			.append(&7u64);
+		stream.out().to_vec()
+	}
+
+	/// A signed EIP-1559 transaction calling `to` with `input`.
+	pub(crate) fn transaction(to: H160, input: &[u8]) -> Vec<u8> {
+		let mut stream = RlpStream::new_list(12);
+		stream
+			.append(&1u64)
+			.append(&3u64)
+			.append(&1_000_000_000u64)
+			.append(&2_000_000_000u64)
+			.append(&100_000u64)
+			.append(&to)
+			.append(&0u8)
+			.append(input)
+			.begin_list(0);
+		stream.append(&1u8).append(&H256::repeat_byte(6)).append(&H256::repeat_byte(7));
+		let mut raw = vec![2];
+		raw.extend_from_slice(&stream.out());
+		raw
+	}
+
+	/// An EIP-1559 receipt with `logs`.
+	pub(crate) fn receipt(succeeded: bool, logs: &[ReceiptLog]) -> Vec<u8> {
+		let mut stream = RlpStream::new_list(4);
+		stream
+			.append(&u8::from(succeeded))
+			.append(&21_000u64)
+			.append(&vec![0u8; 256])
+			.begin_list(logs.len());
+		for log in logs {
+			stream
+				.begin_list(3)
+				.append(&log.address)
+				.append_list(&log.topics)
+				.append(&log.data);
+		}
+		let mut raw = vec![2];
+		raw.extend_from_slice(&stream.out());
+		raw
+	}
+
+	/// The input of an ethless transfer from `from` to `to`, without the function selector.
+	pub(crate) fn ethless_input(from: H160, to: H160, amount: U256, nonce: U256) -> Vec<u8> {
+		let calldata = ethless_transfer_function_abi()
+			.encode_input(&[
+				Token::Address(from),
+				Token::Address(to),
+				Token::Uint(amount),
+				Token::Uint(U256::zero()),
+				Token::Uint(nonce),
+				Token::Bytes(vec![0u8; 65]),
+			])
+			.expect("valid tokens; qed");
+		calldata[4..].to_vec()
+	}
+
+	/// The ERC-20 `Transfer` log of `contract` moving `amount` from `from` to `to`.
+	pub(crate) fn transfer_log(contract: H160, from: H160, to: H160, amount: U256) -> ReceiptLog {
+		let signature = H256(keccak_256(b"Transfer(address,address,uint256)"));
+		let mut data = vec![0u8; 32];
+		amount.to_big_endian(&mut data);
+		ReceiptLog { address: contract, topics: vec![signature, from.into(), to.into()], data }
+	}
+
+	/// A proof that a successful transaction calling `to` with `input` is the first one of block
+	/// `number`, along with the header's hash and the transaction's hash.
+	pub(crate) fn transfer_proof(
+		number: u64,
+		timestamp: u64,
+		to: H160,
+		input: &[u8],
+	) -> (TransferProof, H256, H256) {
+		let selector = ethless_transfer_function_abi().short_signature();
+		let calldata: Vec<u8> = selector.iter().chain(input).copied().collect();
+		transaction_proof(number, timestamp, to, &calldata, &[])
+	}
+
+	/// Like [`transfer_proof`], for a transaction calling `to` with the full `calldata` and
+	/// emitting `logs`.
+	pub(crate) fn transaction_proof(
+		number: u64,
+		timestamp: u64,
+		to: H160,
+		calldata: &[u8],
+		logs: &[ReceiptLog],
+	) -> (TransferProof, H256, H256) {
+		let raw_transaction = transaction(to, calldata);
+		let (transactions_root, transaction_proof) = single_value_trie(0, &raw_transaction);
+		let (receipts_root, receipt_proof) = single_value_trie(0, &receipt(true, logs));
+		let header = header(number, timestamp, transactions_root, receipts_root);
+
+		let bounded = |nodes: Vec<Vec<u8>>| {
+			let nodes: Vec<_> = nodes
+				.into_iter()
+				.map(|node| node.try_into().expect("small node; qed"))
+				.collect();
+			nodes.try_into().expect("few nodes; qed")
+		};
+		let proof = TransferProof {
+			transaction_index: 0,
+			transaction: bounded(transaction_proof),
+			receipt: bounded(receipt_proof),
+			header: header.clone().try_into().expect("small header; qed"),
+		};
+		(proof, H256(keccak_256(&header)), H256(keccak_256(&raw_transaction)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::test_utils::*;
+	use super::*;
+	use rlp::RlpStream;
+
+	#[test]
+	fn headers_decode() {
+		let raw = header(42, 1_700_000_000, H256::repeat_byte(8), H256::repeat_byte(9));
+		let header = EthHeader::decode(&raw).unwrap();
+
+		assert_eq!(header.hash, H256(keccak_256(&raw)));
+		assert_eq!(header.number, 42);
+		assert_eq!(header.timestamp, 1_700_000_000);
+		assert_eq!(header.transactions_root, H256::repeat_byte(8));
+		assert_eq!(header.receipts_root, H256::repeat_byte(9));
+		assert_eq!(EthHeader::decode(&raw[..100]), Err(ProofError::Malformed));
+	}
+
+	#[test]
+	fn transactions_and_receipts_decode() {
+		let to = H160::repeat_byte(0xaa);
+		let call = decode_transaction(&transaction(to, b"input")).unwrap();
+		assert_eq!(call, EthCall { to: Some(to), input: b"input".to_vec() });
+		assert_eq!(decode_transaction(&[3, 0xc0]), Err(ProofError::UnsupportedType));
+
+		assert_eq!(receipt_succeeded(&receipt(true, &[])), Ok(true));
+		assert_eq!(receipt_succeeded(&receipt(false, &[])), Ok(false));
+
+		let log = transfer_log(to, H160::repeat_byte(1), H160::repeat_byte(2), U256::from(100));
+		assert_eq!(receipt_logs(&receipt(true, &[log.clone()])), Ok(vec![log]));
+	}
+
+	#[test]
+	fn single_value_proofs_verify() {
+		let (root, proof) = single_value_trie(0, b"receipt");
+
+		assert_eq!(verify_proof(&root, &trie_key(0), &proof), Ok(b"receipt".to_vec()));
+		assert_eq!(verify_proof(&root, &trie_key(1), &proof), Err(ProofError::Absent));
+		assert_eq!(
+			verify_proof(&H256::repeat_byte(1), &trie_key(0), &proof),
+			Err(ProofError::HashMismatch)
+		);
+		assert_eq!(
+			verify_proof(&root, &trie_key(0), &Vec::<Vec<u8>>::new()),
+			Err(ProofError::MissingNode)
+		);
+	}
+
+	#[test]
+	fn proofs_verify_through_branches() {
+		// the keys of indices 0 and 1 are 0x80 and 0x01, they split at the first nibble
+		let first = leaf(&[0], &[0xa0; 40]);
+		let second = leaf(&[1], &[0xb0; 40]);
+
+		let mut branch = RlpStream::new_list(17);
+		for nibble in 0..16 {
+			match nibble {
+				0 => branch.append(&H256(keccak_256(&second))),
+				8 => branch.append(&H256(keccak_256(&first))),
+				_ => branch.append_empty_data(),
+			};
+		}
+		branch.append_empty_data();
+		let branch = branch.out().to_vec();
+		let root = H256(keccak_256(&branch));
+
+		let proof = vec![branch.clone(), first.clone()];
+		assert_eq!(verify_proof(&root, &trie_key(0), &proof), Ok(vec![0xa0; 40]));
+		let proof = vec![branch.clone(), second];
+		assert_eq!(verify_proof(&root, &trie_key(1), &proof), Ok(vec![0xb0; 40]));
+
+		// a proof for index 0 doesn't prove index 1
+		let proof = vec![branch, first];
+		assert_eq!(verify_proof(&root, &trie_key(1), &proof), Err(ProofError::HashMismatch));
+	}
+}
@@ -0,0 +1,121 @@
+use crate::ocw::VerificationFailureCause;
+use crate::{pallet::*, Error, FailedTransfer, TransferId};
+use frame_support::{ensure, traits::Get, weights::Weight};
+use frame_system::pallet_prelude::*;
+use sp_runtime::traits::CheckedSub;
+use tracing as log;
+
+impl<T: Config> Pallet<T> {
+	pub(crate) fn record_failed_transfer(
+		id: &TransferId<T::Hash>,
+		cause: VerificationFailureCause,
+		authority: Option<T::AccountId>,
+	) {
+		let block = Self::block_number();
+		let recorded =
+			FailedTransfersAt::<T>::mutate(block, |ids| ids.try_push(id.clone()).is_ok());
+		if recorded {
+			FailedTransfers::<T>::insert(id, FailedTransfer { cause, authority, block });
+		} else {
+			log::warn!("Too many failed transfers at {block:?}, not recording {id:?}");
+		}
+	}
+
+	/// Drops the failures recorded `FailedTransferTtl` blocks before `now`, unless the transfer
+	/// failed again since.
+	pub(crate) fn prune_failed_transfers(now: BlockNumberFor<T>) -> Weight {
+		let Some(recorded_at) = now.checked_sub(&T::FailedTransferTtl::get()) else {
+			return Weight::zero();
+		};
+		let ids = FailedTransfersAt::<T>::take(recorded_at);
+		for id in &ids {
+			if FailedTransfers::<T>::get(id).map_or(false, |failed| failed.block == recorded_at) {
+				FailedTransfers::<T>::remove(id);
+			}
+		}
+
+		let count = ids.len() as u64;
+		T::DbWeight::get().reads_writes(count.saturating_add(1), count.saturating_add(1))
+	}
+
+	/// Only failures that would happen again block the transfer, the others, like missing
+	/// confirmations or a timeout, can be retried right away.
+	pub(crate) fn ensure_not_failed(id: &TransferId<T::Hash>) -> Result<(), Error<T>> {
+		let fatal = FailedTransfers::<T>::get(id).map_or(false, |failed| failed.cause.is_final());
+		ensure!(!fatal, Error::<T>::TransferVerificationFailed);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::mock::{AccountId, ExtBuilder, System, Test};
+	use crate::ocw::VerificationFailureCause;
+	use crate::pallet::{FailedTransfers, FailedTransfersAt, Pallet as Creditcoin};
+	use crate::{Blockchain, FailedTransfer, TransferId};
+	use frame_support::traits::Hooks;
+
+	#[test]
+	fn failures_are_pruned_after_their_ttl_unless_they_happened_again() {
+		ExtBuilder::default().build_and_execute(|| {
+			System::set_block_number(1);
+			let first = TransferId::new::<Test>(&Blockchain::Rinkeby, b"0x01");
+			let again = TransferId::new::<Test>(&Blockchain::Rinkeby, b"0x02");
+			Creditcoin::<Test>::record_failed_transfer(
+				&first,
+				VerificationFailureCause::TaskFailed,
+				None,
+			);
+			Creditcoin::<Test>::record_failed_transfer(
+				&again,
+				VerificationFailureCause::TaskUnconfirmed,
+				None,
+			);
+
+			System::set_block_number(5);
+			Creditcoin::<Test>::record_failed_transfer(
+				&again,
+				VerificationFailureCause::IncorrectAmount,
+				None,
+			);
+
+			// the mock keeps failures for 10 blocks
+			Creditcoin::<Test>::on_initialize(11);
+			assert_eq!(FailedTransfers::<Test>::get(&first), None);
+			assert_eq!(FailedTransfers::<Test>::get(&again).unwrap().block, 5);
+			assert!(FailedTransfersAt::<Test>::get(1).is_empty());
+
+			Creditcoin::<Test>::on_initialize(15);
+			assert_eq!(FailedTransfers::<Test>::get(&again), None);
+		});
+	}
+
+	#[test]
+	fn only_a_bounded_number_of_failures_is_recorded_per_block() {
+		ExtBuilder::default().build_and_execute(|| {
+			System::set_block_number(1);
+			let authority = AccountId::new([1; 32]);
+			let ids: Vec<_> =
+				(0u8..3).map(|i| TransferId::new::<Test>(&Blockchain::Rinkeby, &[i])).collect();
+			for id in &ids {
+				Creditcoin::<Test>::record_failed_transfer(
+					id,
+					VerificationFailureCause::TaskFailed,
+					Some(authority.clone()),
+				);
+			}
+
+			// the mock records at most 2 failures per block
+			assert_eq!(FailedTransfersAt::<Test>::get(1).into_inner(), ids[..2].to_vec());
+			assert_eq!(
+				FailedTransfers::<Test>::get(&ids[0]),
+				Some(FailedTransfer {
+					cause: VerificationFailureCause::TaskFailed,
+					authority: Some(authority),
+					block: 1,
+				})
+			);
+			assert_eq!(FailedTransfers::<Test>::get(&ids[2]), None);
+		});
+	}
+}
@@ -0,0 +1,56 @@
+use super::{eth_message, signing};
+use crate::{
+	pallet::*,
+	types::{Address, AddressId, ContractSignature, UnverifiedContractOwnership},
+	Blockchain, Error, ExternalAddress,
+};
+use frame_support::{ensure, fail, pallet_prelude::DispatchResult};
+use pallet_offchain_task_scheduler::tasks::{TaskScheduler, TaskV2};
+use parity_scale_codec::Encode;
+
+impl<T: Config> Pallet<T> {
+	/// Smart contract wallets can't produce a signature we can recover onchain, so the
+	/// ERC-1271 proof is handed over to the authorities and the address is registered once
+	/// the task is persisted.
+	pub fn register_contract_address_internal(
+		who: T::AccountId,
+		blockchain: Blockchain,
+		address: ExternalAddress,
+		signature: ContractSignature,
+	) -> DispatchResult {
+		let address_id = AddressId::new::<T>(&blockchain, &address);
+
+		if let Ok(account_id) = Addresses::<T>::try_get(&address_id) {
+			if who == account_id.owner {
+				fail!(Error::<T>::AddressAlreadyRegisteredByCaller);
+			}
+			fail!(Error::<T>::AddressAlreadyRegistered);
+		}
+
+		let payload = signing::domain_separated_payload::<T>(
+			signing::SigningDomain::RegisterAddressV2,
+			who.encode().as_slice(),
+		);
+		let message_hash = eth_message(&sp_io::hashing::blake2_256(&payload));
+
+		let deadline = T::TaskScheduler::deadline();
+		let entry = Address { blockchain, value: address, owner: who };
+		let pending = UnverifiedContractOwnership {
+			address: entry.clone(),
+			message_hash,
+			signature,
+			deadline,
+		};
+
+		let task_id = TaskV2::<T>::to_id(&pending);
+		ensure!(
+			!T::TaskScheduler::is_pending(&task_id),
+			Error::<T>::AddressRegistrationAlreadyPending
+		);
+		T::TaskScheduler::insert(&deadline, &task_id, pending.into());
+
+		Self::deposit_event(Event::<T>::AddressRegistrationPending(address_id, entry));
+
+		Ok(())
+	}
+}
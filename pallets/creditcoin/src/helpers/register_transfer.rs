@@ -1,14 +1,15 @@
 use crate::OrderId;
 use crate::{
-	pallet::*, types::AddressId, Error, ExternalAmount, ExternalTxId, Task, Transfer, TransferId,
+	pallet::*, types::AddressId, Error, ExternalAmount, ExternalTxId, Transfer, TransferId,
 	TransferKind, UnverifiedTransfer,
 };
-use frame_support::ensure;
+use frame_support::{ensure, traits::Get, weights::Weight};
 use frame_system::pallet_prelude::*;
 use frame_system::Config as SystemConfig;
 use pallet_offchain_task_scheduler::tasks::TaskScheduler;
 use pallet_offchain_task_scheduler::tasks::TaskV2;
 use pallet_timestamp::Config as TimestampConfig;
+use sp_runtime::traits::CheckedSub;
 use sp_std::prelude::*;
 
 type UnverifiedTransferFor<T> = UnverifiedTransfer<
@@ -56,6 +57,7 @@ impl<T: Config> Pallet<T> {
 			account_id: who,
 			tx_id: blockchain_tx_id,
 			timestamp: None,
+			evidence: None,
 		};
 
 		let deadline = T::TaskScheduler::deadline();
@@ -104,6 +106,7 @@ impl<T: Config> Pallet<T> {
 			account_id: who,
 			tx_id: blockchain_tx_id,
 			timestamp: None,
+			evidence: None,
 		};
 
 		let deadline = T::TaskScheduler::deadline();
@@ -116,6 +119,7 @@ impl<T: Config> Pallet<T> {
 		};
 
 		let inner_id = TaskV2::<T>::to_id(&pending);
+		Self::ensure_not_failed(&TransferId::from(inner_id))?;
 
 		Self::check_and_submit_transfer_as_task(&inner_id, pending)?;
 
@@ -133,13 +137,77 @@ impl<T: Config> Pallet<T> {
 			!<UnverifiedTransfer<_, _, _, _> as TaskV2::<T>>::is_persisted(task_id),
 			Error::<T>::TransferAlreadyRegistered
 		);
-		ensure!(
-			!T::TaskScheduler::is_scheduled(&deadline, task_id),
-			Error::<T>::TransferAlreadyRegistered
-		);
-		let pending_transfer = Task::from(pending_transfer);
-		T::TaskScheduler::insert(&deadline, task_id, pending_transfer);
+		ensure!(!T::TaskScheduler::is_pending(task_id), Error::<T>::TransferAlreadyRegistered);
+		T::TaskScheduler::insert(&deadline, task_id, pending_transfer.into());
+
+		Ok(())
+	}
+
+	/// Stores a transfer verified in this block, by the authorities or from a proof, and checks
+	/// it again while its chain may still reorg. A cancellation of an earlier registration no
+	/// longer applies once the transfer is verified.
+	pub(crate) fn store_verified_transfer(
+		id: TransferId<T::Hash>,
+		mut transfer: TransferFor<T>,
+	) -> Event<T> {
+		transfer.block = Self::block_number();
+
+		Self::schedule_reverification(&id, &transfer);
+		Transfers::<T>::insert(&id, transfer);
+		CancelledTransfers::<T>::remove(&id);
+		Event::<T>::TransferVerified(id)
+	}
+
+	pub(crate) fn record_cancelled_transfer(id: &TransferId<T::Hash>) -> Result<(), Error<T>> {
+		let block = Self::block_number();
+		let recorded =
+			CancelledTransfersAt::<T>::mutate(block, |ids| ids.try_push(id.clone()).is_ok());
+		ensure!(recorded, Error::<T>::TooManyCancelledTransfers);
+		CancelledTransfers::<T>::insert(id, block);
+		Ok(())
+	}
 
+	/// Drops the cancellations made `CancelledTransferTtl` blocks before `now`, unless the
+	/// transfer was cancelled again since. Outputs for those registrations can't be included
+	/// anymore.
+	pub(crate) fn prune_cancelled_transfers(now: BlockNumberFor<T>) -> Weight {
+		let Some(cancelled_at) = now.checked_sub(&T::CancelledTransferTtl::get()) else {
+			return Weight::zero();
+		};
+		let ids = CancelledTransfersAt::<T>::take(cancelled_at);
+		for id in &ids {
+			if CancelledTransfers::<T>::get(id) == Some(cancelled_at) {
+				CancelledTransfers::<T>::remove(id);
+			}
+		}
+
+		let count = ids.len() as u64;
+		T::DbWeight::get().reads_writes(count.saturating_add(1), count.saturating_add(1))
+	}
+
+	/// Once a transfer was cancelled, an output for it has to be about the transfer pending
+	/// under its id now. Failures carry no transfer, they have to be for the pending task's
+	/// current `deadline`, which the cancelled registration never had.
+	pub(crate) fn ensure_current_transfer_task(
+		id: &TransferId<T::Hash>,
+		deadline: &T::BlockNumber,
+		output: Option<&Transfer<T::AccountId, BlockNumberFor<T>, T::Hash, T::Moment>>,
+	) -> Result<(), crate::Error<T>> {
+		if !CancelledTransfers::<T>::contains_key(id) {
+			return Ok(());
+		}
+
+		let pending: Option<(T::BlockNumber, UnverifiedTransferFor<T>)> =
+			T::TaskScheduler::find(&id.clone().into_inner())
+				.and_then(|(deadline, task)| Some((deadline, task.try_into().ok()?)));
+		let current = match (pending, output) {
+			(Some((_, pending)), Some(output)) => {
+				pending.transfer == Transfer { timestamp: None, evidence: None, ..output.clone() }
+			},
+			(Some((current, _)), None) => current == *deadline,
+			(None, _) => false,
+		};
+		ensure!(current, Error::<T>::StaleTaskOutput);
 		Ok(())
 	}
 }
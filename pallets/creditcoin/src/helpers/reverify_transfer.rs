@@ -0,0 +1,71 @@
+use crate::{
+	pallet::*, OrderId, Reverification, Transfer, TransferId, TransferReverification,
+	TransferReverificationFor,
+};
+use frame_support::traits::Get;
+use frame_system::pallet_prelude::*;
+use pallet_offchain_task_scheduler::tasks::TaskScheduler;
+use pallet_timestamp::Config as TimestampConfig;
+use sp_runtime::traits::{Saturating, Zero};
+use tracing as log;
+
+type TransferFor<T> = Transfer<
+	<T as frame_system::Config>::AccountId,
+	BlockNumberFor<T>,
+	<T as frame_system::Config>::Hash,
+	<T as TimestampConfig>::Moment,
+>;
+
+impl<T: Config> Pallet<T> {
+	/// Checks the transfer again until `TransferReverificationWindow` blocks from now. Transfers
+	/// verified without evidence have no block hash to compare against and aren't checked.
+	pub(crate) fn schedule_reverification(id: &TransferId<T::Hash>, transfer: &TransferFor<T>) {
+		let window = T::TransferReverificationWindow::get();
+		let Some(evidence) = transfer.evidence else {
+			return;
+		};
+		if window.is_zero() {
+			return;
+		}
+
+		let task: TransferReverificationFor<T> = TransferReverification {
+			transfer_id: id.clone(),
+			blockchain: transfer.blockchain.clone(),
+			tx_id: transfer.tx_id.clone(),
+			evidence,
+			due: Self::block_number().saturating_add(window),
+		};
+		let task: T::Task = task.into();
+		let deadline = T::TaskScheduler::deadline_for(&task);
+		T::TaskScheduler::insert(&deadline, &id.reverification_id::<T>(), task);
+	}
+
+	/// Applies the authorities' finding. A transfer already used for a deal order stays, the
+	/// deal order is flagged instead.
+	pub(crate) fn settle_reverification(
+		id: TransferId<T::Hash>,
+		outcome: Reverification,
+	) -> Event<T> {
+		match outcome {
+			Reverification::Confirmed(evidence) => {
+				Transfers::<T>::mutate(&id, |transfer| {
+					if let Some(transfer) = transfer {
+						transfer.evidence = Some(evidence);
+					}
+				});
+				Event::<T>::TransferReverified(id)
+			},
+			Reverification::Reorged => match Transfers::<T>::get(&id) {
+				Some(Transfer { is_processed: true, order_id: OrderId::Deal(deal_id), .. }) => {
+					log::warn!("Processed transfer {id:?} of deal order {deal_id:?} was reorged");
+					FlaggedDealOrders::<T>::insert(&deal_id, id.clone());
+					Event::<T>::DealOrderFlagged(deal_id, id)
+				},
+				_ => {
+					Transfers::<T>::remove(&id);
+					Event::<T>::TransferRevoked(id)
+				},
+			},
+		}
+	}
+}
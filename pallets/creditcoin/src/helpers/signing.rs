@@ -0,0 +1,109 @@
+use crate::{pallet::*, types::concatenate, Error};
+use frame_support::RuntimeDebug;
+use frame_system::pallet_prelude::BlockNumberFor;
+use sp_runtime::traits::Zero;
+use sp_std::prelude::*;
+
+/// Prefix of every domain-separated payload a user signs. The suffix is the version of the
+/// signing scheme and must be bumped whenever the layout of the payload changes.
+pub const SIGNED_PAYLOAD_PREFIX: &[u8] = b"creditcoin/signed-payload/v1";
+
+/// Blocks legacy payloads stay accepted for on a new chain, or after the upgrade that
+/// introduced domain separation. About 90 days at 15 second blocks.
+pub const LEGACY_SIGNING_GRACE_PERIOD: u32 = 90 * 24 * 60 * 4;
+
+/// The extrinsic a user-signed payload is meant for. A signature made for one call can't be
+/// replayed against another.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum SigningDomain {
+	RegisterAddress,
+	RegisterAddressV2,
+	RegisterDealOrder,
+	RotateAuthority,
+}
+
+impl SigningDomain {
+	pub fn as_bytes(&self) -> &'static [u8] {
+		match self {
+			SigningDomain::RegisterAddress => b"register_address",
+			SigningDomain::RegisterAddressV2 => b"register_address_v2",
+			SigningDomain::RegisterDealOrder => b"register_deal_order",
+			SigningDomain::RotateAuthority => b"rotate_authority",
+		}
+	}
+}
+
+/// Builds `prefix ++ genesis_hash ++ call ++ payload`. The genesis hash ties the signature to
+/// a single chain, so a signature made on testnet is rejected on mainnet.
+pub fn domain_separated_payload<T: Config>(domain: SigningDomain, payload: &[u8]) -> Vec<u8> {
+	let genesis_hash = frame_system::Pallet::<T>::block_hash(BlockNumberFor::<T>::zero());
+	concatenate!(SIGNED_PAYLOAD_PREFIX, genesis_hash.as_ref(), domain.as_bytes(), payload)
+}
+
+/// Legacy, non domain-separated payloads are accepted until `LegacySigningDeadline` (inclusive).
+/// Without a deadline they are accepted indefinitely.
+pub fn legacy_signatures_accepted<T: Config>() -> bool {
+	LegacySigningDeadline::<T>::get()
+		.map_or(true, |deadline| Pallet::<T>::block_number() <= deadline)
+}
+
+/// Runs `verify` against the domain-separated form of `legacy_payload` and, failing that,
+/// against `legacy_payload` itself while the deprecation window is open.
+pub fn verify_signed_payload<T: Config>(
+	domain: SigningDomain,
+	legacy_payload: &[u8],
+	verify: impl Fn(&[u8]) -> Result<(), Error<T>>,
+) -> Result<(), Error<T>> {
+	let payload = domain_separated_payload::<T>(domain, legacy_payload);
+	let error = match verify(&payload) {
+		Ok(()) => return Ok(()),
+		Err(e) => e,
+	};
+
+	match verify(legacy_payload) {
+		Ok(()) if legacy_signatures_accepted::<T>() => Ok(()),
+		Ok(()) => Err(Error::<T>::LegacySignatureExpired),
+		Err(_) => Err(error),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mock::{ExtBuilder, System, Test};
+
+	#[test]
+	fn domain_separated_payload_differs_per_domain() {
+		ExtBuilder::default().build_and_execute(|| {
+			let register = domain_separated_payload::<Test>(SigningDomain::RegisterAddressV2, b"x");
+			let deal = domain_separated_payload::<Test>(SigningDomain::RegisterDealOrder, b"x");
+			assert_ne!(register, deal);
+			assert!(register.starts_with(SIGNED_PAYLOAD_PREFIX));
+			assert!(register.ends_with(b"register_address_v2x"));
+		});
+	}
+
+	#[test]
+	fn domain_separated_payload_commits_to_genesis_hash() {
+		ExtBuilder::default().build_and_execute(|| {
+			let before = domain_separated_payload::<Test>(SigningDomain::RegisterAddress, b"x");
+			frame_system::BlockHash::<Test>::insert(0, sp_core::H256::repeat_byte(1));
+			let after = domain_separated_payload::<Test>(SigningDomain::RegisterAddress, b"x");
+			assert_ne!(before, after);
+		});
+	}
+
+	#[test]
+	fn legacy_signatures_are_accepted_until_the_deadline() {
+		ExtBuilder::default().build_and_execute(|| {
+			System::set_block_number(10);
+			assert!(legacy_signatures_accepted::<Test>());
+
+			LegacySigningDeadline::<Test>::put(10);
+			assert!(legacy_signatures_accepted::<Test>());
+
+			System::set_block_number(11);
+			assert!(!legacy_signatures_accepted::<Test>());
+		});
+	}
+}
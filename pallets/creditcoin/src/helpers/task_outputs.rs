@@ -0,0 +1,57 @@
+use crate::{Call, Config};
+use core::marker::PhantomData;
+use frame_support::traits::IsSubType;
+use pallet_offchain_task_scheduler::submission::{CompletedTask, TaskOutputs};
+
+/// The calls completing a task, the only ones authorities may send unsigned.
+pub struct TaskOutputCalls<T>(PhantomData<T>);
+
+impl<T: Config, RuntimeCall: IsSubType<Call<T>>> TaskOutputs<RuntimeCall, T::BlockNumber, T::Hash>
+	for TaskOutputCalls<T>
+{
+	fn task_of(call: &RuntimeCall) -> Option<CompletedTask<T::BlockNumber, T::Hash>> {
+		match call.is_sub_type()? {
+			Call::persist_task_output { deadline, task_output, assignment } => {
+				Some(CompletedTask {
+					deadline: *deadline,
+					task_id: task_output.task_id::<T>(),
+					assignment: assignment.clone(),
+				})
+			},
+			Call::fail_task { deadline, task_id, assignment, .. } => Some(CompletedTask {
+				deadline: *deadline,
+				task_id: task_id.clone().into_inner(),
+				assignment: assignment.clone(),
+			}),
+			_ => None,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mock::{RuntimeCall, Test};
+	use crate::ocw::errors::VerificationFailureCause;
+	use crate::{TaskId, TransferId};
+	use sp_core::H256;
+
+	#[test]
+	fn task_outputs_name_the_task_they_complete() {
+		let id = TransferId::from(H256::repeat_byte(1));
+		let call = RuntimeCall::Creditcoin(Call::fail_task {
+			deadline: 7,
+			task_id: TaskId::VerifyTransfer(id),
+			cause: VerificationFailureCause::TransactionNotFound,
+			assignment: None,
+		});
+
+		assert_eq!(
+			TaskOutputCalls::<Test>::task_of(&call),
+			Some(CompletedTask { deadline: 7, task_id: H256::repeat_byte(1), assignment: None })
+		);
+
+		let remark = RuntimeCall::System(frame_system::Call::remark { remark: vec![] });
+		assert_eq!(TaskOutputCalls::<Test>::task_of(&remark), None);
+	}
+}
@@ -0,0 +1,153 @@
+use super::eth_proof::{self, EthHeader, ProofError};
+use crate::{
+	pallet::*, Blockchain, Error, HeaderAttestation, HeaderId, Transfer, TransferEvidence,
+	TransferId, TransferKind, TransferProof, UnverifiedTransfer, UnverifiedTransferFor,
+};
+use frame_support::{ensure, fail, pallet_prelude::DispatchResult, traits::Get, weights::Weight};
+use frame_system::pallet_prelude::*;
+use pallet_offchain_task_scheduler::tasks::TaskScheduler;
+use sp_core::H256;
+use sp_runtime::traits::{CheckedSub, UniqueSaturatedFrom};
+use tracing as log;
+
+fn invalid_proof<T: Config>(e: ProofError) -> Error<T> {
+	log::debug!("Invalid transfer proof: {e:?}");
+	Error::<T>::InvalidTransferProof
+}
+
+impl<T: Config> Pallet<T> {
+	pub(crate) fn request_header_attestation_internal(
+		blockchain: Blockchain,
+		number: u64,
+	) -> DispatchResult {
+		ensure!(
+			matches!(
+				blockchain,
+				Blockchain::Ethereum | Blockchain::Rinkeby | Blockchain::Luniverse
+			),
+			Error::<T>::UnsupportedBlockchain
+		);
+		let header_id = HeaderId::new::<T>(&blockchain, number);
+		ensure!(!AttestedHeaders::<T>::contains_key(&header_id), Error::<T>::HeaderAlreadyAttested);
+
+		let task_id = header_id.clone().into_inner();
+		ensure!(
+			!T::TaskScheduler::is_pending(&task_id),
+			Error::<T>::HeaderAttestationAlreadyPending
+		);
+		let header = HeaderAttestation { blockchain, number };
+		let task: T::Task = header.clone().into();
+		let deadline = T::TaskScheduler::deadline_for(&task);
+		T::TaskScheduler::insert(&deadline, &task_id, task);
+
+		Self::deposit_event(Event::<T>::HeaderAttestationRequested(header_id, header));
+		Ok(())
+	}
+
+	/// Whether another header can be attested in this block.
+	pub(crate) fn can_attest_header() -> bool {
+		let attested = AttestedHeadersAt::<T>::decode_len(Self::block_number()).unwrap_or(0);
+		attested < T::MaxAttestedHeadersPerBlock::get() as usize
+	}
+
+	/// Keeps the header until it is pruned. Attestations are only persisted when
+	/// [`Self::can_attest_header`], so there is room for it.
+	pub(crate) fn record_attested_header(id: &HeaderId<T::Hash>, hash: H256) {
+		let block = Self::block_number();
+		let recorded =
+			AttestedHeadersAt::<T>::mutate(block, |ids| ids.try_push(id.clone()).is_ok());
+		if recorded {
+			AttestedHeaders::<T>::insert(id, hash);
+		} else {
+			log::warn!("Too many attested headers at {block:?}, not recording {id:?}");
+		}
+	}
+
+	/// Drops the headers attested `AttestedHeaderTtl` blocks before `now`.
+	pub(crate) fn prune_attested_headers(now: BlockNumberFor<T>) -> Weight {
+		let Some(attested_at) = now.checked_sub(&T::AttestedHeaderTtl::get()) else {
+			return Weight::zero();
+		};
+		let ids = AttestedHeadersAt::<T>::take(attested_at);
+		for id in &ids {
+			AttestedHeaders::<T>::remove(id);
+		}
+
+		let count = ids.len() as u64;
+		T::DbWeight::get().reads_writes(1, count.saturating_add(1))
+	}
+
+	/// Completes the verification of a pending ethless transfer without the authorities, from
+	/// proofs that its transaction and successful receipt are in a block with an attested
+	/// header. The header being attested stands in for the authorities' confirmations.
+	pub(crate) fn verify_transfer_proof_internal(
+		transfer_id: TransferId<T::Hash>,
+		proof: TransferProof,
+	) -> DispatchResult {
+		let task_id = transfer_id.clone().into_inner();
+		ensure!(T::TaskScheduler::is_pending(&task_id), Error::<T>::NonExistentTransfer);
+		let (deadline, pending) = T::TaskScheduler::find(&task_id)
+			.and_then(|(deadline, task)| {
+				let pending: UnverifiedTransferFor<T> = task.try_into().ok()?;
+				Some((deadline, pending))
+			})
+			.ok_or(Error::<T>::NonExistentTransfer)?;
+		let UnverifiedTransfer { transfer, from_external, to_external, .. } = pending;
+		let TransferKind::Ethless(contract) = &transfer.kind else {
+			fail!(Error::<T>::UnsupportedTransferKind);
+		};
+
+		let header = EthHeader::decode(&proof.header).map_err(invalid_proof::<T>)?;
+		let header_id = HeaderId::new::<T>(&transfer.blockchain, header.number);
+		ensure!(
+			AttestedHeaders::<T>::get(&header_id) == Some(header.hash),
+			Error::<T>::HeaderNotAttested
+		);
+
+		let key = eth_proof::trie_key(proof.transaction_index);
+		let raw_transaction =
+			eth_proof::verify_proof(&header.transactions_root, &key, &proof.transaction)
+				.map_err(invalid_proof::<T>)?;
+		let raw_receipt = eth_proof::verify_proof(&header.receipts_root, &key, &proof.receipt)
+			.map_err(invalid_proof::<T>)?;
+
+		let tx_hash = sp_io::hashing::keccak_256(&raw_transaction);
+		ensure!(transfer.tx_id.as_slice() == tx_hash.as_slice(), Error::<T>::TransferProofMismatch);
+		ensure!(
+			eth_proof::receipt_succeeded(&raw_receipt).map_err(invalid_proof::<T>)?,
+			Error::<T>::TransferProofMismatch
+		);
+		let call = eth_proof::decode_transaction(&raw_transaction).map_err(invalid_proof::<T>)?;
+		let logs = eth_proof::receipt_logs(&raw_receipt).map_err(invalid_proof::<T>)?;
+		let log_index = Self::validate_ethless_calldata(
+			contract,
+			&from_external,
+			&to_external,
+			&transfer.order_id,
+			&transfer.amount,
+			call.to.as_ref(),
+			&call.input,
+			&logs,
+		)
+		.map_err(|e| {
+			log::debug!("Proven transaction doesn't match transfer {transfer_id:?}: {e:?}");
+			Error::<T>::TransferProofMismatch
+		})?;
+
+		let transfer = Transfer {
+			timestamp: Some(T::Moment::unique_saturated_from(header.timestamp)),
+			evidence: Some(TransferEvidence {
+				block_number: header.number,
+				block_hash: header.hash,
+				transaction_index: proof.transaction_index,
+				log_index,
+			}),
+			..transfer
+		};
+		let event = Self::store_verified_transfer(transfer_id, transfer);
+		T::TaskScheduler::remove(&deadline, &task_id);
+
+		Self::deposit_event(event);
+		Ok(())
+	}
+}
@@ -41,20 +41,24 @@ mod types;
 pub mod test_utils;
 
 use crate::types::{BurnId, BurnInfo};
+pub use helpers::TaskOutputCalls;
 use ocw::tasks::collect_coins::DeployedContract;
 pub use types::{
 	loan_terms, Address, AddressId, AskOrder, AskOrderId, AskTerms, BidOrder, BidOrderId, BidTerms,
 	Blockchain, CollectedCoinsId, CollectedCoinsStruct, DealOrder, DealOrderId, Duration,
-	ExternalAddress, ExternalAmount, ExternalTxId, Guid, InterestRate, InterestType, LegacySighash,
-	LoanTerms, Offer, OfferId, OrderId, RatePerPeriod, Task, TaskId, TaskOutput, Transfer,
-	TransferId, TransferKind, UnverifiedTransfer,
+	ExternalAddress, ExternalAmount, ExternalTxId, FailedTransfer, Guid, HeaderAttestation,
+	HeaderId, InterestRate, InterestType, LegacySighash, LoanTerms, Offer, OfferId, OrderId,
+	RatePerPeriod, Reverification, TaskId, TaskOutput, Transfer, TransferEvidence, TransferId,
+	TransferKind, TransferProof, TransferReverification, TransferReverificationFor,
+	TransferVerification, UnverifiedContractOwnership, UnverifiedContractOwnershipFor,
+	UnverifiedTransfer, UnverifiedTransferFor,
 };
 
 pub(crate) use types::{DoubleMapExt, Id};
 
 pub type BalanceFor<T> = <T as pallet_balances::Config>::Balance;
 
-pub const STORAGE_VERSION: StorageVersion = StorageVersion::new(8);
+pub const STORAGE_VERSION: StorageVersion = StorageVersion::new(10);
 
 #[frame_support::pallet]
 pub mod pallet {
@@ -68,11 +72,19 @@ pub mod pallet {
 		traits::tokens::{currency::Currency as CurrencyT, ExistenceRequirement},
 		transactional,
 	};
-	use frame_system::{ensure_signed, offchain::CreateSignedTransaction, pallet_prelude::*};
+	use frame_system::{
+		ensure_signed, offchain::CreateSignedTransaction, pallet_prelude::*, WeightInfo as _,
+	};
 	use ocw::errors::VerificationFailureCause;
+	use pallet_offchain_task_scheduler::assignment::{TaskAssignment, VrfAssignment};
+	use pallet_offchain_task_scheduler::attestation::{Attestation, Attestations};
 	use pallet_offchain_task_scheduler::authority::AuthorityController;
+	use pallet_offchain_task_scheduler::incentives::AuthorityIncentives;
+	use pallet_offchain_task_scheduler::liveness::AuthorityLiveness;
 	use pallet_offchain_task_scheduler::tasks::TaskScheduler;
-	use sp_runtime::traits::{IdentifyAccount, UniqueSaturatedFrom, UniqueSaturatedInto, Verify};
+	use sp_runtime::traits::{
+		Hash as _, IdentifyAccount, UniqueSaturatedFrom, UniqueSaturatedInto, Verify,
+	};
 	use tracing as log;
 	use types::StorageCleanupState;
 
@@ -116,14 +128,61 @@ pub mod pallet {
 
 		type WeightInfo: WeightInfo;
 
-		type TaskScheduler: TaskScheduler<
-				BlockNumber = Self::BlockNumber,
+		/// The scheduler's task type, the kinds of task this pallet schedules are registered
+		/// with it.
+		type Task: From<UnverifiedTransferFor<Self>>
+			+ From<UnverifiedContractOwnershipFor<Self>>
+			+ From<TransferReverificationFor<Self>>
+			+ From<HeaderAttestation>
+			+ TryInto<UnverifiedTransferFor<Self>>
+			+ TryInto<TransferReverificationFor<Self>>;
+
+		type TaskScheduler: TaskScheduler<BlockNumber = Self::BlockNumber, Hash = Self::Hash, Task = Self::Task>
+			+ AuthorityController<AccountId = Self::AccountId>
+			+ Attestations<AccountId = Self::AccountId, Hash = Self::Hash>
+			+ TaskAssignment<AccountId = Self::AccountId, Hash = Self::Hash>
+			+ AuthorityIncentives<
+				AccountId = Self::AccountId,
 				Hash = Self::Hash,
-				Task = Task<Self::AccountId, Self::BlockNumber, Self::Hash, Self::Moment>,
-			> + AuthorityController<AccountId = Self::AccountId>;
+				Balance = BalanceFor<Self>,
+			> + AuthorityLiveness;
 
 		#[pallet::constant]
 		type PerBlockCleanupLimit: Get<u32>;
+
+		/// How many blocks a failed verification stays in `FailedTransfers`.
+		#[pallet::constant]
+		type FailedTransferTtl: Get<Self::BlockNumber>;
+
+		/// The most failed verifications recorded per block, further ones are only reported
+		/// in events.
+		#[pallet::constant]
+		type MaxFailedTransfersPerBlock: Get<u32>;
+
+		/// How long a verified transfer is checked again for reorgs of its chain, zero disables
+		/// the checks. Together with `UnverifiedTaskTimeout` it has to stay under the
+		/// scheduler's task lifetime.
+		#[pallet::constant]
+		type TransferReverificationWindow: Get<Self::BlockNumber>;
+
+		/// How many blocks transfers can be proven against an attested header, after which it
+		/// has to be attested again.
+		#[pallet::constant]
+		type AttestedHeaderTtl: Get<Self::BlockNumber>;
+
+		/// The most headers attested per block, further attestations wait for the next one.
+		#[pallet::constant]
+		type MaxAttestedHeadersPerBlock: Get<u32>;
+
+		/// How many blocks a cancellation stays in `CancelledTransfers`. It has to outlast the
+		/// outputs authorities may still submit for the cancelled registration: the mortality
+		/// of their signed transactions and the scheduler's task lifetime.
+		#[pallet::constant]
+		type CancelledTransferTtl: Get<Self::BlockNumber>;
+
+		/// The most transfers cancelled per block, further cancellations wait for the next one.
+		#[pallet::constant]
+		type MaxCancelledTransfersPerBlock: Get<u32>;
 	}
 
 	pub trait WeightInfo {
@@ -148,6 +207,16 @@ pub mod pallet {
 		fn register_deal_order() -> Weight;
 		fn remove_authority() -> Weight;
 		fn register_address_v2() -> Weight;
+		fn register_contract_address() -> Weight;
+		fn set_legacy_signing_deadline() -> Weight;
+		fn set_attestation_threshold() -> Weight;
+		fn bond_authority() -> Weight;
+		fn unbond_authority() -> Weight;
+		fn slash_authority() -> Weight;
+		fn rotate_authority() -> Weight;
+		fn cancel_transfer() -> Weight;
+		fn request_header_attestation() -> Weight;
+		fn verify_transfer_proof() -> Weight;
 	}
 
 	#[pallet::pallet]
@@ -252,6 +321,73 @@ pub mod pallet {
 	pub type BurnedFunds<T: Config> =
 		CountedStorageMap<_, Identity, BurnId, BurnInfo<T::AccountId, T::Balance>>;
 
+	/// Last block (inclusive) at which signatures over the legacy, non domain-separated
+	/// payloads are accepted. `None` keeps accepting them.
+	#[pallet::storage]
+	pub type LegacySigningDeadline<T: Config> = StorageValue<_, T::BlockNumber, OptionQuery>;
+
+	/// Transfers whose verification was cancelled by their registrant, with the block they
+	/// were cancelled at. Authorities that were already working on them may still submit
+	/// outputs, which only count for the transfer registered again under the same id. Dropped
+	/// once that one is verified, fails or expires, or `CancelledTransferTtl` blocks later.
+	#[pallet::storage]
+	pub type CancelledTransfers<T: Config> =
+		StorageMap<_, Identity, TransferId<T::Hash>, T::BlockNumber>;
+
+	/// The `CancelledTransfers` cancelled at a block, pruned `CancelledTransferTtl` blocks later.
+	#[pallet::storage]
+	pub type CancelledTransfersAt<T: Config> = StorageMap<
+		_,
+		Identity,
+		T::BlockNumber,
+		BoundedVec<TransferId<T::Hash>, T::MaxCancelledTransfersPerBlock>,
+		ValueQuery,
+	>;
+
+	/// Transfers that recently failed verification.
+	#[pallet::storage]
+	#[pallet::getter(fn failed_transfers)]
+	pub type FailedTransfers<T: Config> =
+		StorageMap<_, Identity, TransferId<T::Hash>, FailedTransfer<T::AccountId, T::BlockNumber>>;
+
+	/// The `FailedTransfers` recorded at a block, pruned `FailedTransferTtl` blocks later.
+	#[pallet::storage]
+	pub type FailedTransfersAt<T: Config> = StorageMap<
+		_,
+		Identity,
+		T::BlockNumber,
+		BoundedVec<TransferId<T::Hash>, T::MaxFailedTransfersPerBlock>,
+		ValueQuery,
+	>;
+
+	/// Deal orders whose transfer was reorged out of its chain after it was processed, with
+	/// the transfer. Flagged deal orders can't be locked or closed.
+	#[pallet::storage]
+	#[pallet::getter(fn flagged_deal_orders)]
+	pub type FlaggedDealOrders<T: Config> = StorageMap<
+		_,
+		Identity,
+		DealOrderId<T::BlockNumber, T::Hash>,
+		TransferId<T::Hash>,
+		OptionQuery,
+	>;
+
+	/// Hashes of external blocks attested by the authorities, for checking transfer proofs.
+	#[pallet::storage]
+	#[pallet::getter(fn attested_headers)]
+	pub type AttestedHeaders<T: Config> =
+		StorageMap<_, Identity, HeaderId<T::Hash>, sp_core::H256, OptionQuery>;
+
+	/// The `AttestedHeaders` attested at a block, pruned `AttestedHeaderTtl` blocks later.
+	#[pallet::storage]
+	pub type AttestedHeadersAt<T: Config> = StorageMap<
+		_,
+		Identity,
+		T::BlockNumber,
+		BoundedVec<HeaderId<T::Hash>, T::MaxAttestedHeadersPerBlock>,
+		ValueQuery,
+	>;
+
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	pub enum Event<T: Config> {
@@ -334,6 +470,45 @@ pub mod pallet {
 		TransferFailedVerification(TransferId<T::Hash>, VerificationFailureCause),
 
 		Burned(BurnId),
+
+		/// An address owned by a smart contract wallet has been submitted for registration
+		/// and will be verified.
+		/// [pending_address_id, pending_address]
+		AddressRegistrationPending(AddressId<T::Hash>, Address<T::AccountId>),
+
+		/// The smart contract wallet rejected the ownership proof and the address was not registered.
+		/// [address_id, cause]
+		AddressFailedVerification(AddressId<T::Hash>, VerificationFailureCause),
+
+		/// The deprecation window for legacy signed payloads has been changed.
+		/// [legacy_signing_deadline]
+		LegacySigningDeadlineSet(Option<T::BlockNumber>),
+
+		/// The registrant withdrew a transfer from verification.
+		/// [transfer_id]
+		TransferCancelled(TransferId<T::Hash>),
+
+		/// A verified transfer is still on its chain at the end of its re-verification window.
+		/// [transfer_id]
+		TransferReverified(TransferId<T::Hash>),
+
+		/// A verified transfer was reorged out of its chain before it was processed and is no
+		/// longer verified.
+		/// [transfer_id]
+		TransferRevoked(TransferId<T::Hash>),
+
+		/// A processed transfer was reorged out of its chain, the deal order it was used for
+		/// needs to be looked at.
+		/// [deal_order_id, transfer_id]
+		DealOrderFlagged(DealOrderId<T::BlockNumber, T::Hash>, TransferId<T::Hash>),
+
+		/// The hash of an external block was requested from the authorities.
+		/// [header_id, header]
+		HeaderAttestationRequested(HeaderId<T::Hash>, HeaderAttestation),
+
+		/// The authorities agreed on the hash of an external block.
+		/// [header_id, block_hash]
+		HeaderAttested(HeaderId<T::Hash>, sp_core::H256),
 	}
 
 	// Errors inform users that something went wrong.
@@ -422,6 +597,18 @@ pub mod pallet {
 		/// The account you are trying to remove is not  an authority.
 		NotAnAuthority,
 
+		/// The authority has already voted on the output of this task.
+		AlreadyAttested,
+
+		/// The attestation threshold must be at least 1.
+		InvalidAttestationThreshold,
+
+		/// The authority was not selected to work on this task.
+		NotAssigned,
+
+		/// The authority's bond is below the required minimum.
+		InsufficientBond,
+
 		/// The offer has already been made.
 		DuplicateOffer,
 
@@ -530,18 +717,70 @@ pub mod pallet {
 
 		/// A call to burn specified more free funds than the account has access to
 		BurnInsufficientFunds,
+
+		/// The signature was made over a legacy payload after the deprecation window closed.
+		/// Sign the domain-separated payload instead.
+		LegacySignatureExpired,
+
+		/// The ownership of this smart contract wallet address is already being verified.
+		AddressRegistrationAlreadyPending,
+
+		/// The output is for a cancelled transfer task, not the one currently pending.
+		StaleTaskOutput,
+
+		/// The transfer recently failed verification for a reason that registering it again
+		/// won't fix, see `FailedTransfers`.
+		TransferVerificationFailed,
+
+		/// The transfer isn't being re-verified.
+		ReverificationNotPending,
+
+		/// The transfer's re-verification window isn't over, it can only be revoked until then.
+		ReverificationNotDue,
+
+		/// The deal order's transfer was reorged out of its chain, see `FlaggedDealOrders`.
+		DealOrderFlagged,
+
+		/// The hash of this block has already been attested.
+		HeaderAlreadyAttested,
+
+		/// The hash of this block is already being looked up.
+		HeaderAttestationAlreadyPending,
+
+		/// The proof is against a header that wasn't attested, request its attestation first.
+		HeaderNotAttested,
+
+		/// The transfer proof is malformed or doesn't lead to the header's roots.
+		InvalidTransferProof,
+
+		/// The proven transaction isn't the transfer, or it failed.
+		TransferProofMismatch,
+
+		/// There is no pending task with this id.
+		TaskNotPending,
+
+		/// `MaxAttestedHeadersPerBlock` headers were already attested in this block.
+		TooManyAttestedHeaders,
+
+		/// `MaxCancelledTransfersPerBlock` transfers were already cancelled in this block.
+		TooManyCancelledTransfers,
 	}
 
 	#[pallet::genesis_config]
 	pub struct GenesisConfig<T: Config> {
 		pub legacy_wallets: Vec<(LegacySighash, T::Balance)>,
 		pub legacy_balance_keeper: Option<T::AccountId>,
+		pub legacy_signing_deadline: Option<T::BlockNumber>,
 	}
 
 	#[cfg(feature = "std")]
 	impl<T: Config> Default for GenesisConfig<T> {
 		fn default() -> Self {
-			Self { legacy_wallets: Vec::new(), legacy_balance_keeper: None }
+			Self {
+				legacy_wallets: Vec::new(),
+				legacy_balance_keeper: None,
+				legacy_signing_deadline: Some(helpers::signing::LEGACY_SIGNING_GRACE_PERIOD.into()),
+			}
 		}
 	}
 
@@ -554,6 +793,7 @@ pub mod pallet {
 			if let Some(acct) = &self.legacy_balance_keeper {
 				LegacyBalanceKeeper::<T>::put(acct.clone());
 			}
+			LegacySigningDeadline::<T>::set(self.legacy_signing_deadline);
 		}
 	}
 
@@ -621,6 +861,9 @@ pub mod pallet {
 			}
 
 			CleanupState::<T>::put(current);
+			let pruned = Self::prune_failed_transfers(block_number)
+				.saturating_add(Self::prune_attested_headers(block_number))
+				.saturating_add(Self::prune_cancelled_transfers(block_number));
 
 			log::debug!(
 				"Done, cleaned up {} ask orders, {} bid orders, {} offers",
@@ -630,6 +873,7 @@ pub mod pallet {
 			);
 
 			<T as Config>::WeightInfo::on_initialize(ask_count, bid_count, offer_count, 0, 0)
+				.saturating_add(pruned)
 		}
 
 		fn on_runtime_upgrade() -> Weight {
@@ -683,25 +927,33 @@ pub mod pallet {
 			// TODO: Why do we hash twice? I presume signed blake2 digest should be sufficient.
 			// The signing happens here: https://github.com/gluwa/creditcoin/blob/d1918252b27069afccbca290f35e4ecd8fce0640/creditcoin-js/src/utils.ts#L9
 			let who = ensure_signed(origin)?;
-			let message = sp_io::hashing::sha2_256(who.encode().as_slice());
-			// Prep message for public key recovery/extraction
-			let message = &sp_io::hashing::blake2_256(message.as_ref());
-
-			// Extract public key of keypair used to sign the address of the caller
 			let signature = <[u8; 65]>::from(ownership_proof);
-			let raw_pubkey = secp256k1_ecdsa_recover_compressed(&signature, message)
-				.map_err(|_| Error::<T>::InvalidSignature)?;
-
-			// Build the external address from the public key
-			let recreated_address = helpers::generate_external_address(
-				&blockchain,
-				&address,
-				sp_core::ecdsa::Public::from_raw(raw_pubkey),
-			)
-			.ok_or(Error::<T>::AddressFormatNotSupported)?;
-			// Check if external address of keypair used to sign AccountID
-			// is the same one mentioned in this call to register_address
-			ensure!(recreated_address == address, Error::<T>::OwnershipNotSatisfied);
+
+			helpers::verify_signed_payload::<T>(
+				helpers::SigningDomain::RegisterAddress,
+				who.encode().as_slice(),
+				|payload| {
+					let message = sp_io::hashing::sha2_256(payload);
+					// Prep message for public key recovery/extraction
+					let message = &sp_io::hashing::blake2_256(message.as_ref());
+
+					// Extract public key of keypair used to sign the address of the caller
+					let raw_pubkey = secp256k1_ecdsa_recover_compressed(&signature, message)
+						.map_err(|_| Error::<T>::InvalidSignature)?;
+
+					// Build the external address from the public key
+					let recreated_address = helpers::generate_external_address(
+						&blockchain,
+						&address,
+						sp_core::ecdsa::Public::from_raw(raw_pubkey),
+					)
+					.ok_or(Error::<T>::AddressFormatNotSupported)?;
+					// Check if external address of keypair used to sign AccountID
+					// is the same one mentioned in this call to register_address
+					ensure!(recreated_address == address, Error::<T>::OwnershipNotSatisfied);
+					Ok(())
+				},
+			)?;
 
 			let address_id = AddressId::new::<T>(&blockchain, &address);
 
@@ -904,13 +1156,20 @@ pub mod pallet {
 		}
 
 		#[pallet::call_index(6)]
-		#[pallet::weight(<T as Config>::WeightInfo::lock_deal_order())]
+		// `lock_deal_order` predates flagging deal orders: the flag (r:1) comes on top
+		#[pallet::weight(<T as Config>::WeightInfo::lock_deal_order()
+			.saturating_add(T::DbWeight::get().reads(1)))]
 		pub fn lock_deal_order(
 			origin: OriginFor<T>,
 			deal_order_id: DealOrderId<T::BlockNumber, T::Hash>,
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
 
+			ensure!(
+				!FlaggedDealOrders::<T>::contains_key(&deal_order_id),
+				Error::<T>::DealOrderFlagged
+			);
+
 			DealOrders::<T>::try_mutate(
 				deal_order_id.expiration(),
 				deal_order_id.hash(),
@@ -1007,10 +1266,17 @@ pub mod pallet {
 				.chain(terms.encode())
 				.collect::<Vec<u8>>();
 
-			ensure!(
-				borrower_signature.verify(message.as_slice(), &borrower_account),
-				Error::<T>::InvalidSignature
-			);
+			helpers::verify_signed_payload::<T>(
+				helpers::SigningDomain::RegisterDealOrder,
+				message.as_slice(),
+				|payload| {
+					ensure!(
+						borrower_signature.verify(payload, &borrower_account),
+						Error::<T>::InvalidSignature
+					);
+					Ok(())
+				},
+			)?;
 
 			let borrower = Self::get_address(&borrower_address_id)?;
 			ensure!(borrower.owner == borrower_account, Error::<T>::NotAddressOwner);
@@ -1091,7 +1357,9 @@ pub mod pallet {
 		}
 
 		#[pallet::call_index(9)]
-		#[pallet::weight(<T as Config>::WeightInfo::close_deal_order())]
+		// `close_deal_order` predates flagging deal orders: the flag (r:1) comes on top
+		#[pallet::weight(<T as Config>::WeightInfo::close_deal_order()
+			.saturating_add(T::DbWeight::get().reads(1)))]
 		pub fn close_deal_order(
 			origin: OriginFor<T>,
 			deal_order_id: DealOrderId<T::BlockNumber, T::Hash>,
@@ -1099,6 +1367,11 @@ pub mod pallet {
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
 
+			ensure!(
+				!FlaggedDealOrders::<T>::contains_key(&deal_order_id),
+				Error::<T>::DealOrderFlagged
+			);
+
 			Self::try_mutate_deal_order_and_transfer(
 				&deal_order_id,
 				&transfer_id,
@@ -1234,6 +1507,7 @@ pub mod pallet {
 						from: deal_order.lender_address_id.clone(),
 						to: deal_order.lender_address_id.clone(),
 						timestamp: Some(Self::timestamp()),
+						evidence: None,
 					};
 					let fake_transfer_id =
 						TransferId::new::<T>(&fake_transfer.blockchain, &fake_transfer.tx_id);
@@ -1250,31 +1524,96 @@ pub mod pallet {
 
 		#[transactional]
 		#[pallet::call_index(16)]
-		#[pallet::weight(<T as Config>::WeightInfo::persist_transfer())]
+		// `persist_transfer` predates attesting outputs: the task's lifetime, kind stats and
+		// cancellation, the authority count and the assignment's seed block (r:5 w:4) come on top
+		#[pallet::weight(<T as Config>::WeightInfo::persist_transfer()
+			.saturating_add(T::DbWeight::get().reads_writes(5, 4))
+			.saturating_add(T::TaskScheduler::max_liveness_weight()))]
 		pub fn persist_task_output(
 			origin: OriginFor<T>,
 			deadline: T::BlockNumber,
 			task_output: TaskOutput<T::AccountId, T::BlockNumber, T::Hash, T::Moment>,
+			assignment: Option<VrfAssignment>,
 		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
 
 			ensure!(T::TaskScheduler::is_authority(&who), Error::<T>::InsufficientAuthority);
+			ensure!(T::TaskScheduler::is_bonded(&who), Error::<T>::InsufficientBond);
 
-			let (task_id, event) = match task_output {
+			match &task_output {
 				TaskOutput::VerifyTransfer(id, transfer) => {
 					ensure!(
-						!Transfers::<T>::contains_key(&id),
+						!Transfers::<T>::contains_key(id),
 						non_paying_error(Error::<T>::TransferAlreadyRegistered)
 					);
+					Self::ensure_current_transfer_task(id, &deadline, Some(transfer))
+						.map_err(non_paying_error)?;
+				},
+				TaskOutput::VerifyContractOwnership(id, _) => ensure!(
+					!Addresses::<T>::contains_key(id),
+					non_paying_error(Error::<T>::AddressAlreadyRegistered)
+				),
+				TaskOutput::ReverifyTransfer(id, outcome) => {
+					let pending: TransferReverificationFor<T> =
+						T::TaskScheduler::find(&id.reverification_id::<T>())
+							.and_then(|(_, task)| task.try_into().ok())
+							.ok_or_else(|| {
+								non_paying_error(Error::<T>::ReverificationNotPending)
+							})?;
+					if let Reverification::Confirmed(_) = outcome {
+						ensure!(
+							Self::block_number() >= pending.due,
+							non_paying_error(Error::<T>::ReverificationNotDue)
+						);
+					}
+				},
+				TaskOutput::AttestHeader(id, _) => {
+					ensure!(
+						!AttestedHeaders::<T>::contains_key(id),
+						non_paying_error(Error::<T>::HeaderAlreadyAttested)
+					);
+					ensure!(
+						Self::can_attest_header(),
+						non_paying_error(Error::<T>::TooManyAttestedHeaders)
+					);
+				},
+			}
 
-					let mut transfer = transfer;
-					transfer.block = frame_system::Pallet::<T>::block_number();
+			let task_id = task_output.task_id::<T>();
+			ensure!(
+				T::TaskScheduler::is_assigned(&task_id, &who, assignment.as_ref()),
+				non_paying_error(Error::<T>::NotAssigned)
+			);
+			let output_hash = T::Hashing::hash_of(&task_output);
+			let agreeing = match T::TaskScheduler::attest(&task_id, &who, output_hash) {
+				Attestation::Reached { agreeing } => agreeing,
+				Attestation::Pending { votes, threshold } => {
+					log::debug!("Task {task_id:?} has {votes} of {threshold} votes");
+					return Ok(PostDispatchInfo { actual_weight: None, pays_fee: Pays::No });
+				},
+				Attestation::AlreadyVoted => {
+					return Err(non_paying_error(Error::<T>::AlreadyAttested));
+				},
+			};
 
-					Transfers::<T>::insert(&id, transfer);
-					(id.clone().into_inner(), Event::<T>::TransferVerified(id))
+			let (task_id, event) = match task_output {
+				TaskOutput::VerifyTransfer(id, transfer) => {
+					(id.clone().into_inner(), Self::store_verified_transfer(id, transfer))
+				},
+				TaskOutput::VerifyContractOwnership(id, address) => {
+					Addresses::<T>::insert(&id, address.clone());
+					(id.clone().into_inner(), Event::<T>::AddressRegistered(id, address))
+				},
+				TaskOutput::ReverifyTransfer(id, outcome) => {
+					(id.reverification_id::<T>(), Self::settle_reverification(id, outcome))
+				},
+				TaskOutput::AttestHeader(id, hash) => {
+					Self::record_attested_header(&id, hash);
+					(id.clone().into_inner(), Event::<T>::HeaderAttested(id, hash))
 				},
 			};
 			T::TaskScheduler::remove(&deadline, &task_id);
+			Self::reward_agreeing(&agreeing);
 
 			Self::deposit_event(event);
 
@@ -1282,30 +1621,68 @@ pub mod pallet {
 		}
 
 		#[pallet::call_index(17)]
-		#[pallet::weight(<T as Config>::WeightInfo::fail_transfer())]
+		// `fail_transfer` predates recording failures and attesting them: the failure, the
+		// task's lifetime and cancellation, the authority count and the assignment's seed block
+		// (r:5 w:3) come on top
+		#[pallet::weight(<T as Config>::WeightInfo::fail_transfer()
+			.saturating_add(T::DbWeight::get().reads_writes(5, 3))
+			.saturating_add(T::TaskScheduler::max_liveness_weight()))]
 		pub fn fail_task(
 			origin: OriginFor<T>,
 			deadline: T::BlockNumber,
 			task_id: TaskId<T::Hash>,
 			cause: VerificationFailureCause,
+			assignment: Option<VrfAssignment>,
 		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
 
 			ensure!(T::TaskScheduler::is_authority(&who), Error::<T>::InsufficientAuthority);
+			ensure!(T::TaskScheduler::is_bonded(&who), Error::<T>::InsufficientBond);
 
-			let (task_id, event) = match task_id {
+			let (id, event) = match &task_id {
 				TaskId::VerifyTransfer(transfer_id) => {
 					ensure!(
-						!Transfers::<T>::contains_key(&transfer_id),
+						!Transfers::<T>::contains_key(transfer_id),
 						Error::<T>::TransferAlreadyRegistered
 					);
+					Self::ensure_current_transfer_task(transfer_id, &deadline, None)?;
 					(
 						transfer_id.clone().into_inner(),
-						Event::<T>::TransferFailedVerification(transfer_id, cause),
+						Event::<T>::TransferFailedVerification(transfer_id.clone(), cause),
+					)
+				},
+				TaskId::VerifyContractOwnership(address_id) => {
+					ensure!(
+						!Addresses::<T>::contains_key(address_id),
+						Error::<T>::AddressAlreadyRegistered
+					);
+					(
+						address_id.clone().into_inner(),
+						Event::<T>::AddressFailedVerification(address_id.clone(), cause),
 					)
 				},
 			};
-			T::TaskScheduler::remove(&deadline, &task_id);
+			ensure!(T::TaskScheduler::is_pending(&id), Error::<T>::TaskNotPending);
+			ensure!(
+				T::TaskScheduler::is_assigned(&id, &who, assignment.as_ref()),
+				Error::<T>::NotAssigned
+			);
+			let output_hash = T::Hashing::hash_of(&(id, cause));
+			let agreeing = match T::TaskScheduler::attest(&id, &who, output_hash) {
+				Attestation::Reached { agreeing } => agreeing,
+				Attestation::Pending { votes, threshold } => {
+					log::debug!("Failure of task {id:?} has {votes} of {threshold} votes");
+					return Ok(PostDispatchInfo { actual_weight: None, pays_fee: Pays::No });
+				},
+				Attestation::AlreadyVoted => return Err(Error::<T>::AlreadyAttested.into()),
+			};
+
+			if let TaskId::VerifyTransfer(transfer_id) = &task_id {
+				Self::record_failed_transfer(transfer_id, cause, Some(who.clone()));
+				CancelledTransfers::<T>::remove(transfer_id);
+			}
+			T::TaskScheduler::remove(&deadline, &id);
+			Self::reward_agreeing(&agreeing);
 			Self::deposit_event(event);
 
 			Ok(PostDispatchInfo { actual_weight: None, pays_fee: Pays::No })
@@ -1345,8 +1722,12 @@ pub mod pallet {
 		/// Registers an address on an external blockchain as the property of an onchain address.
 		/// To prove ownership, a signature is provided. To create the signature, the public key of the external address is used to sign a hash of the account_id of whoever is submitting this transaction.
 		/// The signature type allows the caller to specify if this address was signed using the older an insecure EthSign method or the new PersonalSign method. See here for details https://docs.metamask.io/wallet/how-to/sign-data/
+		/// Smart contract wallets use an ERC-1271 proof instead. It is checked offchain by the authorities and the address is only registered once that check succeeds.
 		#[pallet::call_index(22)]
-		#[pallet::weight(<T as Config>::WeightInfo::register_address_v2())]
+		#[pallet::weight(match ownership_proof {
+			OwnershipProof::Erc1271(_) => <T as Config>::WeightInfo::register_contract_address(),
+			_ => <T as Config>::WeightInfo::register_address_v2(),
+		})]
 		pub fn register_address_v2(
 			origin: OriginFor<T>,
 			blockchain: Blockchain,
@@ -1367,35 +1748,202 @@ pub mod pallet {
 				Error::<T>::MalformedExternalAddress
 			);
 
-			match helpers::try_extract_address::<T>(ownership_proof, account, &blockchain, &address)
-			{
-				Ok(recreated_address) => {
+			if let OwnershipProof::Erc1271(signature) = ownership_proof {
+				return Self::register_contract_address_internal(
+					who, blockchain, address, signature,
+				);
+			}
+
+			helpers::verify_signed_payload::<T>(
+				helpers::SigningDomain::RegisterAddressV2,
+				account,
+				|payload| {
+					let recreated_address = helpers::try_extract_address::<T>(
+						ownership_proof.clone(),
+						payload,
+						&blockchain,
+						&address,
+					)?;
 					// Check if external address of keypair used to sign AccountID
 					// is the same one mentioned in this call to register_address
 					ensure!(recreated_address == address, Error::<T>::OwnershipNotSatisfied);
-					let address_id = AddressId::new::<T>(&blockchain, &address);
-
-					if let Ok(account_id) = Addresses::<T>::try_get(&address_id) {
-						// Already registered, let's figure out who owns it so we can
-						// return a nice error
-						if who == account_id.owner {
-							fail!(Error::<T>::AddressAlreadyRegisteredByCaller);
-						}
-						fail!(Error::<T>::AddressAlreadyRegistered);
-					}
-
-					let entry = Address { blockchain, value: address, owner: who };
-					Self::deposit_event(Event::<T>::AddressRegistered(
-						address_id.clone(),
-						entry.clone(),
-					));
-					<Addresses<T>>::insert(address_id, entry);
 					Ok(())
 				},
-				Err(e) => {
-					fail!(e)
-				},
+			)?;
+
+			let address_id = AddressId::new::<T>(&blockchain, &address);
+
+			if let Ok(account_id) = Addresses::<T>::try_get(&address_id) {
+				// Already registered, let's figure out who owns it so we can
+				// return a nice error
+				if who == account_id.owner {
+					fail!(Error::<T>::AddressAlreadyRegisteredByCaller);
+				}
+				fail!(Error::<T>::AddressAlreadyRegistered);
 			}
+
+			let entry = Address { blockchain, value: address, owner: who };
+			Self::deposit_event(Event::<T>::AddressRegistered(address_id.clone(), entry.clone()));
+			<Addresses<T>>::insert(address_id, entry);
+			Ok(())
+		}
+
+		/// Sets the last block at which signatures over legacy payloads are still accepted by
+		/// `register_address`, `register_address_v2` and `register_deal_order`.
+		/// Passing `None` keeps accepting them indefinitely.
+		#[pallet::call_index(23)]
+		#[pallet::weight(<T as Config>::WeightInfo::set_legacy_signing_deadline())]
+		pub fn set_legacy_signing_deadline(
+			origin: OriginFor<T>,
+			deadline: Option<T::BlockNumber>,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+
+			LegacySigningDeadline::<T>::set(deadline);
+			Self::deposit_event(Event::<T>::LegacySigningDeadlineSet(deadline));
+
+			Ok(())
+		}
+
+		/// Sets how many authorities must submit the same output for a task before it is
+		/// persisted. It is capped by the number of authorities.
+		#[pallet::call_index(24)]
+		#[pallet::weight(<T as Config>::WeightInfo::set_attestation_threshold())]
+		pub fn set_attestation_threshold(origin: OriginFor<T>, threshold: u32) -> DispatchResult {
+			ensure_root(origin)?;
+
+			ensure!(threshold > 0, Error::<T>::InvalidAttestationThreshold);
+			T::TaskScheduler::set_attestation_threshold(threshold);
+
+			Ok(())
+		}
+
+		/// Reserves `amount` of the caller's funds as its authority bond. Authorities need a
+		/// bond of at least `AuthorityBond` to submit task outputs.
+		#[pallet::call_index(25)]
+		#[pallet::weight(<T as Config>::WeightInfo::bond_authority())]
+		pub fn bond_authority(origin: OriginFor<T>, amount: BalanceFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			T::TaskScheduler::bond(&who, amount)
+		}
+
+		/// Releases the caller's bond once it is no longer an authority.
+		#[pallet::call_index(26)]
+		#[pallet::weight(<T as Config>::WeightInfo::unbond_authority())]
+		pub fn unbond_authority(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			T::TaskScheduler::unbond(&who)
+		}
+
+		/// Slashes up to `amount` of an authority's bond after governance found its attestation
+		/// on `task_id` to be wrong, e.g. by reviewing conflicting votes.
+		#[pallet::call_index(27)]
+		#[pallet::weight(<T as Config>::WeightInfo::slash_authority())]
+		pub fn slash_authority(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			task_id: T::Hash,
+			amount: BalanceFor<T>,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+
+			T::TaskScheduler::slash(&who, &task_id, amount);
+
+			Ok(())
+		}
+
+		/// Replaces the authority `old` with `new` at once, moving its bond over. Besides root,
+		/// `old` itself can rotate by providing `new`'s signature over the domain-separated
+		/// encoding of `(old, new)`.
+		#[transactional]
+		#[pallet::call_index(28)]
+		#[pallet::weight(<T as Config>::WeightInfo::rotate_authority())]
+		pub fn rotate_authority(
+			origin: OriginFor<T>,
+			old: T::AccountId,
+			new: T::AccountId,
+			new_key_signature: Option<T::SignerSignature>,
+		) -> DispatchResultWithPostInfo {
+			if ensure_root(origin.clone()).is_err() {
+				let who = ensure_signed(origin)?;
+				ensure!(who == old, DispatchError::BadOrigin);
+
+				let payload = helpers::signing::domain_separated_payload::<T>(
+					helpers::SigningDomain::RotateAuthority,
+					&(&old, &new).encode(),
+				);
+				let signed_by_new =
+					new_key_signature.map_or(false, |sig| sig.verify(payload.as_slice(), &new));
+				ensure!(signed_by_new, Error::<T>::InvalidSignature);
+			}
+
+			ensure!(T::TaskScheduler::is_authority(&old), Error::<T>::NotAnAuthority);
+			ensure!(!T::TaskScheduler::is_authority(&new), Error::<T>::AlreadyAuthority);
+
+			T::TaskScheduler::rotate_authority(&old, &new)?;
+
+			Ok(PostDispatchInfo { actual_weight: None, pays_fee: Pays::No })
+		}
+
+		/// Withdraws a transfer that is still being verified, so that it can be registered
+		/// again with the right details. Only the account that registered it can cancel it.
+		#[transactional]
+		#[pallet::call_index(29)]
+		#[pallet::weight(<T as Config>::WeightInfo::cancel_transfer())]
+		pub fn cancel_transfer(
+			origin: OriginFor<T>,
+			transfer_id: TransferId<T::Hash>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let task_id = transfer_id.clone().into_inner();
+			let pending: UnverifiedTransferFor<T> = T::TaskScheduler::find(&task_id)
+				.and_then(|(_, task)| task.try_into().ok())
+				.ok_or(Error::<T>::NonExistentTransfer)?;
+			ensure!(pending.transfer.account_id == who, Error::<T>::TransferAccountMismatch);
+
+			Self::record_cancelled_transfer(&transfer_id)?;
+			T::TaskScheduler::cancel(&task_id);
+
+			Self::deposit_event(Event::<T>::TransferCancelled(transfer_id));
+			Ok(())
+		}
+
+		/// Asks the authorities for the hash of the external block at `number`, once it has
+		/// enough confirmations. Transfers in the block can then be proven with
+		/// `verify_transfer_proof`.
+		#[pallet::call_index(30)]
+		#[pallet::weight(<T as Config>::WeightInfo::request_header_attestation())]
+		pub fn request_header_attestation(
+			origin: OriginFor<T>,
+			blockchain: Blockchain,
+			number: u64,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+
+			Self::request_header_attestation_internal(blockchain, number)
+		}
+
+		/// Verifies a pending ethless transfer onchain, from Merkle-Patricia proofs of its
+		/// transaction and receipt against an attested block header. Anyone can submit the
+		/// proof, the transfer stays with the account that registered it.
+		#[transactional]
+		#[pallet::call_index(31)]
+		// hashing the proof's nodes grows with its length, like hashing a remark
+		#[pallet::weight(<T as Config>::WeightInfo::verify_transfer_proof()
+			.saturating_add(<T as frame_system::Config>::SystemWeightInfo::remark_with_event(
+				proof.encoded_size() as u32,
+			)))]
+		pub fn verify_transfer_proof(
+			origin: OriginFor<T>,
+			transfer_id: TransferId<T::Hash>,
+			proof: TransferProof,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+
+			Self::verify_transfer_proof_internal(transfer_id, proof)
 		}
 	}
 }
@@ -12,6 +12,7 @@ pub(crate) trait Migrate {
 }
 
 mod v1;
+mod v10;
 mod v2;
 mod v3;
 mod v4;
@@ -19,6 +20,7 @@ mod v5;
 pub(crate) mod v6;
 pub mod v7;
 mod v8;
+mod v9;
 
 pub(crate) fn migrate<T: Config>() -> Weight {
 	let version = StorageVersion::get::<Pallet<T>>();
@@ -33,6 +35,8 @@ pub(crate) fn migrate<T: Config>() -> Weight {
 		&v6::Migration::<T>::new(),
 		&v7::Migration::<T>::new(),
 		&v8::Migration::<T>::new(),
+		&v9::Migration::<T>::new(),
+		&v10::Migration::<T>::new(),
 	];
 
 	for (idx, &calls) in callbacks.iter().enumerate() {
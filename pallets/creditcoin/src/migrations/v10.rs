@@ -0,0 +1,233 @@
+// `evidence` added to `Transfer`, in `Transfers` and in the pending verification tasks
+
+use super::Vec;
+use super::{AccountIdOf, BlockNumberOf, HashOf, Migrate, MomentOf, PhantomData};
+use crate::{
+	AddressId, Blockchain, Config, ExternalAddress, ExternalAmount, ExternalTxId, OrderId,
+	Transfer, TransferKind, UnverifiedContractOwnership, UnverifiedTransfer,
+};
+use frame_support::weights::Weight;
+use frame_support::{pallet_prelude::*, storage_alias, traits::Get, Identity};
+use parity_scale_codec::{Decode, Encode};
+
+#[derive(Clone, Encode, Decode)]
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub struct OldTransfer<AccountId, BlockNum, Hash, Moment> {
+	pub blockchain: Blockchain,
+	pub kind: TransferKind,
+	pub from: AddressId<Hash>,
+	pub to: AddressId<Hash>,
+	pub order_id: OrderId<BlockNum, Hash>,
+	pub amount: ExternalAmount,
+	pub tx_id: ExternalTxId,
+	pub block: BlockNum,
+	pub is_processed: bool,
+	pub account_id: AccountId,
+	pub timestamp: Option<Moment>,
+}
+
+impl<AccountId, BlockNum, Hash, Moment> From<OldTransfer<AccountId, BlockNum, Hash, Moment>>
+	for Transfer<AccountId, BlockNum, Hash, Moment>
+{
+	fn from(old: OldTransfer<AccountId, BlockNum, Hash, Moment>) -> Self {
+		Transfer {
+			blockchain: old.blockchain,
+			kind: old.kind,
+			from: old.from,
+			to: old.to,
+			order_id: old.order_id,
+			amount: old.amount,
+			tx_id: old.tx_id,
+			block: old.block,
+			is_processed: old.is_processed,
+			account_id: old.account_id,
+			timestamp: old.timestamp,
+			evidence: None,
+		}
+	}
+}
+
+#[derive(Clone, Encode, Decode)]
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub struct OldUnverifiedTransfer<AccountId, BlockNum, Hash, Moment> {
+	pub transfer: OldTransfer<AccountId, BlockNum, Hash, Moment>,
+	pub from_external: ExternalAddress,
+	pub to_external: ExternalAddress,
+	pub deadline: BlockNum,
+}
+
+/// The runtime's task type at this version, the indices have to match its `construct_tasks!`.
+#[derive(Clone, Encode, Decode)]
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub enum OldTask<AccountId, BlockNum, Hash, Moment> {
+	#[codec(index = 0)]
+	VerifyTransfer(OldUnverifiedTransfer<AccountId, BlockNum, Hash, Moment>),
+	#[codec(index = 1)]
+	VerifyContractOwnership(UnverifiedContractOwnership<AccountId, BlockNum>),
+}
+
+#[storage_alias]
+type Transfers<T: Config> = StorageMap<
+	crate::Pallet<T>,
+	Identity,
+	crate::TransferId<HashOf<T>>,
+	OldTransfer<AccountIdOf<T>, BlockNumberOf<T>, HashOf<T>, MomentOf<T>>,
+>;
+
+mod new {
+	use super::*;
+
+	#[derive(Clone, Encode, Decode)]
+	#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+	pub enum Task<AccountId, BlockNum, Hash, Moment> {
+		#[codec(index = 0)]
+		VerifyTransfer(UnverifiedTransfer<AccountId, BlockNum, Hash, Moment>),
+		#[codec(index = 1)]
+		VerifyContractOwnership(UnverifiedContractOwnership<AccountId, BlockNum>),
+	}
+
+	#[storage_alias]
+	pub type PendingTasks<T: Config> = StorageDoubleMap<
+		TaskScheduler,
+		Identity,
+		BlockNumberOf<T>,
+		Identity,
+		HashOf<T>,
+		Task<AccountIdOf<T>, BlockNumberOf<T>, HashOf<T>, MomentOf<T>>,
+	>;
+}
+
+pub(super) struct Migration<Runtime>(PhantomData<Runtime>);
+
+impl<Runtime: Config> Migration<Runtime> {
+	pub(super) fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T: Config> Migrate for Migration<T> {
+	fn pre_upgrade(&self) -> Vec<u8> {
+		let transfers = Transfers::<T>::iter_keys().count() as u64;
+		transfers.encode()
+	}
+
+	fn migrate(&self) -> Weight {
+		let mut weight: Weight = Weight::zero();
+		let weight_each = T::DbWeight::get().reads_writes(1, 1);
+
+		crate::Transfers::<T>::translate::<
+			OldTransfer<AccountIdOf<T>, BlockNumberOf<T>, HashOf<T>, MomentOf<T>>,
+			_,
+		>(|_, old| {
+			weight = weight.saturating_add(weight_each);
+			Some(old.into())
+		});
+
+		new::PendingTasks::<T>::translate::<
+			OldTask<AccountIdOf<T>, BlockNumberOf<T>, HashOf<T>, MomentOf<T>>,
+			_,
+		>(|_, _, old| {
+			weight = weight.saturating_add(weight_each);
+			Some(match old {
+				OldTask::VerifyTransfer(pending) => new::Task::VerifyTransfer(UnverifiedTransfer {
+					transfer: pending.transfer.into(),
+					from_external: pending.from_external,
+					to_external: pending.to_external,
+					deadline: pending.deadline,
+				}),
+				OldTask::VerifyContractOwnership(pending) => {
+					new::Task::VerifyContractOwnership(pending)
+				},
+			})
+		});
+
+		weight
+	}
+
+	fn post_upgrade(&self, blob: Vec<u8>) {
+		assert_eq!(
+			StorageVersion::get::<crate::Pallet<T>>(),
+			10,
+			"expected storage version to be 10 after migrations complete"
+		);
+		let transfers = u64::decode(&mut blob.as_slice()).expect("pre_upgrade encodes a u64");
+		assert_eq!(crate::Transfers::<T>::iter_values().count() as u64, transfers);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		mock::{ExtBuilder, Test},
+		types::test::create_unverified_transfer,
+		TransferId,
+	};
+	use pallet_offchain_task_scheduler::tasks::TaskV2;
+
+	#[storage_alias]
+	type PendingTasks<T: Config> = StorageDoubleMap<
+		TaskScheduler,
+		Identity,
+		BlockNumberOf<T>,
+		Identity,
+		HashOf<T>,
+		OldTask<AccountIdOf<T>, BlockNumberOf<T>, HashOf<T>, MomentOf<T>>,
+	>;
+
+	fn old_transfer<AccountId, BlockNum, Hash, Moment>(
+		transfer: Transfer<AccountId, BlockNum, Hash, Moment>,
+	) -> OldTransfer<AccountId, BlockNum, Hash, Moment> {
+		OldTransfer {
+			blockchain: transfer.blockchain,
+			kind: transfer.kind,
+			from: transfer.from,
+			to: transfer.to,
+			order_id: transfer.order_id,
+			amount: transfer.amount,
+			tx_id: transfer.tx_id,
+			block: transfer.block,
+			is_processed: transfer.is_processed,
+			account_id: transfer.account_id,
+			timestamp: transfer.timestamp,
+		}
+	}
+
+	#[test]
+	fn transfers_migrate_without_evidence() {
+		ExtBuilder::default().build_and_execute(|| {
+			let pending = create_unverified_transfer();
+			let transfer = Transfer { timestamp: Some(42), ..pending.transfer };
+			let id = TransferId::new::<Test>(&transfer.blockchain, &transfer.tx_id);
+
+			Transfers::<Test>::insert(&id, old_transfer(transfer.clone()));
+
+			super::Migration::<Test>::new().migrate();
+
+			assert_eq!(crate::Transfers::<Test>::get(&id), Some(transfer));
+		});
+	}
+
+	#[test]
+	fn pending_transfer_tasks_migrate() {
+		ExtBuilder::default().build_and_execute(|| {
+			let pending = create_unverified_transfer();
+			let id = TaskV2::<Test>::to_id(&pending);
+
+			let old = OldUnverifiedTransfer {
+				transfer: old_transfer(pending.transfer.clone()),
+				from_external: pending.from_external.clone(),
+				to_external: pending.to_external.clone(),
+				deadline: pending.deadline,
+			};
+			PendingTasks::<Test>::insert(1, id, OldTask::VerifyTransfer(old));
+
+			super::Migration::<Test>::new().migrate();
+
+			assert_eq!(
+				new::PendingTasks::<Test>::get(1, id),
+				Some(new::Task::VerifyTransfer(pending))
+			);
+		});
+	}
+}
@@ -114,6 +114,7 @@ impl<T: Config> Migrate for Migration<T> {
 					is_processed: transfer.processed,
 					account_id: transfer.sighash,
 					timestamp: None,
+					evidence: None,
 				})
 			},
 		);
@@ -272,6 +273,7 @@ mod test {
 					is_processed: old_transfer.processed,
 					account_id: old_transfer.sighash,
 					timestamp: None,
+					evidence: None,
 				}
 			);
 		});
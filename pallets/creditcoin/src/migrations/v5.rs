@@ -138,6 +138,7 @@ mod tests {
 					is_processed: false,
 					account_id: test_info.lender.account_id,
 					timestamp: None,
+					evidence: None,
 				},
 				from_external: b"baba".to_vec().try_into().unwrap(),
 				to_external: b"abab".to_vec().try_into().unwrap(),
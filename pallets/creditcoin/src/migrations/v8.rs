@@ -39,10 +39,14 @@ pub type PendingTasks<T: Config> = StorageDoubleMap<
 
 mod new {
 
-	use crate::Task;
-
 	use super::*;
 
+	/// The task type at this version, before tasks were registered with the scheduler by kind.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub enum Task<AccountId, BlockNum, Hash, Moment> {
+		VerifyTransfer(UnverifiedTransfer<AccountId, BlockNum, Hash, Moment>),
+	}
+
 	#[storage_alias]
 	pub type PendingTasks<T: Config> = StorageDoubleMap<
 		TaskScheduler, // the prefix for the storage item, which is generally the name of the pallet that defines the storage. We use an identifier instead of a string here because that's what the macro expects.
@@ -98,7 +102,7 @@ impl<T: Config> Migrate for Migration<T> {
 							deadline: pending.deadline,
 						};
 
-						Some(crate::types::Task::VerifyTransfer(new))
+						Some(new::Task::VerifyTransfer(new))
 					},
 				}
 			},
@@ -112,11 +116,11 @@ impl<T: Config> Migrate for Migration<T> {
 mod tests {
 	use pallet_offchain_task_scheduler::tasks::TaskV2;
 
+	use super::new::Task;
 	use super::*;
 	use crate::{
 		mock::{self, ExtBuilder, Test},
 		types::{collect_coins::ContractType, test::create_unverified_transfer},
-		Task,
 	};
 
 	#[frame_support::storage_alias]
@@ -173,8 +177,10 @@ mod tests {
 
 			super::Migration::<Test>::new().migrate();
 
-			let Task::VerifyTransfer(migrated_pending) =
-				new::PendingTasks::<Test>::get(1, id).unwrap();
+			let Some(Task::VerifyTransfer(migrated_pending)) = new::PendingTasks::<Test>::get(1, id)
+			else {
+				panic!("expected a migrated VerifyTransfer task");
+			};
 
 			assert_eq!(pending, migrated_pending);
 		});
@@ -0,0 +1,75 @@
+// chains upgraded to domain-separated signing get a deadline for legacy payloads
+use super::Vec;
+use super::{Migrate, PhantomData};
+use crate::{helpers::signing::LEGACY_SIGNING_GRACE_PERIOD, Config, LegacySigningDeadline};
+use frame_support::{pallet_prelude::*, traits::Get};
+use frame_system::Pallet as System;
+use sp_runtime::traits::Saturating;
+
+pub(super) struct Migration<Runtime>(PhantomData<Runtime>);
+
+impl<Runtime: Config> Migration<Runtime> {
+	pub(super) fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T: Config> Migrate for Migration<T> {
+	fn pre_upgrade(&self) -> Vec<u8> {
+		Vec::new()
+	}
+
+	fn migrate(&self) -> Weight {
+		let weight = T::DbWeight::get().reads(1);
+		if LegacySigningDeadline::<T>::exists() {
+			return weight;
+		}
+
+		let deadline =
+			System::<T>::block_number().saturating_add(LEGACY_SIGNING_GRACE_PERIOD.into());
+		LegacySigningDeadline::<T>::put(deadline);
+
+		weight.saturating_add(T::DbWeight::get().writes(1))
+	}
+
+	fn post_upgrade(&self, _blob: Vec<u8>) {
+		assert_eq!(
+			StorageVersion::get::<crate::Pallet<T>>(),
+			9,
+			"expected storage version to be 9 after migrations complete"
+		);
+		assert!(LegacySigningDeadline::<T>::exists());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mock::{ExtBuilder, System, Test};
+
+	#[test]
+	fn sets_a_deadline_after_the_grace_period() {
+		ExtBuilder::default().build_and_execute(|| {
+			System::set_block_number(100);
+			LegacySigningDeadline::<Test>::kill();
+
+			super::Migration::<Test>::new().migrate();
+
+			assert_eq!(
+				LegacySigningDeadline::<Test>::get(),
+				Some(100 + LEGACY_SIGNING_GRACE_PERIOD as u64)
+			);
+		});
+	}
+
+	#[test]
+	fn keeps_an_existing_deadline() {
+		ExtBuilder::default().build_and_execute(|| {
+			LegacySigningDeadline::<Test>::put(7);
+
+			super::Migration::<Test>::new().migrate();
+
+			assert_eq!(LegacySigningDeadline::<Test>::get(), Some(7));
+		});
+	}
+}
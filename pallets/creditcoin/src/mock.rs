@@ -1,13 +1,14 @@
 use crate::{
 	self as pallet_creditcoin,
 	ocw::rpc::{JsonRpcRequest, JsonRpcResponse},
-	Blockchain, LegacySighash,
+	Blockchain, LegacySighash, TransferEvidence,
 };
 use ethereum_types::U256;
 use frame_support::{
 	once_cell::sync::Lazy,
 	parameter_types,
 	traits::{ConstU32, ConstU64, GenesisBuild, Hooks},
+	PalletId,
 };
 use frame_system as system;
 use pallet_offchain_task_scheduler::crypto::AuthorityId;
@@ -25,7 +26,7 @@ use sp_runtime::{
 	},
 	testing::{Header, TestXt},
 	traits::{BlakeTwo256, Extrinsic as ExtrinsicT, IdentifyAccount, IdentityLookup, Verify},
-	MultiSignature, RuntimeAppPublic,
+	MultiSignature, Perquintill, RuntimeAppPublic,
 };
 pub(crate) use std::sync::Arc;
 use std::{cell::Cell, collections::HashMap};
@@ -51,7 +52,7 @@ frame_support::construct_runtime!(
 		Creditcoin: pallet_creditcoin::{Pallet, Call, Storage, Event<T>, Config<T>},
 		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
 		Timestamp: pallet_timestamp::{Pallet, Call, Storage},
-		TaskScheduler: pallet_offchain_task_scheduler::{Pallet, Storage, Event<T>},
+		TaskScheduler: pallet_offchain_task_scheduler::{Pallet, Call, Storage, Event<T>, ValidateUnsigned},
 	}
 );
 
@@ -115,18 +116,59 @@ impl pallet_creditcoin::Config for Test {
 
 	type WeightInfo = super::weights::WeightInfo<Test>;
 
+	type Task = Task;
+
 	type TaskScheduler = TaskScheduler;
 
 	type PerBlockCleanupLimit = ConstU32<CLEANUP_LIMIT>;
+	type FailedTransferTtl = ConstU64<10>;
+	type MaxFailedTransfersPerBlock = ConstU32<2>;
+	type TransferReverificationWindow = ConstU64<5>;
+	type AttestedHeaderTtl = ConstU64<10>;
+	type MaxAttestedHeadersPerBlock = ConstU32<2>;
+	type CancelledTransferTtl = ConstU64<10>;
+	type MaxCancelledTransfersPerBlock = ConstU32<2>;
+}
+
+pallet_offchain_task_scheduler::construct_tasks! {
+	pub enum Task for Test {
+		VerifyTransfer(pallet_creditcoin::UnverifiedTransferFor<Test>) = 0,
+		VerifyContractOwnership(pallet_creditcoin::UnverifiedContractOwnershipFor<Test>) = 1,
+		ReverifyTransfer(pallet_creditcoin::TransferReverificationFor<Test>) = 2,
+		AttestHeader(pallet_creditcoin::HeaderAttestation) = 3,
+	}
+}
+
+parameter_types! {
+	pub const TaskSampleSize: Perquintill = Perquintill::from_percent(100);
+	pub const TaskSchedulerPalletId: PalletId = PalletId(*b"cc/tasks");
+	pub storage TaskReward: Balance = 0;
+	pub storage AuthorityBond: Balance = 0;
 }
 
 impl pallet_offchain_task_scheduler::Config for Test {
 	type RuntimeEvent = RuntimeEvent;
 	type UnverifiedTaskTimeout = ConstU64<5>;
+	type MaxTaskLifetime = ConstU64<20>;
+	type MaxExpiriesPerBlock = ConstU32<100>;
+	type OnTaskExpired = Task;
+	type TaskSampleSize = TaskSampleSize;
+	type Currency = Balances;
+	type PalletId = TaskSchedulerPalletId;
+	type TaskReward = TaskReward;
+	type AuthorityBond = AuthorityBond;
+	type MissedTasksBeforeWarning = ConstU32<0>;
+	type MissedTasksBeforeSuspension = ConstU32<0>;
+	type MaxAuthoritiesNoted = ConstU32<10>;
+	type OffchainTimeBudget = ConstU64<10_000>;
+	type OffchainRequestBudget = ConstU32<100>;
 	type AuthorityId = AuthorityId;
 	type TaskCall = RuntimeCall;
+	type SubmissionMode = ();
+	type TaskOutputs = pallet_creditcoin::TaskOutputCalls<Self>;
+	type UnsignedPriority = ConstU64<{ u64::MAX / 2 }>;
 	type WeightInfo = pallet_offchain_task_scheduler::weights::WeightInfo<Self>;
-	type Task = pallet_creditcoin::Task<AccountId, BlockNumber, Hash, Moment>;
+	type Task = Task;
 	type Authorship = TaskScheduler;
 }
 
@@ -262,6 +304,7 @@ impl ExtBuilder {
 		let _ = crate::GenesisConfig::<Test> {
 			legacy_wallets: self.legacy_wallets,
 			legacy_balance_keeper: self.legacy_keeper,
+			..Default::default()
 		}
 		.assimilate_storage(&mut storage);
 
@@ -363,12 +406,38 @@ pub fn roll_by_with_ocw(n: BlockNumber) {
 
 // must be called in an externalities-provided environment
 pub fn set_rpc_uri(blockchain: &Blockchain, value: impl AsRef<[u8]>) {
+	set_unchecked_rpc_uri(blockchain, value.as_ref());
+	let Ok(url) = core::str::from_utf8(value.as_ref()) else {
+		return;
+	};
+	// the endpoint is trusted to serve the right chain
+	if let Some(chain_id) = blockchain.rpc_chain_id() {
+		StorageValueRef::persistent(&crate::ocw::chain_id_key(url)).set(&chain_id);
+	}
+	// requests are mocked one by one, tests of batches opt in
+	crate::ocw::set_batch_support(url, false);
+}
+
+// must be called in an externalities-provided environment
+pub fn set_unchecked_rpc_uri(blockchain: &Blockchain, value: impl AsRef<[u8]>) {
 	let mut key = Vec::from(blockchain.as_bytes());
 	key.extend(b"-rpc-uri");
 	let rpc_url_storage = StorageValueRef::persistent(&key);
 	rpc_url_storage.set(&value.as_ref());
 }
 
+// must be called in an externalities-provided environment
+pub fn set_rpc_uris(blockchain: &Blockchain, values: &[&str]) {
+	let key = crate::types::concatenate!(blockchain.as_bytes(), b"-rpc-uris");
+	StorageValueRef::persistent(&key).set(&values);
+}
+
+// must be called in an externalities-provided environment
+pub fn set_rpc_quorum(blockchain: &Blockchain, quorum: u32) {
+	let key = crate::types::concatenate!(blockchain.as_bytes(), b"-rpc-quorum");
+	StorageValueRef::persistent(&key).set(&quorum);
+}
+
 pub fn pending_rpc_request(
 	method: &str,
 	params: impl IntoIterator<Item = serde_json::Value>,
@@ -391,6 +460,37 @@ pub fn pending_rpc_request(
 	}
 }
 
+/// A JSON-RPC batch of `requests`, `(method, params)` pairs, answered with the `responses` to
+/// their methods.
+pub fn pending_rpc_batch_request(
+	requests: &[(&str, Vec<serde_json::Value>)],
+	uri: &str,
+	responses: &HashMap<String, JsonRpcResponse<serde_json::Value>>,
+) -> PendingRequest {
+	let batch: Vec<_> = requests
+		.iter()
+		.enumerate()
+		.map(|(id, (method, params))| {
+			JsonRpcRequest::new(*method, params.clone()).with_id(id as u64)
+		})
+		.collect();
+	let response: Vec<_> = requests
+		.iter()
+		.enumerate()
+		.map(|(id, (method, _))| JsonRpcResponse { id: id as u64, ..responses[*method].clone() })
+		.collect();
+	PendingRequest {
+		method: "POST".into(),
+		uri: uri.into(),
+		headers: vec![("Content-Type".into(), "application/json".into())],
+		body: serde_json::to_vec(&batch).unwrap(),
+		response: Some(serde_json::to_vec(&response).unwrap()),
+		response_headers: vec![("Content-Type".into(), "application/json".into())],
+		sent: true,
+		..Default::default()
+	}
+}
+
 pub(crate) static ETHLESS_RESPONSES: Lazy<HashMap<String, JsonRpcResponse<serde_json::Value>>> =
 	Lazy::new(|| serde_json::from_slice(include_bytes!("tests/ethlessTransfer.json")).unwrap());
 
@@ -474,6 +574,22 @@ pub(crate) fn get_mock_timestamp() -> u64 {
 	u64::from_str_radix(timestamp_hex.trim_start_matches("0x"), 16).unwrap()
 }
 
+pub(crate) fn get_mock_evidence() -> TransferEvidence {
+	let responses = &*ETHLESS_RESPONSES;
+	let tx = responses["eth_getTransactionByHash"].result.clone().unwrap();
+	let quantity = |value: &serde_json::Value| {
+		u64::from_str_radix(value.as_str().unwrap().trim_start_matches("0x"), 16).unwrap()
+	};
+
+	TransferEvidence {
+		block_number: quantity(&tx["blockNumber"]),
+		block_hash: serde_json::from_value(tx["blockHash"].clone()).unwrap(),
+		transaction_index: quantity(&tx["transactionIndex"]),
+		// the first log pays the fee, the second one moves the amount
+		log_index: Some(1),
+	}
+}
+
 #[extend::ext(name = PendingRequestExt)]
 pub(crate) impl Option<PendingRequest> {
 	fn set_response(&mut self, response: impl serde::Serialize) {
@@ -567,5 +683,9 @@ fn default_works() {
 
 		assert_eq!(defaults.legacy_wallets.len(), 0);
 		assert_eq!(defaults.legacy_balance_keeper, None);
+		assert_eq!(
+			defaults.legacy_signing_deadline,
+			Some(crate::helpers::signing::LEGACY_SIGNING_GRACE_PERIOD.into())
+		);
 	});
 }
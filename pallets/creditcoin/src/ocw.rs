@@ -4,7 +4,7 @@ pub(crate) mod tasks;
 
 use self::{errors::RpcUrlError, rpc::errors::RpcError};
 use super::ExternalAddress;
-use crate::{Blockchain, ExternalTxId, TransferKind};
+use crate::{types::concatenate, Blockchain, ExternalTxId, TransferKind};
 use alloc::string::String;
 pub(crate) use errors::{OffchainError, VerificationFailureCause, VerificationResult};
 use sp_runtime::offchain::storage::StorageValueRef;
@@ -24,6 +24,160 @@ impl Blockchain {
 			Err(RpcUrlError::NoValue)
 		}
 	}
+	/// All the RPC endpoints configured for this chain, in order of preference. Nodes that
+	/// only set a single `<chain>-rpc-uri` get a list with that one url.
+	pub fn rpc_urls(&self) -> OffchainResult<Vec<String>, errors::RpcUrlError> {
+		let key = concatenate!(self.as_bytes(), b"-rpc-uris");
+		match StorageValueRef::persistent(&key).get::<Vec<Vec<u8>>>()? {
+			Some(urls) if !urls.is_empty() => {
+				urls.into_iter().map(|url| Ok(String::from_utf8(url)?)).collect()
+			},
+			_ => Ok(vec![self.rpc_url()?]),
+		}
+	}
+
+	/// The number of endpoints that have to agree on a response, `None` unless more than one is
+	/// required. A quorum larger than the number of endpoints could never be reached, so it is
+	/// capped by it.
+	pub fn rpc_quorum(&self) -> Option<u32> {
+		let key = concatenate!(self.as_bytes(), b"-rpc-quorum");
+		let quorum = StorageValueRef::persistent(&key).get::<u32>().ok().flatten()?;
+		let endpoints = self.rpc_urls().map_or(1, |urls| urls.len() as u32);
+		if quorum > endpoints {
+			log::error!(
+				"RPC quorum of {quorum} for {} exceeds its {endpoints} endpoints, using {endpoints}",
+				String::from_utf8_lossy(self.as_bytes())
+			);
+		}
+		Some(quorum.min(endpoints)).filter(|&q| q > 1)
+	}
+
+	/// The chain ID the RPC endpoints of this chain have to report. The one configured for the
+	/// node takes precedence over [`Blockchain::expected_chain_id`].
+	pub fn rpc_chain_id(&self) -> Option<u64> {
+		let key = concatenate!(self.as_bytes(), b"-rpc-chain-id");
+		StorageValueRef::persistent(&key)
+			.get::<u64>()
+			.ok()
+			.flatten()
+			.or_else(|| self.expected_chain_id())
+	}
+
+	/// Like [`Blockchain::rpc_url`], but also makes sure the endpoint serves the expected chain.
+	pub fn checked_rpc_url(&self) -> OffchainResult<String> {
+		let rpc_url = self.rpc_url()?;
+		self.check_chain_id(&rpc_url)?;
+		Ok(rpc_url)
+	}
+
+	/// Makes sure `rpc_url` serves the expected chain.
+	/// The chain ID is only requested once per url, after that the cached value is used.
+	pub fn check_chain_id(&self, rpc_url: &str) -> OffchainResult<()> {
+		let Some(expected) = self.rpc_chain_id() else {
+			return Ok(());
+		};
+
+		let cache = StorageValueRef::persistent(&chain_id_key(rpc_url));
+		if let Ok(Some(chain_id)) = cache.get::<u64>() {
+			if chain_id == expected {
+				return Ok(());
+			}
+		}
+
+		let chain_id = rpc::eth_chain_id(rpc_url)?.low_u64();
+		if chain_id != expected {
+			log::error!(
+				"RPC endpoint for {} reports chain ID {chain_id}, expected {expected}",
+				String::from_utf8_lossy(self.as_bytes())
+			);
+			return Err(OffchainError::IncorrectChainId);
+		}
+		cache.set(&chain_id);
+
+		Ok(())
+	}
+
+	/// Runs `request` against the endpoints of this chain in order and returns the first result
+	/// that isn't caused by an unreachable or misconfigured endpoint.
+	pub fn with_failover<R>(
+		&self,
+		mut request: impl FnMut(&str) -> OffchainResult<R>,
+	) -> OffchainResult<R> {
+		let mut last_error = None;
+		for (i, rpc_url) in self.rpc_urls()?.iter().enumerate() {
+			match self.check_chain_id(rpc_url).and_then(|()| request(rpc_url)) {
+				Err(e) if e.is_endpoint_failure() => {
+					log::warn!(
+						"RPC endpoint #{i} for {} failed: {e:?}",
+						String::from_utf8_lossy(self.as_bytes())
+					);
+					last_error = Some(e);
+				},
+				result => return result,
+			}
+		}
+		Err(last_error.unwrap_or(OffchainError::NoRpcUrl(RpcUrlError::NoValue)))
+	}
+
+	/// Runs `request` against the endpoints of this chain until `quorum` of them return
+	/// responses that `agree` with each other, and returns those responses. Verification
+	/// failures only count when `quorum` endpoints report the same cause, so a single endpoint
+	/// can't fail a task on its own.
+	pub fn with_quorum<R>(
+		&self,
+		quorum: u32,
+		mut request: impl FnMut(&str) -> OffchainResult<R>,
+		agree: impl Fn(&R, &R) -> bool,
+	) -> OffchainResult<Vec<R>> {
+		let quorum = quorum as usize;
+		let mut responses: Vec<Vec<R>> = Vec::new();
+		let mut failures: Vec<(VerificationFailureCause, usize)> = Vec::new();
+
+		for (i, rpc_url) in self.rpc_urls()?.iter().enumerate() {
+			match self.check_chain_id(rpc_url).and_then(|()| request(rpc_url)) {
+				Ok(response) => {
+					let group = match responses.iter().position(|group| agree(&group[0], &response))
+					{
+						Some(group) => group,
+						None => {
+							responses.push(Vec::new());
+							responses.len() - 1
+						},
+					};
+					responses[group].push(response);
+					if responses[group].len() >= quorum {
+						return Ok(responses.swap_remove(group));
+					}
+				},
+				Err(OffchainError::InvalidTask(cause)) => {
+					let votes = match failures.iter_mut().find(|(c, _)| *c == cause) {
+						Some((_, votes)) => {
+							*votes += 1;
+							*votes
+						},
+						None => {
+							failures.push((cause, 1));
+							1
+						},
+					};
+					if votes >= quorum {
+						return Err(cause.into());
+					}
+				},
+				Err(e) => log::warn!(
+					"RPC endpoint #{i} for {} failed: {e:?}",
+					String::from_utf8_lossy(self.as_bytes())
+				),
+			}
+		}
+
+		log::warn!(
+			"RPC endpoints for {} did not reach a quorum of {quorum}",
+			String::from_utf8_lossy(self.as_bytes())
+		);
+		Err(OffchainError::QuorumNotReached)
+	}
+
 	pub fn supports(&self, kind: &TransferKind) -> bool {
 		match (self, kind) {
 			(
@@ -36,7 +190,29 @@ impl Blockchain {
 	}
 }
 
-const ETH_CONFIRMATIONS: u64 = 12;
+pub(crate) const ETH_CONFIRMATIONS: u64 = 12;
+
+/// Offchain storage key holding the chain ID reported by `rpc_url`.
+pub fn chain_id_key(rpc_url: &str) -> Vec<u8> {
+	concatenate!(b"chain-id/", rpc_url.as_bytes())
+}
+
+/// Offchain storage key holding whether `rpc_url` answers JSON-RPC batches.
+pub fn batch_support_key(rpc_url: &str) -> Vec<u8> {
+	concatenate!(b"rpc-batch/", rpc_url.as_bytes())
+}
+
+/// Whether requests to `rpc_url` can be batched, `None` until the endpoint has been tried.
+pub(crate) fn batch_support(rpc_url: &str) -> Option<bool> {
+	StorageValueRef::persistent(&batch_support_key(rpc_url))
+		.get::<bool>()
+		.ok()
+		.flatten()
+}
+
+pub(crate) fn set_batch_support(rpc_url: &str, supported: bool) {
+	StorageValueRef::persistent(&batch_support_key(rpc_url)).set(&supported);
+}
 
 fn parse_eth_address(address: &ExternalAddress) -> OffchainResult<rpc::Address> {
 	let address_bytes = <[u8; 20]>::try_from(address.as_slice())
@@ -46,13 +222,16 @@ fn parse_eth_address(address: &ExternalAddress) -> OffchainResult<rpc::Address>
 }
 
 fn eth_get_transaction(tx_id: &ExternalTxId, rpc_url: &str) -> OffchainResult<rpc::EthTransaction> {
-	rpc::eth_get_transaction(tx_id, rpc_url).map_err(|e| {
-		if let RpcError::NoResult = e {
-			OffchainError::InvalidTask(VerificationFailureCause::TransactionNotFound)
-		} else {
-			e.into()
-		}
-	})
+	rpc::eth_get_transaction(tx_id, rpc_url).map_err(transaction_not_found)
+}
+
+/// A transaction lookup without a result means the transaction doesn't exist.
+fn transaction_not_found(e: RpcError) -> OffchainError {
+	if let RpcError::NoResult = e {
+		OffchainError::InvalidTask(VerificationFailureCause::TransactionNotFound)
+	} else {
+		e.into()
+	}
 }
 
 #[cfg(test)]
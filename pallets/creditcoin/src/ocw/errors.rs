@@ -12,6 +12,8 @@ pub enum OffchainError {
 	InvalidTask(VerificationFailureCause),
 	NoRpcUrl(RpcUrlError),
 	RpcError(RpcError),
+	IncorrectChainId,
+	QuorumNotReached,
 }
 
 #[derive(Debug)]
@@ -19,9 +21,23 @@ pub enum SchedulerError {
 	NoRpcUrl(RpcUrlError),
 	RpcError(RpcError),
 	IncorrectChainId,
+	QuorumNotReached,
 	Unschedulable,
 }
 
+impl OffchainError {
+	/// Whether the error is specific to the endpoint that was queried, so another endpoint of
+	/// the same chain is worth trying.
+	pub fn is_endpoint_failure(&self) -> bool {
+		matches!(
+			self,
+			OffchainError::RpcError(
+				RpcError::HttpError(_) | RpcError::RequestError(_) | RpcError::Timeout(_)
+			) | OffchainError::IncorrectChainId
+		)
+	}
+}
+
 pub type VerificationResult<T> = Result<T, OffchainError>;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, TypeInfo, MaxEncodedLen)]
@@ -46,6 +62,9 @@ pub enum VerificationFailureCause {
 	UnsupportedMethod,
 	TransactionNotFound,
 	InsufficientFaucetBalance,
+	InvalidContractSignature,
+	/// No authority managed to verify the task before it expired.
+	Timeout,
 }
 
 impl VerificationFailureCause {
@@ -69,10 +88,20 @@ impl VerificationFailureCause {
 			| IncorrectReceiver
 			| TaskNonexistent
 			| TransactionNotFound
-			| InsufficientFaucetBalance => true,
+			| InsufficientFaucetBalance
+			| InvalidContractSignature
+			| Timeout => true,
 			TaskPending | TaskUnconfirmed => false,
 		}
 	}
+
+	/// Whether the transfer would fail the same way if it was verified again. Timeouts and
+	/// transactions not found say as much about the authorities' endpoints as about the
+	/// transfer, so they don't count.
+	pub fn is_final(self) -> bool {
+		use VerificationFailureCause::*;
+		self.is_fatal() && !matches!(self, Timeout | TransactionNotFound)
+	}
 }
 
 #[derive(Debug, PartialEq, Eq)]
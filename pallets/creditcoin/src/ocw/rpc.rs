@@ -8,7 +8,7 @@ use serde::{
 	de::{Error, Unexpected, Visitor},
 	Deserialize, Deserializer, Serialize, Serializer,
 };
-use sp_runtime::offchain::{http, Duration, Timestamp};
+use sp_runtime::offchain::{http, Duration, HttpError, Timestamp};
 use sp_std::{prelude::*, vec::Vec};
 
 use crate::ExternalTxId;
@@ -27,6 +27,9 @@ pub mod errors {
 		RequestError(sp_runtime::offchain::http::Error),
 		InvalidArgument(&'static str),
 		Timeout(PendingRequest),
+		AbiError(ethabi::Error),
+		/// The endpoint didn't answer a batch of requests with a batch of responses.
+		BatchUnsupported,
 	}
 
 	impl_enum_from_variant!(
@@ -35,7 +38,8 @@ pub mod errors {
 		serde_json::Error => SerdeError,
 		HttpError => HttpError,
 		sp_runtime::offchain::http::Error => RequestError,
-		PendingRequest => Timeout
+		PendingRequest => Timeout,
+		ethabi::Error => AbiError
 	);
 }
 
@@ -180,27 +184,156 @@ impl JsonRpcRequest {
 		self
 	}
 
-	#[allow(dead_code)]
 	pub fn to_bytes(&self) -> Vec<u8> {
 		serde_json::to_vec(self).expect("serialization cannot fail; qed")
 	}
 
+	pub fn with_id(self, id: u64) -> Self {
+		Self { id, ..self }
+	}
+
 	pub fn send<T: for<'de> serde::Deserialize<'de>>(
 		self,
 		rpc_url: &str,
 	) -> OffchainResult<T, RpcError> {
-		let rpc_bytes = serde_json::to_vec(&self).map_err(RpcError::SerdeError)?;
-		let timeout = timeout();
-		let response = http::Request::post(rpc_url, vec![rpc_bytes])
-			.add_header("Content-Type", "application/json")
-			.send()?
-			.try_wait(timeout)??;
+		let response = post(rpc_url, self.to_bytes())?.try_wait(timeout())??;
 		let body: Vec<u8> = response.body().collect();
 		let rpc_response: JsonRpcResponse<T> = serde_json::from_slice(&body)?;
 		rpc_response.result()
 	}
 }
 
+fn post(rpc_url: &str, body: Vec<u8>) -> Result<http::PendingRequest, HttpError> {
+	http::Request::post(rpc_url, vec![body])
+		.add_header("Content-Type", "application/json")
+		.send()
+}
+
+/// Decodes the result of a request sent with [`send_all`].
+pub fn decode<T: for<'de> serde::Deserialize<'de>>(
+	result: serde_json::Value,
+) -> OffchainResult<T, RpcError> {
+	Ok(serde_json::from_value(result)?)
+}
+
+/// What was sent in a single HTTP request of [`send_all`], indices into its `requests`.
+enum Exchange {
+	Single(usize),
+	Batch(Vec<usize>),
+}
+
+/// Sends every request at once and waits for all of the responses until `deadline`, instead of
+/// waiting for each response before sending the next request. Requests to endpoints for which
+/// `batch` holds are sent as one JSON-RPC batch per endpoint; if the endpoint doesn't answer
+/// with a batch their results are [`RpcError::BatchUnsupported`], and requests it leaves out of
+/// its batch fail like a dropped connection. The results are in the order of `requests` and
+/// still have to be [`decode`]d.
+pub fn send_all(
+	requests: Vec<(&str, JsonRpcRequest)>,
+	batch: impl Fn(&str) -> bool,
+	deadline: Timestamp,
+) -> Vec<OffchainResult<serde_json::Value, RpcError>> {
+	let mut results: Vec<Option<OffchainResult<serde_json::Value, RpcError>>> =
+		requests.iter().map(|_| None).collect();
+
+	let mut exchanges = Vec::new();
+	let mut batches: Vec<(&str, Vec<usize>)> = Vec::new();
+	for (i, (rpc_url, _)) in requests.iter().enumerate() {
+		if !batch(rpc_url) {
+			exchanges.push((*rpc_url, Exchange::Single(i)));
+		} else if let Some((_, batch)) = batches.iter_mut().find(|(url, _)| *url == *rpc_url) {
+			batch.push(i);
+		} else {
+			batches.push((*rpc_url, vec![i]));
+		}
+	}
+	exchanges.extend(batches.into_iter().map(|(rpc_url, batch)| (rpc_url, Exchange::Batch(batch))));
+
+	let mut pending = Vec::new();
+	let mut sent = Vec::new();
+	for (rpc_url, exchange) in exchanges {
+		let body = match &exchange {
+			Exchange::Single(i) => requests[*i].1.to_bytes(),
+			Exchange::Batch(batch) => {
+				let batch: Vec<_> = batch
+					.iter()
+					.enumerate()
+					.map(|(id, i)| requests[*i].1.clone().with_id(id as u64))
+					.collect();
+				serde_json::to_vec(&batch).expect("serialization cannot fail; qed")
+			},
+		};
+		match post(rpc_url, body) {
+			Ok(request) => {
+				pending.push(request);
+				sent.push(exchange);
+			},
+			Err(e) => {
+				for i in exchange.indices() {
+					results[i] = Some(Err(RpcError::HttpError(e)));
+				}
+			},
+		}
+	}
+
+	let responses = http::PendingRequest::try_wait_all(pending, deadline);
+	for (exchange, response) in sent.into_iter().zip(responses) {
+		let body: Result<Vec<u8>, http::Error> = match response {
+			Ok(Ok(response)) => Ok(response.body().collect()),
+			Ok(Err(e)) => Err(e),
+			Err(_) => Err(http::Error::DeadlineReached),
+		};
+		match (exchange, body) {
+			(Exchange::Single(i), Ok(body)) => {
+				results[i] = Some(
+					serde_json::from_slice::<JsonRpcResponse<serde_json::Value>>(&body)
+						.map_err(RpcError::from)
+						.and_then(JsonRpcResponse::result),
+				);
+			},
+			(Exchange::Batch(batch), Ok(body)) => {
+				let Ok(responses) =
+					serde_json::from_slice::<Vec<JsonRpcResponse<serde_json::Value>>>(&body)
+				else {
+					for i in batch {
+						results[i] = Some(Err(RpcError::BatchUnsupported));
+					}
+					continue;
+				};
+				for response in responses {
+					if let Some(&i) = batch.get(response.id as usize) {
+						results[i] = Some(response.result());
+					}
+				}
+				// an entry the endpoint left out is lost in transport, it says nothing about
+				// whether what was requested exists
+				for i in batch {
+					results[i].get_or_insert(Err(RpcError::RequestError(http::Error::IoError)));
+				}
+			},
+			(exchange, Err(e)) => {
+				for i in exchange.indices() {
+					results[i] = Some(Err(RpcError::RequestError(e.clone())));
+				}
+			},
+		}
+	}
+
+	results
+		.into_iter()
+		.map(|result| result.unwrap_or(Err(RpcError::NoResult)))
+		.collect()
+}
+
+impl Exchange {
+	fn indices(&self) -> Vec<usize> {
+		match self {
+			Exchange::Single(i) => vec![*i],
+			Exchange::Batch(batch) => batch.clone(),
+		}
+	}
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
 pub struct JsonRpcResponse<T> {
 	#[allow(dead_code)]
@@ -231,13 +364,19 @@ pub struct JsonRpcError {
 	pub message: String,
 }
 
-#[derive(serde::Deserialize, Clone, Debug, Default)]
+#[derive(serde::Deserialize, Clone, Debug, Default, PartialEq, Eq)]
 pub struct EthTransaction {
 	/// Hash
 	pub hash: H256,
 	/// Block number. None when pending.
 	#[serde(rename = "blockNumber")]
 	pub block_number: Option<U64>,
+	/// Block hash. None when pending.
+	#[serde(default, rename = "blockHash")]
+	pub block_hash: Option<H256>,
+	/// Position in the block. None when pending.
+	#[serde(default, rename = "transactionIndex")]
+	pub transaction_index: Option<U64>,
 	/// Sender
 	#[serde(default, rename = "from", skip_serializing_if = "Option::is_none")]
 	pub sender: Option<Address>,
@@ -254,6 +393,11 @@ impl EthTransaction {
 	pub fn input(&self) -> &[u8] {
 		&self.input.0[4..]
 	}
+
+	/// The whole input, function selector included.
+	pub fn calldata(&self) -> &[u8] {
+		&self.input.0
+	}
 }
 
 #[cfg(test)]
@@ -263,7 +407,7 @@ impl EthTransaction {
 	}
 }
 
-#[derive(serde::Deserialize, Clone, Debug, Default)]
+#[derive(serde::Deserialize, Clone, Debug, Default, PartialEq, Eq)]
 pub struct EthTransactionReceipt {
 	/// Transaction hash.
 	#[serde(rename = "transactionHash")]
@@ -283,12 +427,18 @@ pub struct EthTransactionReceipt {
 	pub recipient: Option<Address>,
 	/// Status: either 1 (success) or 0 (failure).
 	pub status: Option<U64>,
+	/// Events emitted by the transaction.
+	/// Note: empty if the client did not return this value
+	#[serde(default)]
+	pub logs: Vec<EthLog>,
 }
 
 #[derive(serde::Deserialize, Clone, Debug, Default)]
 pub struct EthBlock {
 	/// Timestamp of the block's collation.
 	pub timestamp: U64,
+	#[serde(default)]
+	pub hash: Option<H256>,
 }
 
 impl EthTransactionReceipt {
@@ -318,44 +468,139 @@ fn format_as_hex<T: sp_std::fmt::LowerHex>(value: T) -> String {
 	alloc::format!("0x{value:x}")
 }
 
+pub fn get_transaction_request(tx_id: &ExternalTxId) -> JsonRpcRequest {
+	JsonRpcRequest::new(
+		"eth_getTransactionByHash",
+		Some(serde_json::Value::String(to_json_hex(tx_id.as_ref()))),
+	)
+}
+
 pub fn eth_get_transaction(
 	tx_id: &ExternalTxId,
 	rpc_url: &str,
 ) -> OffchainResult<EthTransaction, RpcError> {
-	let rpc_req = JsonRpcRequest::new(
-		"eth_getTransactionByHash",
+	get_transaction_request(tx_id).send(rpc_url)
+}
+
+pub fn get_transaction_receipt_request(tx_id: &ExternalTxId) -> JsonRpcRequest {
+	JsonRpcRequest::new(
+		"eth_getTransactionReceipt",
 		Some(serde_json::Value::String(to_json_hex(tx_id.as_ref()))),
-	);
-	rpc_req.send(rpc_url)
+	)
 }
 
 pub fn eth_get_transaction_receipt(
 	tx_id: &ExternalTxId,
 	rpc_url: &str,
 ) -> OffchainResult<EthTransactionReceipt, RpcError> {
-	let rpc_req = JsonRpcRequest::new(
-		"eth_getTransactionReceipt",
-		Some(serde_json::Value::String(to_json_hex(tx_id.as_ref()))),
-	);
-	rpc_req.send(rpc_url)
+	get_transaction_receipt_request(tx_id).send(rpc_url)
+}
+
+pub fn block_number_request() -> JsonRpcRequest {
+	JsonRpcRequest::new("eth_blockNumber", None)
 }
 
 pub fn eth_get_block_number(rpc_url: &str) -> OffchainResult<U64, RpcError> {
-	let rpc_req = JsonRpcRequest::new("eth_blockNumber", None);
-	rpc_req.send(rpc_url)
+	block_number_request().send(rpc_url)
+}
+
+pub fn block_by_number_request(block_number: U64) -> JsonRpcRequest {
+	JsonRpcRequest::new(
+		"eth_getBlockByNumber",
+		[serde_json::Value::String(format_as_hex(block_number)), serde_json::Value::Bool(false)],
+	)
 }
 
 pub fn eth_get_block_by_number(
 	block_number: U64,
 	rpc_url: &str,
 ) -> OffchainResult<EthBlock, RpcError> {
+	block_by_number_request(block_number).send(rpc_url)
+}
+
+/// Parameters of a read-only contract call.
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct EthCall {
+	pub to: Address,
+	pub data: Bytes,
+}
+
+/// Executes a read-only call against the latest block and returns the raw output.
+pub fn eth_call(to: &Address, data: &[u8], rpc_url: &str) -> OffchainResult<Bytes, RpcError> {
+	let call = EthCall { to: *to, data: Bytes::from(data) };
 	let rpc_req = JsonRpcRequest::new(
-		"eth_getBlockByNumber",
-		[serde_json::Value::String(format_as_hex(block_number)), serde_json::Value::Bool(false)],
+		"eth_call",
+		[serde_json::to_value(call)?, serde_json::Value::String("latest".into())],
 	);
 	rpc_req.send(rpc_url)
 }
 
+/// Calls `function` on `contract` with ABI encoded `args` and decodes the returned values.
+pub fn eth_call_function(
+	contract: &Address,
+	function: &ethabi::Function,
+	args: &[ethabi::Token],
+	rpc_url: &str,
+) -> OffchainResult<Vec<ethabi::Token>, RpcError> {
+	let input = function.encode_input(args)?;
+	let output = eth_call(contract, &input, rpc_url)?;
+	Ok(function.decode_output(&output.0)?)
+}
+
+/// Filter for `eth_getLogs`. Each entry of `topics` matches the topic at the same position,
+/// `None` matches anything and several hashes match any of them.
+#[derive(serde::Serialize, Clone, Debug, Default)]
+pub struct EthLogFilter {
+	#[serde(rename = "fromBlock", skip_serializing_if = "Option::is_none")]
+	pub from_block: Option<U64>,
+	#[serde(rename = "toBlock", skip_serializing_if = "Option::is_none")]
+	pub to_block: Option<U64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub address: Option<Address>,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub topics: Vec<Option<Vec<H256>>>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct EthLog {
+	/// Address of the contract that emitted the log.
+	pub address: Address,
+	/// Indexed event parameters, the first one being the event signature.
+	pub topics: Vec<H256>,
+	/// Non-indexed event parameters.
+	pub data: Bytes,
+	/// Block number. None when pending.
+	#[serde(rename = "blockNumber")]
+	pub block_number: Option<U64>,
+	/// Hash of the transaction that emitted the log. None when pending.
+	#[serde(rename = "transactionHash")]
+	pub transaction_hash: Option<H256>,
+	/// Position of the log in the block. None when pending.
+	#[serde(default, rename = "logIndex")]
+	pub log_index: Option<U64>,
+	/// Whether the log was removed by a chain reorganization.
+	#[serde(default)]
+	pub removed: bool,
+}
+
+impl EthLog {
+	/// Decodes the log against `event`, checking its signature topic.
+	pub fn parse(&self, event: &ethabi::Event) -> OffchainResult<ethabi::Log, RpcError> {
+		let raw = ethabi::RawLog { topics: self.topics.clone(), data: self.data.0.clone() };
+		Ok(event.parse_log(raw)?)
+	}
+}
+
+pub fn eth_get_logs(filter: &EthLogFilter, rpc_url: &str) -> OffchainResult<Vec<EthLog>, RpcError> {
+	let rpc_req = JsonRpcRequest::new("eth_getLogs", Some(serde_json::to_value(filter)?));
+	rpc_req.send(rpc_url)
+}
+
+pub fn eth_chain_id(rpc_url: &str) -> OffchainResult<U64, RpcError> {
+	let rpc_req = JsonRpcRequest::new("eth_chainId", None);
+	rpc_req.send(rpc_url)
+}
+
 #[cfg(test)]
 mod tests {
 	#[test]
@@ -369,4 +614,47 @@ mod tests {
 	fn format_as_hex_works() {
 		assert_eq!(super::format_as_hex(0x123456789abcdefu64), "0x123456789abcdef");
 	}
+
+	#[test]
+	fn log_filter_serialization_works() {
+		let filter = super::EthLogFilter {
+			from_block: Some(0x10u64.into()),
+			address: Some(super::Address::repeat_byte(0xaa)),
+			topics: vec![Some(vec![super::H256::repeat_byte(1)]), None],
+			..Default::default()
+		};
+		let expected = serde_json::json!({
+			"fromBlock": "0x10",
+			"address": "0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+			"topics": [["0x0101010101010101010101010101010101010101010101010101010101010101"], null],
+		});
+		assert_eq!(serde_json::to_value(filter).unwrap(), expected);
+	}
+
+	#[test]
+	fn eth_log_parse_works() {
+		use ethabi::{Event, EventParam, ParamType, Token};
+
+		let event = Event {
+			name: "Transfer".into(),
+			inputs: vec![
+				EventParam { name: "from".into(), kind: ParamType::Address, indexed: true },
+				EventParam { name: "to".into(), kind: ParamType::Address, indexed: true },
+				EventParam { name: "value".into(), kind: ParamType::Uint(256), indexed: false },
+			],
+			anonymous: false,
+		};
+		let from = super::Address::repeat_byte(1);
+		let to = super::Address::repeat_byte(2);
+		let log = super::EthLog {
+			topics: vec![event.signature(), from.into(), to.into()],
+			data: ethabi::encode(&[Token::Uint(100u64.into())]).into(),
+			..Default::default()
+		};
+
+		let parsed = log.parse(&event).unwrap();
+		assert_eq!(parsed.params[0].value, Token::Address(from));
+		assert_eq!(parsed.params[1].value, Token::Address(to));
+		assert_eq!(parsed.params[2].value, Token::Uint(100u64.into()));
+	}
 }
@@ -1,9 +1,16 @@
+pub mod attest_header;
 pub mod collect_coins;
+pub mod reverify_transfer;
+pub mod verify_contract_ownership;
 pub mod verify_transfer;
 
 use crate::ocw::errors::VerificationResult;
-use crate::types::Task;
+use crate::types::{
+	verified_transfer_call, AddressId, HeaderAttestation, TransferId, TransferReverificationFor,
+	UnverifiedContractOwnershipFor, UnverifiedTransferFor,
+};
 use crate::Config;
+use frame_support::{traits::Get, weights::Weight};
 
 pub(crate) trait OffchainVerification<T: Config> {
 	type Output;
@@ -12,28 +19,199 @@ pub(crate) trait OffchainVerification<T: Config> {
 
 use crate::ocw::errors::SchedulerError;
 use crate::ocw::VerificationFailureCause;
+use pallet_offchain_task_scheduler::assignment::VrfAssignment;
+use pallet_offchain_task_scheduler::registry::TaskKind;
 use pallet_offchain_task_scheduler::tasks::error::TaskError;
 pub use pallet_offchain_task_scheduler::tasks::ForwardTask;
-use pallet_offchain_task_scheduler::tasks::TaskV2;
+use pallet_offchain_task_scheduler::tasks::{TaskPriority, TaskV2};
 use pallet_offchain_task_scheduler::Config as TaskConfig;
+use sp_runtime::offchain::Timestamp;
+use sp_runtime::traits::{Saturating, UniqueSaturatedFrom};
+use verify_transfer::PrefetchedTransfers;
 
-impl<T: Config + TaskConfig> ForwardTask<T>
-	for Task<T::AccountId, T::BlockNumber, T::Hash, T::Moment>
+impl<T: Config> crate::pallet::Call<T> {
+	/// Attaches the proof that the authority was selected for the task to the calls completing it.
+	fn with_assignment(self, assignment: Option<VrfAssignment>) -> Self {
+		match self {
+			Self::persist_task_output { deadline, task_output, .. } => {
+				Self::persist_task_output { deadline, task_output, assignment }
+			},
+			Self::fail_task { deadline, task_id, cause, .. } => {
+				Self::fail_task { deadline, task_id, cause, assignment }
+			},
+			call => call,
+		}
+	}
+}
+
+impl<T: Config + TaskConfig> ForwardTask<T> for UnverifiedTransferFor<T>
 where
 	<T as TaskConfig>::TaskCall: From<crate::pallet::Call<T>>,
 {
 	type Call = T::TaskCall;
 	type EvaluationError = VerificationFailureCause;
 	type SchedulerError = SchedulerError;
+	type Prefetched = PrefetchedTransfers<T::Hash>;
+	/// Transfers fund and repay loans, they go before address registrations.
+	fn priority(&self) -> TaskPriority {
+		TaskPriority::High
+	}
+	/// The transaction, its receipt, the chain tip and possibly the block's timestamp.
+	fn http_requests(&self) -> u32 {
+		4
+	}
+	fn set_deadline(&mut self, deadline: T::BlockNumber) {
+		self.deadline = deadline;
+	}
+	fn prefetch(tasks: &[(&T::Hash, &Self)], until: Timestamp) -> Self::Prefetched {
+		crate::Pallet::<T>::prefetch_ethless_transfers(tasks, until)
+	}
+	fn is_persisted(&self, id: &T::Hash) -> bool {
+		<Self as TaskV2<T>>::is_persisted(id)
+	}
 	fn forward_task(
 		&self,
 		deadline: T::BlockNumber,
+		assignment: Option<VrfAssignment>,
+		prefetched: &Self::Prefetched,
 	) -> Result<Self::Call, TaskError<Self::EvaluationError, Self::SchedulerError>> {
-		use Task::*;
-		match self {
-			VerifyTransfer(unverified) => {
-				unverified.forward_task(deadline).map(|c: crate::pallet::Call<T>| c.into())
+		let id = TaskV2::<T>::to_id(self);
+		let call = match prefetched.get(&id) {
+			Some(&verified) => {
+				let verified = verified
+					.map(|verified| verified.map_timestamp(T::Moment::unique_saturated_from))
+					.map_err(Into::into);
+				verified_transfer_call::<T>(self, deadline, &id, verified)?
 			},
-		}
+			None => TaskV2::<T>::forward_task(self, deadline)?,
+		};
+		Ok(call.with_assignment(assignment).into())
+	}
+}
+
+impl<T: Config + TaskConfig> TaskKind<T> for UnverifiedTransferFor<T>
+where
+	<T as TaskConfig>::TaskCall: From<crate::pallet::Call<T>>,
+{
+	fn on_expired(self, id: &T::Hash) -> Weight {
+		let cause = VerificationFailureCause::Timeout;
+		let transfer_id = TransferId::from(*id);
+		crate::Pallet::<T>::record_failed_transfer(&transfer_id, cause, None);
+		crate::CancelledTransfers::<T>::remove(&transfer_id);
+		crate::Pallet::<T>::deposit_event(crate::Event::<T>::TransferFailedVerification(
+			transfer_id,
+			cause,
+		));
+		T::DbWeight::get().reads_writes(1, 4)
 	}
 }
+
+impl<T: Config + TaskConfig> ForwardTask<T> for UnverifiedContractOwnershipFor<T>
+where
+	<T as TaskConfig>::TaskCall: From<crate::pallet::Call<T>>,
+{
+	type Call = T::TaskCall;
+	type EvaluationError = VerificationFailureCause;
+	type SchedulerError = SchedulerError;
+	type Prefetched = ();
+	fn set_deadline(&mut self, deadline: T::BlockNumber) {
+		self.deadline = deadline;
+	}
+	fn is_persisted(&self, id: &T::Hash) -> bool {
+		<Self as TaskV2<T>>::is_persisted(id)
+	}
+	fn forward_task(
+		&self,
+		deadline: T::BlockNumber,
+		assignment: Option<VrfAssignment>,
+		_prefetched: &(),
+	) -> Result<Self::Call, TaskError<Self::EvaluationError, Self::SchedulerError>> {
+		let call = TaskV2::<T>::forward_task(self, deadline)?;
+		Ok(call.with_assignment(assignment).into())
+	}
+}
+
+impl<T: Config + TaskConfig> TaskKind<T> for UnverifiedContractOwnershipFor<T>
+where
+	<T as TaskConfig>::TaskCall: From<crate::pallet::Call<T>>,
+{
+	fn on_expired(self, id: &T::Hash) -> Weight {
+		let cause = VerificationFailureCause::Timeout;
+		crate::Pallet::<T>::deposit_event(crate::Event::<T>::AddressFailedVerification(
+			AddressId::from(*id),
+			cause,
+		));
+		T::DbWeight::get().writes(1)
+	}
+}
+
+impl<T: Config + TaskConfig> ForwardTask<T> for TransferReverificationFor<T>
+where
+	<T as TaskConfig>::TaskCall: From<crate::pallet::Call<T>>,
+{
+	type Call = T::TaskCall;
+	type EvaluationError = VerificationFailureCause;
+	type SchedulerError = SchedulerError;
+	type Prefetched = ();
+	/// Re-verification only guards against reorgs, new verifications go first.
+	fn priority(&self) -> TaskPriority {
+		TaskPriority::Low
+	}
+	/// The whole window, then as long as any other task to submit the output.
+	fn timeout(&self) -> Option<T::BlockNumber> {
+		let window = <T as Config>::TransferReverificationWindow::get();
+		Some(window.saturating_add(<T as Config>::UnverifiedTaskTimeout::get()))
+	}
+	/// The block, and when the transaction moved, the transaction, its receipt and the tip.
+	fn http_requests(&self) -> u32 {
+		4
+	}
+	fn is_persisted(&self, id: &T::Hash) -> bool {
+		<Self as TaskV2<T>>::is_persisted(id)
+	}
+	fn forward_task(
+		&self,
+		deadline: T::BlockNumber,
+		assignment: Option<VrfAssignment>,
+		_prefetched: &(),
+	) -> Result<Self::Call, TaskError<Self::EvaluationError, Self::SchedulerError>> {
+		let call = TaskV2::<T>::forward_task(self, deadline)?;
+		Ok(call.with_assignment(assignment).into())
+	}
+}
+
+impl<T: Config + TaskConfig> TaskKind<T> for TransferReverificationFor<T> where
+	<T as TaskConfig>::TaskCall: From<crate::pallet::Call<T>>
+{
+}
+
+impl<T: Config + TaskConfig> ForwardTask<T> for HeaderAttestation
+where
+	<T as TaskConfig>::TaskCall: From<crate::pallet::Call<T>>,
+{
+	type Call = T::TaskCall;
+	type EvaluationError = VerificationFailureCause;
+	type SchedulerError = SchedulerError;
+	type Prefetched = ();
+	/// The block and the tip.
+	fn http_requests(&self) -> u32 {
+		2
+	}
+	fn is_persisted(&self, id: &T::Hash) -> bool {
+		<Self as TaskV2<T>>::is_persisted(id)
+	}
+	fn forward_task(
+		&self,
+		deadline: T::BlockNumber,
+		assignment: Option<VrfAssignment>,
+		_prefetched: &(),
+	) -> Result<Self::Call, TaskError<Self::EvaluationError, Self::SchedulerError>> {
+		let call = TaskV2::<T>::forward_task(self, deadline)?;
+		Ok(call.with_assignment(assignment).into())
+	}
+}
+
+impl<T: Config + TaskConfig> TaskKind<T> for HeaderAttestation where
+	<T as TaskConfig>::TaskCall: From<crate::pallet::Call<T>>
+{
+}
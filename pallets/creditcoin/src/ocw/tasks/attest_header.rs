@@ -0,0 +1,33 @@
+use crate::{
+	ocw::{rpc, OffchainResult, VerificationFailureCause, VerificationResult, ETH_CONFIRMATIONS},
+	Config, HeaderAttestation,
+};
+use ethereum_types::{H256, U64};
+
+impl<T: Config> crate::Pallet<T> {
+	/// Looks up the hash of the block at the attested height, once it has enough confirmations.
+	pub fn attest_header_ocw(task: &HeaderAttestation) -> VerificationResult<H256> {
+		let HeaderAttestation { blockchain, number } = task;
+
+		let fetch = |rpc_url: &str| -> OffchainResult<(Option<H256>, U64)> {
+			let block = rpc::eth_get_block_by_number(U64::from(*number), rpc_url)?;
+			let eth_tip = rpc::eth_get_block_number(rpc_url)?;
+			Ok((block.hash, eth_tip))
+		};
+		let (hash, eth_tip) = match blockchain.rpc_quorum() {
+			Some(quorum) => {
+				let responses = blockchain.with_quorum(quorum, fetch, |a, b| a.0 == b.0)?;
+				// the lowest tip is the most conservative one
+				let eth_tip = responses.iter().map(|(_, tip)| *tip).min().unwrap_or_default();
+				(responses[0].0, eth_tip)
+			},
+			None => blockchain.with_failover(fetch)?,
+		};
+
+		let hash = hash.ok_or(VerificationFailureCause::TaskPending)?;
+		if eth_tip.as_u64().saturating_sub(*number) < ETH_CONFIRMATIONS {
+			return Err(VerificationFailureCause::TaskUnconfirmed.into());
+		}
+		Ok(hash)
+	}
+}
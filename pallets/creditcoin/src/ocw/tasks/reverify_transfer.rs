@@ -0,0 +1,94 @@
+use super::verify_transfer::{ethless_evidence, ethless_responses_agree, EthlessResponse};
+use crate::{
+	ocw::{
+		self, rpc, OffchainError, OffchainResult, VerificationFailureCause, VerificationResult,
+		ETH_CONFIRMATIONS,
+	},
+	Config, Reverification, TransferReverification, TransferReverificationFor,
+};
+use ethereum_types::U64;
+
+/// Where the transaction stands on one endpoint's chain.
+enum Lookup {
+	/// The block it was verified in is still there.
+	Unchanged,
+	/// It is in another block now.
+	Moved(EthlessResponse),
+	/// It isn't in any block.
+	Gone,
+}
+
+fn lookups_agree(a: &Lookup, b: &Lookup) -> bool {
+	match (a, b) {
+		(Lookup::Unchanged, Lookup::Unchanged) | (Lookup::Gone, Lookup::Gone) => true,
+		(Lookup::Moved(a), Lookup::Moved(b)) => ethless_responses_agree(a, b),
+		_ => false,
+	}
+}
+
+impl<T: Config> crate::Pallet<T> {
+	/// Checks that the transaction of a verified transfer is still where it was found, or at
+	/// least still on the chain with enough confirmations.
+	pub fn reverify_transfer_ocw(
+		task: &TransferReverificationFor<T>,
+	) -> VerificationResult<Reverification> {
+		let TransferReverification { blockchain, tx_id, evidence, .. } = task;
+
+		let fetch = |rpc_url: &str| -> OffchainResult<Lookup> {
+			let block = rpc::eth_get_block_by_number(U64::from(evidence.block_number), rpc_url)?;
+			if block.hash == Some(evidence.block_hash) {
+				return Ok(Lookup::Unchanged);
+			}
+
+			let tx = match ocw::eth_get_transaction(tx_id, rpc_url) {
+				Ok(tx) => tx,
+				Err(OffchainError::InvalidTask(VerificationFailureCause::TransactionNotFound)) => {
+					return Ok(Lookup::Gone)
+				},
+				Err(e) => return Err(e),
+			};
+			if tx.block_number.is_none() {
+				return Ok(Lookup::Gone);
+			}
+			let tx_receipt = rpc::eth_get_transaction_receipt(tx_id, rpc_url)?;
+			let eth_tip = rpc::eth_get_block_number(rpc_url)?;
+			Ok(Lookup::Moved((tx, tx_receipt, eth_tip)))
+		};
+		let lookup = match blockchain.rpc_quorum() {
+			Some(quorum) => {
+				let mut lookups = blockchain.with_quorum(quorum, fetch, lookups_agree)?;
+				// the lowest tip is the most conservative one
+				let eth_tip = lookups
+					.iter()
+					.filter_map(|lookup| match lookup {
+						Lookup::Moved((_, _, tip)) => Some(*tip),
+						_ => None,
+					})
+					.min();
+				let mut lookup = lookups.swap_remove(0);
+				if let (Lookup::Moved((_, _, tip)), Some(eth_tip)) = (&mut lookup, eth_tip) {
+					*tip = eth_tip;
+				}
+				lookup
+			},
+			None => blockchain.with_failover(fetch)?,
+		};
+
+		match lookup {
+			Lookup::Unchanged => Ok(Reverification::Confirmed(*evidence)),
+			Lookup::Gone => Ok(Reverification::Reorged),
+			// reverted on the new branch, the transfer didn't happen there
+			Lookup::Moved((_, receipt, _)) if !receipt.is_success() => Ok(Reverification::Reorged),
+			Lookup::Moved(response) => {
+				// the transfer log isn't looked up again, its index in the new block is unknown
+				let moved = ethless_evidence(&response, None)
+					.ok_or(VerificationFailureCause::TaskPending)?;
+				let (_, _, eth_tip) = response;
+				if eth_tip.as_u64().saturating_sub(moved.block_number) < ETH_CONFIRMATIONS {
+					return Err(VerificationFailureCause::TaskUnconfirmed.into());
+				}
+				Ok(Reverification::Confirmed(moved))
+			},
+		}
+	}
+}
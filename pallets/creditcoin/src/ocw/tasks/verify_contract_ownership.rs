@@ -0,0 +1,188 @@
+use ethabi::{Function, Param, ParamType, StateMutability, Token};
+use frame_support::ensure;
+use hex_literal::hex;
+
+#[cfg(not(feature = "std"))]
+use sp_std::prelude::*;
+
+use crate::{
+	ocw::{
+		parse_eth_address,
+		rpc::{self, errors::RpcError},
+		OffchainError, VerificationFailureCause, VerificationResult,
+	},
+	Address, Config, UnverifiedContractOwnership,
+};
+
+/// Returned by `isValidSignature` when the contract accepts the signature.
+pub(crate) const ERC1271_MAGIC_VALUE: [u8; 4] = hex!("1626ba7e");
+
+pub(crate) fn is_valid_signature_function_abi() -> Function {
+	#[allow(deprecated)]
+	Function {
+		name: "isValidSignature".into(),
+		inputs: vec![
+			Param { name: "_hash".into(), kind: ParamType::FixedBytes(32), internal_type: None },
+			Param { name: "_signature".into(), kind: ParamType::Bytes, internal_type: None },
+		],
+		outputs: vec![Param {
+			name: "magicValue".into(),
+			kind: ParamType::FixedBytes(4),
+			internal_type: None,
+		}],
+		constant: Some(true),
+		state_mutability: StateMutability::View,
+	}
+}
+
+impl<T: Config> crate::Pallet<T> {
+	pub fn verify_contract_ownership_ocw(
+		ownership: &UnverifiedContractOwnership<T::AccountId, T::BlockNumber>,
+	) -> VerificationResult<()> {
+		let UnverifiedContractOwnership {
+			address: Address { blockchain, value, .. },
+			message_hash,
+			signature,
+			..
+		} = ownership;
+		log::debug!("verifying OCW contract ownership");
+
+		let contract = parse_eth_address(value)?;
+
+		let args = [Token::FixedBytes(message_hash.to_vec()), Token::Bytes(signature.to_vec())];
+
+		// contracts are free to revert, or to be an EOA and return nothing, instead of
+		// returning a non-magic value
+		let call = |rpc_url: &str| {
+			rpc::eth_call_function(&contract, &is_valid_signature_function_abi(), &args, rpc_url)
+				.map_err(|e| match e {
+					RpcError::FailureResponse(_) | RpcError::AbiError(_) => {
+						OffchainError::InvalidTask(
+							VerificationFailureCause::InvalidContractSignature,
+						)
+					},
+					e => e.into(),
+				})
+		};
+		let output = match blockchain.rpc_quorum() {
+			Some(quorum) => blockchain
+				.with_quorum(quorum, call, |a, b| a == b)?
+				.into_iter()
+				.next()
+				.expect("a quorum is at least 2 responses; qed"),
+			None => blockchain.with_failover(call)?,
+		};
+
+		ensure!(
+			matches!(output.first(), Some(Token::FixedBytes(magic)) if magic[..] == ERC1271_MAGIC_VALUE),
+			VerificationFailureCause::InvalidContractSignature
+		);
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mock::{set_rpc_uri, AccountId, BlockNumber, ExtBuilder, PendingRequestExt, Test};
+	use crate::ocw::rpc::{Bytes, JsonRpcError, JsonRpcRequest, JsonRpcResponse};
+	use crate::{Blockchain, ContractSignature, ExternalAddress};
+	use sp_runtime::offchain::testing::PendingRequest;
+
+	const CONTRACT: [u8; 20] = [0xab; 20];
+
+	fn unverified() -> UnverifiedContractOwnership<AccountId, BlockNumber> {
+		UnverifiedContractOwnership {
+			address: Address {
+				blockchain: Blockchain::Rinkeby,
+				value: ExternalAddress::try_from(CONTRACT.to_vec()).unwrap(),
+				owner: AccountId::new([1; 32]),
+			},
+			message_hash: [2; 32],
+			signature: ContractSignature::try_from(vec![3; 65]).unwrap(),
+			deadline: 1_000,
+		}
+	}
+
+	fn eth_call_request(
+		ownership: &UnverifiedContractOwnership<AccountId, BlockNumber>,
+	) -> Option<PendingRequest> {
+		let input = is_valid_signature_function_abi()
+			.encode_input(&[
+				Token::FixedBytes(ownership.message_hash.to_vec()),
+				Token::Bytes(ownership.signature.to_vec()),
+			])
+			.unwrap();
+		let call = rpc::EthCall { to: CONTRACT.into(), data: Bytes::from(input) };
+		let request = JsonRpcRequest::new(
+			"eth_call",
+			[serde_json::to_value(call).unwrap(), serde_json::Value::String("latest".into())],
+		);
+		Some(PendingRequest {
+			method: "POST".into(),
+			uri: "dummy".into(),
+			headers: vec![("Content-Type".into(), "application/json".into())],
+			body: request.to_bytes(),
+			response_headers: vec![("Content-Type".into(), "application/json".into())],
+			sent: true,
+			..Default::default()
+		})
+	}
+
+	fn output(bytes: &[u8]) -> JsonRpcResponse<Bytes> {
+		JsonRpcResponse { jsonrpc: "2.0".into(), id: 1, error: None, result: Some(bytes.into()) }
+	}
+
+	#[test]
+	fn verify_contract_ownership_accepts_magic_value() {
+		ExtBuilder::default().build_offchain_and_execute_with_state(|state, _| {
+			set_rpc_uri(&Blockchain::Rinkeby, "dummy");
+			let ownership = unverified();
+			let mut request = eth_call_request(&ownership);
+			let mut magic = ERC1271_MAGIC_VALUE.to_vec();
+			magic.resize(32, 0);
+			request.set_response(output(&magic));
+			state.write().expect_request(request.unwrap());
+
+			assert!(crate::Pallet::<Test>::verify_contract_ownership_ocw(&ownership).is_ok());
+		});
+	}
+
+	#[test]
+	fn verify_contract_ownership_rejects_other_values() {
+		ExtBuilder::default().build_offchain_and_execute_with_state(|state, _| {
+			set_rpc_uri(&Blockchain::Rinkeby, "dummy");
+			let ownership = unverified();
+			let mut request = eth_call_request(&ownership);
+			request.set_response(output(&[0xff; 32]));
+			state.write().expect_request(request.unwrap());
+
+			assert!(matches!(
+				crate::Pallet::<Test>::verify_contract_ownership_ocw(&ownership),
+				Err(OffchainError::InvalidTask(VerificationFailureCause::InvalidContractSignature))
+			));
+		});
+	}
+
+	#[test]
+	fn verify_contract_ownership_treats_revert_as_invalid_signature() {
+		ExtBuilder::default().build_offchain_and_execute_with_state(|state, _| {
+			set_rpc_uri(&Blockchain::Rinkeby, "dummy");
+			let ownership = unverified();
+			let mut request = eth_call_request(&ownership);
+			request.set_response(JsonRpcResponse::<()> {
+				jsonrpc: "2.0".into(),
+				id: 1,
+				error: Some(JsonRpcError { code: 3, message: "execution reverted".into() }),
+				result: None,
+			});
+			state.write().expect_request(request.unwrap());
+
+			assert!(matches!(
+				crate::Pallet::<Test>::verify_contract_ownership_ocw(&ownership),
+				Err(OffchainError::InvalidTask(VerificationFailureCause::InvalidContractSignature))
+			));
+		});
+	}
+}
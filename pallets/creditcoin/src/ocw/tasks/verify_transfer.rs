@@ -1,22 +1,28 @@
 use ethabi::{Function, Param, ParamType, StateMutability, Token};
-use ethereum_types::U64;
+use ethereum_types::{BigEndianHash, H256, U64};
 use frame_support::ensure;
 use frame_system::pallet_prelude::BlockNumberFor;
 
+use alloc::string::String;
+use pallet_offchain_task_scheduler::tasks::TaskV2;
 use sp_core::U256;
+use sp_runtime::offchain::Timestamp;
 use sp_runtime::traits::UniqueSaturatedFrom;
+use sp_std::collections::btree_map::BTreeMap;
 
 #[cfg(not(feature = "std"))]
 use sp_std::prelude::*;
 
 use crate::{
+	helpers::eth_proof::ReceiptLog,
 	ocw::{
 		self, parse_eth_address,
-		rpc::{self, Address, EthBlock, EthTransaction, EthTransactionReceipt},
-		OffchainResult, VerificationFailureCause, VerificationResult, ETH_CONFIRMATIONS,
+		rpc::{self, errors::RpcError, Address, EthBlock, EthTransaction, EthTransactionReceipt},
+		OffchainError, OffchainResult, VerificationFailureCause, VerificationResult,
+		ETH_CONFIRMATIONS,
 	},
 	Blockchain, Config, ExternalAddress, ExternalAmount, ExternalTxId, Id, OrderId, Transfer,
-	TransferKind, UnverifiedTransfer,
+	TransferEvidence, TransferKind, TransferVerification, UnverifiedTransfer,
 };
 
 pub(crate) fn ethless_transfer_function_abi() -> Function {
@@ -46,8 +52,7 @@ pub(in crate::ocw) fn validate_ethless_transfer(
 	transaction: &EthTransaction,
 	eth_tip: U64,
 	id_hash: impl ethereum_types::BigEndianHash<Uint = U256>,
-) -> OffchainResult<()> {
-	let transfer_fn = ethless_transfer_function_abi();
+) -> OffchainResult<Option<u64>> {
 	ensure!(receipt.is_success(), VerificationFailureCause::TaskFailed);
 
 	let block_number = transaction.block_number.ok_or(VerificationFailureCause::TaskPending)?;
@@ -58,17 +63,127 @@ pub(in crate::ocw) fn validate_ethless_transfer(
 
 	ensure!(diff.as_u64() >= ETH_CONFIRMATIONS, VerificationFailureCause::TaskUnconfirmed);
 
-	if let Some(recipient) = &transaction.recipient {
+	match find_transfer_log(receipt, contract, from, to, amount) {
+		// the contract executed the transfer, however the call reached it
+		Some(index) => {
+			validate_embedded_ethless_call(
+				from,
+				to,
+				amount,
+				transaction.calldata(),
+				id_hash.into_uint(),
+			)?;
+			Ok(Some(index))
+		},
+		None => validate_ethless_call(
+			from,
+			to,
+			contract,
+			amount,
+			transaction.recipient.as_ref(),
+			transaction.input(),
+			id_hash,
+		)
+		.map(|()| None),
+	}
+}
+
+/// The position in the receipt of the ERC-20 `Transfer` event `contract` emitted for moving
+/// `amount` from `from` to `to`.
+fn find_transfer_log(
+	receipt: &EthTransactionReceipt,
+	contract: &Address,
+	from: &Address,
+	to: &Address,
+	amount: &ExternalAmount,
+) -> Option<u64> {
+	let index = receipt.logs.iter().position(|log| {
+		!log.removed
+			&& is_transfer_log(&log.address, &log.topics, &log.data.0, contract, from, to, amount)
+	})?;
+	Some(index as u64)
+}
+
+/// Whether the log of `address` is the ERC-20 `Transfer` event `contract` emitted for moving
+/// `amount` from `from` to `to`.
+fn is_transfer_log(
+	address: &Address,
+	topics: &[H256],
+	data: &[u8],
+	contract: &Address,
+	from: &Address,
+	to: &Address,
+	amount: &ExternalAmount,
+) -> bool {
+	let signature = H256(sp_io::hashing::keccak_256(b"Transfer(address,address,uint256)"));
+	let mut value = [0u8; 32];
+	amount.to_big_endian(&mut value);
+
+	address == contract
+		&& topics == [signature, H256::from(*from), H256::from(*to)]
+		&& data == value
+}
+
+/// Looks for the ethless transfer anywhere in `calldata`, as relayers, proxies and multicalls
+/// pass the call on unchanged. The transfer log only vouches for the sender, receiver and
+/// amount, this ties the transfer to the order through the nonce.
+fn validate_embedded_ethless_call(
+	from: &Address,
+	to: &Address,
+	amount: &ExternalAmount,
+	calldata: &[u8],
+	expected_nonce: U256,
+) -> OffchainResult<()> {
+	let transfer_fn = ethless_transfer_function_abi();
+	let selector = transfer_fn.short_signature();
+
+	let mut result: OffchainResult<()> = Err(VerificationFailureCause::AbiMismatch.into());
+	for (offset, _) in calldata.windows(4).enumerate().filter(|(_, w)| *w == selector) {
+		let Ok(inputs) = transfer_fn.decode_input(&calldata[offset + 4..]) else { continue };
+		result = validate_ethless_inputs(from, to, amount, &inputs, expected_nonce);
+		if result.is_ok() {
+			break;
+		}
+	}
+	result
+}
+
+/// Checks that a call of `recipient` with `input` is the ethless transfer described by the
+/// other arguments, wherever the call came from.
+pub(crate) fn validate_ethless_call(
+	from: &Address,
+	to: &Address,
+	contract: &Address,
+	amount: &ExternalAmount,
+	recipient: Option<&Address>,
+	input: &[u8],
+	id_hash: impl ethereum_types::BigEndianHash<Uint = U256>,
+) -> OffchainResult<()> {
+	let transfer_fn = ethless_transfer_function_abi();
+
+	if let Some(recipient) = recipient {
 		ensure!(recipient == contract, VerificationFailureCause::IncorrectContract);
 	} else {
 		return Err(VerificationFailureCause::MissingReceiver.into());
 	}
 
-	let inputs = transfer_fn.decode_input(transaction.input()).map_err(|e| {
+	let inputs = transfer_fn.decode_input(input).map_err(|e| {
 		log::error!("failed to decode inputs: {:?}", e);
 		VerificationFailureCause::AbiMismatch
 	})?;
 
+	validate_ethless_inputs(from, to, amount, &inputs, id_hash.into_uint())
+}
+
+fn validate_ethless_inputs(
+	from: &Address,
+	to: &Address,
+	amount: &ExternalAmount,
+	inputs: &[Token],
+	expected_nonce: U256,
+) -> OffchainResult<()> {
+	let transfer_fn = ethless_transfer_function_abi();
+
 	// IncorrectInputLength and IncorrectInputType are unreachable
 	// under normal circumstances. We get AbiMismatch or InvalidData errors
 	ensure!(
@@ -98,16 +213,47 @@ pub(in crate::ocw) fn validate_ethless_transfer(
 		Some(Token::Uint(value)) => ExternalAmount::from(value),
 		_ => return Err(VerificationFailureCause::IncorrectInputType.into()),
 	};
-	let expected_nonce = id_hash.into_uint();
 	ensure!(nonce == expected_nonce, VerificationFailureCause::IncorrectNonce);
 
 	Ok(())
 }
 
+pub(super) type EthlessResponse = (EthTransaction, EthTransactionReceipt, U64);
+
+/// Outcomes of [`crate::Pallet::prefetch_ethless_transfers`] by task id. A valid transfer comes
+/// with the timestamp of its block, in seconds, if it could be fetched.
+pub type PrefetchedTransfers<Hash> =
+	BTreeMap<Hash, Result<TransferVerification<u64>, VerificationFailureCause>>;
+
+/// Where a validated ethless transfer was mined, `None` if the endpoint left it out. The tips
+/// authorities see differ, so the evidence doesn't record the confirmations; each authority
+/// checked that there were at least `ETH_CONFIRMATIONS`.
+pub(super) fn ethless_evidence(
+	(tx, _, _): &EthlessResponse,
+	log_index: Option<u64>,
+) -> Option<TransferEvidence> {
+	Some(TransferEvidence {
+		block_number: tx.block_number?.as_u64(),
+		block_hash: tx.block_hash?,
+		transaction_index: tx.transaction_index?.as_u64(),
+		log_index,
+	})
+}
+
+/// Endpoints are rarely at the same height, so their responses agree when the transaction and
+/// receipt are identical and the tips are within the confirmation depth of each other.
+pub(super) fn ethless_responses_agree(
+	(tx1, receipt1, tip1): &EthlessResponse,
+	(tx2, receipt2, tip2): &EthlessResponse,
+) -> bool {
+	let lag = if tip1 > tip2 { *tip1 - *tip2 } else { *tip2 - *tip1 };
+	tx1 == tx2 && receipt1 == receipt2 && lag.as_u64() < ETH_CONFIRMATIONS
+}
+
 impl<T: Config> crate::Pallet<T> {
 	pub fn verify_transfer_ocw(
 		transfer: &UnverifiedTransfer<T::AccountId, BlockNumberFor<T>, T::Hash, T::Moment>,
-	) -> VerificationResult<Option<T::Moment>> {
+	) -> VerificationResult<TransferVerification<T::Moment>> {
 		let UnverifiedTransfer {
 			transfer: Transfer { blockchain, kind, order_id, amount, tx_id: tx, .. },
 			from_external: from,
@@ -133,14 +279,57 @@ impl<T: Config> crate::Pallet<T> {
 		order_id: &OrderId<BlockNumberFor<T>, T::Hash>,
 		amount: &ExternalAmount,
 		tx_id: &ExternalTxId,
-	) -> VerificationResult<Option<T::Moment>> {
-		let rpc_url = blockchain.rpc_url()?;
-		let tx = ocw::eth_get_transaction(tx_id, &rpc_url)?;
-		let tx_receipt = rpc::eth_get_transaction_receipt(tx_id, &rpc_url)?;
-		let eth_tip = rpc::eth_get_block_number(&rpc_url)?;
+	) -> VerificationResult<TransferVerification<T::Moment>> {
+		let fetch = |rpc_url: &str| -> OffchainResult<EthlessResponse> {
+			let tx = ocw::eth_get_transaction(tx_id, rpc_url)?;
+			let tx_receipt = rpc::eth_get_transaction_receipt(tx_id, rpc_url)?;
+			let eth_tip = rpc::eth_get_block_number(rpc_url)?;
+			Ok((tx, tx_receipt, eth_tip))
+		};
+		let response = match blockchain.rpc_quorum() {
+			Some(quorum) => {
+				let responses = blockchain.with_quorum(quorum, fetch, ethless_responses_agree)?;
+				// the lowest tip is the most conservative one
+				let eth_tip = responses.iter().map(|(_, _, tip)| *tip).min();
+				let (tx, tx_receipt, _) =
+					responses.into_iter().next().expect("a quorum is at least 2 responses; qed");
+				(tx, tx_receipt, eth_tip.expect("a quorum is at least 2 responses; qed"))
+			},
+			None => blockchain.with_failover(fetch)?,
+		};
 
-		let tx_block_num = tx.block_number;
+		let log_index = Self::validate_ethless_response(
+			contract_address,
+			from,
+			to,
+			order_id,
+			amount,
+			&response,
+		)?;
 
+		let timestamp = if let Some(num) = response.0.block_number {
+			if let Ok(EthBlock { timestamp: block_timestamp, .. }) =
+				blockchain.with_failover(|rpc_url| Ok(rpc::eth_get_block_by_number(num, rpc_url)?))
+			{
+				Some(T::Moment::unique_saturated_from(block_timestamp.as_u64()))
+			} else {
+				None
+			}
+		} else {
+			None
+		};
+
+		Ok(TransferVerification { timestamp, evidence: ethless_evidence(&response, log_index) })
+	}
+
+	fn validate_ethless_response(
+		contract_address: &ExternalAddress,
+		from: &ExternalAddress,
+		to: &ExternalAddress,
+		order_id: &OrderId<BlockNumberFor<T>, T::Hash>,
+		amount: &ExternalAmount,
+		(tx, tx_receipt, eth_tip): &EthlessResponse,
+	) -> OffchainResult<Option<u64>> {
 		let from_addr = parse_eth_address(from)?;
 		let to_addr = parse_eth_address(to)?;
 
@@ -151,25 +340,182 @@ impl<T: Config> crate::Pallet<T> {
 			&to_addr,
 			&ethless_contract,
 			amount,
-			&tx_receipt,
-			&tx,
-			eth_tip,
+			tx_receipt,
+			tx,
+			*eth_tip,
 			T::HashIntoNonce::from(order_id.hash()),
-		)?;
+		)
+	}
+
+	/// Checks the full calldata of a transaction sent to `recipient`, selector included, and the
+	/// `logs` of its receipt against an ethless transfer, like [`validate_ethless_transfer`]:
+	/// through the contract's transfer log when there is one, the direct call otherwise. Returns
+	/// the position of the transfer log among `logs`, `None` for a direct call.
+	pub(crate) fn validate_ethless_calldata(
+		contract_address: &ExternalAddress,
+		from: &ExternalAddress,
+		to: &ExternalAddress,
+		order_id: &OrderId<BlockNumberFor<T>, T::Hash>,
+		amount: &ExternalAmount,
+		recipient: Option<&Address>,
+		calldata: &[u8],
+		logs: &[ReceiptLog],
+	) -> OffchainResult<Option<u64>> {
+		let from = parse_eth_address(from)?;
+		let to = parse_eth_address(to)?;
+		let contract = parse_eth_address(contract_address)?;
+		let id_hash = T::HashIntoNonce::from(order_id.hash());
+
+		let logged = logs.iter().position(|log| {
+			is_transfer_log(&log.address, &log.topics, &log.data, &contract, &from, &to, amount)
+		});
+		if let Some(index) = logged {
+			validate_embedded_ethless_call(&from, &to, amount, calldata, id_hash.into_uint())?;
+			return Ok(Some(index as u64));
+		}
 
-		let timestamp = if let Some(num) = tx_block_num {
-			if let Ok(EthBlock { timestamp: block_timestamp }) =
-				rpc::eth_get_block_by_number(num, &rpc_url)
+		let selector = ethless_transfer_function_abi().short_signature();
+		ensure!(calldata.get(..4) == Some(&selector[..]), VerificationFailureCause::AbiMismatch);
+		validate_ethless_call(&from, &to, &contract, amount, recipient, &calldata[4..], id_hash)
+			.map(|()| None)
+	}
+
+	/// Fetches what [`Self::verify_ethless_transfer`] needs for all of `transfers` at once. The
+	/// transactions and receipts are requested concurrently, or batched per endpoint, together
+	/// with a single `eth_blockNumber` per endpoint; then the block timestamps of the valid
+	/// transfers. Transfers that need a quorum, or whose endpoint failed, are left out and go
+	/// through the usual verification with its failover.
+	pub(crate) fn prefetch_ethless_transfers(
+		transfers: &[(
+			&T::Hash,
+			&UnverifiedTransfer<T::AccountId, BlockNumberFor<T>, T::Hash, T::Moment>,
+		)],
+		until: Timestamp,
+	) -> PrefetchedTransfers<T::Hash> {
+		let mut prefetched = PrefetchedTransfers::new();
+
+		let mut endpoints: Vec<(String, Vec<_>)> = Vec::new();
+		for &(id, transfer) in transfers {
+			let Transfer { blockchain, kind, .. } = &transfer.transfer;
+			if !matches!(kind, TransferKind::Ethless(_))
+				|| blockchain.rpc_quorum().is_some()
+				|| <UnverifiedTransfer<_, _, _, _> as TaskV2<T>>::is_persisted(id)
 			{
-				Some(T::Moment::unique_saturated_from(block_timestamp.as_u64()))
-			} else {
-				None
+				continue;
 			}
-		} else {
-			None
-		};
+			let Some(rpc_url) = blockchain.rpc_urls().ok().and_then(|urls| urls.into_iter().next())
+			else {
+				continue;
+			};
+			if blockchain.check_chain_id(&rpc_url).is_err() {
+				continue;
+			}
+			match endpoints.iter_mut().find(|(url, _)| *url == rpc_url) {
+				Some((_, transfers)) => transfers.push((id, transfer)),
+				None => endpoints.push((rpc_url, vec![(id, transfer)])),
+			}
+		}
 
-		Ok(timestamp)
+		let batched = |rpc_url: &str| ocw::batch_support(rpc_url) != Some(false);
+		let mut requests = Vec::new();
+		for (rpc_url, transfers) in &endpoints {
+			for (_, transfer) in transfers {
+				let tx_id = &transfer.transfer.tx_id;
+				requests.push((rpc_url.as_str(), rpc::get_transaction_request(tx_id)));
+				requests.push((rpc_url.as_str(), rpc::get_transaction_receipt_request(tx_id)));
+			}
+			// the chain tip is shared by every transfer on the endpoint
+			requests.push((rpc_url.as_str(), rpc::block_number_request()));
+		}
+		let mut responses = rpc::send_all(requests, batched, until).into_iter();
+		let mut next = || responses.next().expect("a response for every request; qed");
+
+		let mut valid = Vec::new();
+		for (rpc_url, transfers) in &endpoints {
+			let fetched: Vec<_> = transfers.iter().map(|_| (next(), next())).collect();
+			let eth_tip = next();
+			note_batch_support(rpc_url, fetched.iter().flat_map(|(tx, receipt)| [tx, receipt]));
+			let eth_tip = eth_tip.and_then(rpc::decode::<U64>).ok();
+
+			for ((id, transfer), (tx, receipt)) in transfers.iter().zip(fetched) {
+				let fetched = tx
+					.and_then(rpc::decode::<EthTransaction>)
+					.map_err(ocw::transaction_not_found)
+					.and_then(|tx| {
+						Ok((tx, receipt.and_then(rpc::decode::<EthTransactionReceipt>)?))
+					});
+				let response = match (fetched, eth_tip) {
+					(Ok((tx, receipt)), Some(eth_tip)) => (tx, receipt, eth_tip),
+					(Err(OffchainError::InvalidTask(cause)), _) => {
+						prefetched.insert(**id, Err(cause));
+						continue;
+					},
+					_ => continue,
+				};
+
+				let UnverifiedTransfer {
+					transfer: Transfer { kind, order_id, amount, .. },
+					from_external: from,
+					to_external: to,
+					..
+				} = transfer;
+				let TransferKind::Ethless(contract) = kind else { continue };
+				match Self::validate_ethless_response(
+					contract, from, to, order_id, amount, &response,
+				) {
+					Ok(log_index) => {
+						let evidence = ethless_evidence(&response, log_index);
+						valid.push((**id, rpc_url.as_str(), response.0.block_number, evidence))
+					},
+					Err(OffchainError::InvalidTask(cause)) => {
+						prefetched.insert(**id, Err(cause));
+					},
+					Err(_) => continue,
+				}
+			}
+		}
+
+		let block_requests: Vec<_> = valid
+			.iter()
+			.filter_map(|(_, rpc_url, num, _)| {
+				Some((*rpc_url, rpc::block_by_number_request((*num)?)))
+			})
+			.collect();
+		let mut blocks = rpc::send_all(block_requests, batched, until).into_iter();
+		for (id, _, num, evidence) in valid {
+			let block = num.and_then(|_| blocks.next());
+			let timestamp = block
+				.and_then(|block| block.and_then(rpc::decode::<EthBlock>).ok())
+				.map(|EthBlock { timestamp, .. }| timestamp.as_u64());
+			prefetched.insert(id, Ok(TransferVerification { timestamp, evidence }));
+		}
+
+		prefetched
+	}
+}
+
+/// Remembers whether `rpc_url` answered a batch, judging by the `results` of its requests.
+fn note_batch_support<'a>(
+	rpc_url: &str,
+	mut results: impl Iterator<Item = &'a OffchainResult<serde_json::Value, RpcError>>,
+) {
+	let known = ocw::batch_support(rpc_url);
+	if known == Some(false) {
+		return;
+	}
+	let answered = results.find(|result| {
+		!matches!(
+			result,
+			Err(RpcError::HttpError(_) | RpcError::RequestError(_) | RpcError::Timeout(_))
+		)
+	});
+	match answered {
+		Some(Err(RpcError::BatchUnsupported)) => {
+			log::warn!("RPC endpoint does not support batch requests, sending them one by one");
+			ocw::set_batch_support(rpc_url, false);
+		},
+		Some(_) if known.is_none() => ocw::set_batch_support(rpc_url, true),
+		_ => {},
 	}
 }
 
@@ -179,19 +525,23 @@ mod tests {
 	use crate::mock::AccountId;
 	use crate::mock::RuntimeCall;
 	use crate::mock::RuntimeOrigin as Origin;
+	use crate::mock::Task;
 	use crate::mock::{
-		get_mock_amount, get_mock_contract, get_mock_nonce, get_mock_tx_block_num,
-		get_mock_tx_hash, roll_to_with_ocw, set_rpc_uri, with_failing_create_transaction,
-		Creditcoin, ExtBuilder, MockedRpcRequests, PendingRequestExt, TaskScheduler,
-		TaskSchedulerT, Test, ETHLESS_RESPONSES,
+		get_mock_amount, get_mock_contract, get_mock_evidence, get_mock_nonce,
+		get_mock_tx_block_num, get_mock_tx_hash, pending_rpc_batch_request, roll_to_with_ocw,
+		set_rpc_uri, with_failing_create_transaction, Creditcoin, ExtBuilder, Extrinsic,
+		MockedRpcRequests, PendingRequestExt, TaskScheduler, TaskSchedulerT, Test,
+		ETHLESS_RESPONSES,
 	};
-	use crate::ocw::tasks::Task;
+	use crate::ocw::rpc::{self, errors::RpcError};
 	use crate::ocw::tasks::TaskV2;
 	use crate::ocw::tests::set_up_verify_transfer_env;
 	use crate::tests::{adjust_deal_order_to_nonce, TestInfo};
 	use crate::{Blockchain, LoanTerms, TransferKind};
 	use frame_support::assert_ok;
 	use frame_support::dispatch::Dispatchable;
+	use parity_scale_codec::Decode;
+	use sp_runtime::offchain::Duration;
 	use sp_runtime::traits::IdentifyAccount;
 
 	#[test]
@@ -290,4 +640,96 @@ mod tests {
 			assert!(!TaskScheduler::is_scheduled(&TaskScheduler::deadline(), &id));
 		});
 	}
+
+	#[test]
+	fn pending_transfers_are_fetched_in_batches() {
+		let mut ext = ExtBuilder::default();
+		ext.generate_authority();
+		ext.build_offchain_and_execute_with_state(|state, pool| {
+			let (unverified, _) = set_up_verify_transfer_env(false);
+			let rpc_uri = "http://localhost:8545";
+			crate::ocw::set_batch_support(rpc_uri, true);
+
+			let tx_hash = get_mock_tx_hash();
+			let tx_block_num = get_mock_tx_block_num();
+			let mut state = state.write();
+			state.expect_request(pending_rpc_batch_request(
+				&[
+					("eth_getTransactionByHash", vec![tx_hash.clone().into()]),
+					("eth_getTransactionReceipt", vec![tx_hash.into()]),
+					("eth_blockNumber", vec![]),
+				],
+				rpc_uri,
+				&ETHLESS_RESPONSES,
+			));
+			state.expect_request(pending_rpc_batch_request(
+				&[("eth_getBlockByNumber", vec![tx_block_num.into(), false.into()])],
+				rpc_uri,
+				&ETHLESS_RESPONSES,
+			));
+			drop(state);
+
+			let id = TaskV2::<Test>::to_id(&unverified);
+			TaskScheduler::insert(
+				&TaskScheduler::deadline(),
+				&id,
+				Task::VerifyTransfer(unverified),
+			);
+
+			roll_to_with_ocw(1);
+
+			let tx = pool.write().transactions.pop().expect("persist transfer");
+			assert!(pool.read().transactions.is_empty());
+			let call = Extrinsic::decode(&mut &*tx).unwrap().call;
+			assert!(matches!(
+				call,
+				RuntimeCall::Creditcoin(crate::Call::persist_task_output { .. })
+			));
+		});
+	}
+
+	#[test]
+	fn evidence_does_not_depend_on_the_endpoint_tip() {
+		let result = |method: &str| ETHLESS_RESPONSES[method].result.clone().unwrap();
+		let tx: rpc::EthTransaction =
+			serde_json::from_value(result("eth_getTransactionByHash")).unwrap();
+		let receipt: rpc::EthTransactionReceipt =
+			serde_json::from_value(result("eth_getTransactionReceipt")).unwrap();
+		let evidence = get_mock_evidence();
+
+		for tip in [evidence.block_number + 12, evidence.block_number + 100] {
+			let response = (tx.clone(), receipt.clone(), tip.into());
+			assert_eq!(super::ethless_evidence(&response, evidence.log_index), Some(evidence));
+		}
+	}
+
+	#[test]
+	fn batch_entries_missing_from_the_response_are_transport_errors() {
+		ExtBuilder::default().build_offchain_and_execute_with_state(|state, _| {
+			let rpc_uri = "http://localhost:8545";
+			let mut request = pending_rpc_batch_request(
+				&[("eth_blockNumber", vec![]), ("eth_blockNumber", vec![])],
+				rpc_uri,
+				&ETHLESS_RESPONSES,
+			);
+			let mut response: Vec<serde_json::Value> =
+				serde_json::from_slice(request.response.as_ref().unwrap()).unwrap();
+			response.pop();
+			request.response = Some(serde_json::to_vec(&response).unwrap());
+			state.write().expect_request(request);
+
+			let until = sp_io::offchain::timestamp().add(Duration::from_millis(5_000));
+			let results = rpc::send_all(
+				vec![
+					(rpc_uri, rpc::block_number_request()),
+					(rpc_uri, rpc::block_number_request()),
+				],
+				|_| true,
+				until,
+			);
+
+			assert!(results[0].is_ok());
+			assert!(matches!(results[1], Err(RpcError::RequestError(_))));
+		});
+	}
 }
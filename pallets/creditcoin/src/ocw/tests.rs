@@ -1,30 +1,32 @@
 use super::errors::{
-	RpcUrlError,
+	RpcUrlError, SchedulerError,
 	VerificationFailureCause::{self, *},
 };
 use super::{
 	errors::OffchainError,
 	parse_eth_address,
-	rpc::{Address, EthTransaction, EthTransactionReceipt},
+	rpc::{Address, EthLog, EthTransaction, EthTransactionReceipt},
 	tasks::verify_transfer::ethless_transfer_function_abi,
 	tasks::verify_transfer::validate_ethless_transfer,
-	ETH_CONFIRMATIONS,
+	OffchainResult, ETH_CONFIRMATIONS,
 };
 use crate::tests::adjust_deal_order_to_nonce;
 use crate::Pallet as Creditcoin;
 use crate::{
 	helpers::extensions::HexToAddress,
 	mock::{
-		get_mock_amount, get_mock_contract, get_mock_from_address, get_mock_input_data,
-		get_mock_nonce, get_mock_timestamp, get_mock_to_address, get_mock_tx_block_num,
-		get_mock_tx_hash, roll_to, roll_to_with_ocw, set_rpc_uri, ExtBuilder, Extrinsic,
-		MockedRpcRequests, PendingRequestExt, RuntimeCall as Call, RuntimeOrigin as Origin, RwLock,
-		TaskScheduler, Test, ETHLESS_RESPONSES,
+		get_mock_amount, get_mock_contract, get_mock_evidence, get_mock_from_address,
+		get_mock_input_data, get_mock_nonce, get_mock_timestamp, get_mock_to_address,
+		get_mock_tx_block_num, get_mock_tx_hash, roll_to, roll_to_with_ocw, set_rpc_quorum,
+		set_rpc_uri, set_rpc_uris, set_unchecked_rpc_uri, ExtBuilder, Extrinsic, MockedRpcRequests,
+		PendingRequestExt, RuntimeCall as Call, RuntimeOrigin as Origin, RwLock, TaskScheduler,
+		TaskSchedulerT, Test, ETHLESS_RESPONSES,
 	},
-	ocw::rpc::{errors::RpcError, JsonRpcResponse},
+	ocw::rpc::{errors::RpcError, JsonRpcRequest, JsonRpcResponse},
 	tests::TestInfo,
 	types::{DoubleMapExt, TransferId},
-	Blockchain, ExternalAddress, Id, LoanTerms, OrderId, TransferKind,
+	Blockchain, ExternalAddress, HeaderAttestation, Id, LoanTerms, OrderId, Reverification,
+	TransferEvidence, TransferKind, TransferReverification, TransferReverificationFor,
 };
 use alloc::sync::Arc;
 use assert_matches::assert_matches;
@@ -34,12 +36,12 @@ use ethereum_types::{BigEndianHash, H160, U256, U64};
 use frame_support::{assert_ok, once_cell::sync::Lazy, traits::Get, BoundedVec};
 use frame_system::Pallet as System;
 use pallet_offchain_task_scheduler::tasks::error::TaskError;
-use pallet_offchain_task_scheduler::tasks::ForwardTask;
+use pallet_offchain_task_scheduler::tasks::{ForwardTask, TaskV2};
 use parity_scale_codec::Decode;
 use sp_core::H256;
 use sp_io::offchain;
 use sp_runtime::offchain::storage::MutateStorageError;
-use sp_runtime::offchain::testing::TestOffchainExt;
+use sp_runtime::offchain::testing::{PendingRequest, TestOffchainExt};
 use sp_runtime::offchain::{
 	storage::{StorageRetrievalError, StorageValueRef},
 	testing::OffchainState,
@@ -165,7 +167,7 @@ impl Default for EthlessTestArgs {
 	}
 }
 
-fn test_validate_ethless_transfer(args: EthlessTestArgs) -> Result<(), OffchainError> {
+fn test_validate_ethless_transfer(args: EthlessTestArgs) -> Result<Option<u64>, OffchainError> {
 	let EthlessTestArgs { from, to, contract, amount, receipt, transaction, tip, nonce } = args;
 
 	validate_ethless_transfer(
@@ -309,6 +311,92 @@ fn ethless_transfer_pending() {
 	)
 }
 
+/// An ethless transfer sent through a relayer contract, with the receipt of the relayed call.
+fn relayed_ethless_transfer(
+	input: TransferContractInput,
+	log: Option<EthLog>,
+) -> (EthTransaction, EthTransactionReceipt) {
+	let transfer = ethless_transfer_function_abi().encode_input(&input.into_tokens()).unwrap();
+	let relay =
+		[&[0x1c, 0xff, 0x79, 0xcd][..], &ethabi::encode(&[Token::Bytes(transfer)])].concat();
+	let mut transaction = ETH_TRANSACTION.clone();
+	transaction.recipient = Some(Address::repeat_byte(0x5e));
+	transaction.set_input(&relay);
+	let receipt = EthTransactionReceipt {
+		status: Some(1u64.into()),
+		logs: log.into_iter().collect(),
+		..Default::default()
+	};
+	(transaction, receipt)
+}
+
+/// The `Transfer` event of the default ethless transfer.
+fn ethless_transfer_log() -> EthLog {
+	let signature = sp_io::hashing::keccak_256(b"Transfer(address,address,uint256)");
+	EthLog {
+		address: *ETHLESS_CONTRACT_ADDR,
+		topics: vec![H256(signature), H256::from(*ETHLESS_FROM_ADDR), H256::from(*ETHLESS_TO_ADDR)],
+		data: H256::from_uint(&get_mock_amount()).as_bytes().into(),
+		..Default::default()
+	}
+}
+
+#[test]
+fn ethless_transfer_relayed_valid() {
+	let (transaction, receipt) =
+		relayed_ethless_transfer(TransferContractInput::default(), Some(ethless_transfer_log()));
+	assert_matches!(
+		test_validate_ethless_transfer(EthlessTestArgs {
+			transaction,
+			receipt,
+			..Default::default()
+		}),
+		Ok(Some(0))
+	);
+}
+
+#[test]
+fn ethless_transfer_relayed_without_log() {
+	let (transaction, receipt) = relayed_ethless_transfer(TransferContractInput::default(), None);
+	assert_invalid_task(
+		test_validate_ethless_transfer(EthlessTestArgs {
+			transaction,
+			receipt,
+			..Default::default()
+		}),
+		IncorrectContract,
+	);
+}
+
+#[test]
+fn ethless_transfer_relayed_log_removed() {
+	let log = EthLog { removed: true, ..ethless_transfer_log() };
+	let (transaction, receipt) =
+		relayed_ethless_transfer(TransferContractInput::default(), Some(log));
+	assert_invalid_task(
+		test_validate_ethless_transfer(EthlessTestArgs {
+			transaction,
+			receipt,
+			..Default::default()
+		}),
+		IncorrectContract,
+	);
+}
+
+#[test]
+fn ethless_transfer_relayed_nonce_mismatch() {
+	let input = TransferContractInput { nonce: 1.into(), ..Default::default() };
+	let (transaction, receipt) = relayed_ethless_transfer(input, Some(ethless_transfer_log()));
+	assert_invalid_task(
+		test_validate_ethless_transfer(EthlessTestArgs {
+			transaction,
+			receipt,
+			..Default::default()
+		}),
+		IncorrectNonce,
+	);
+}
+
 #[test]
 fn blockchain_rpc_url_missing() {
 	ExtBuilder::default().build_offchain_and_execute(|| {
@@ -338,6 +426,225 @@ fn blockchain_rpc_url_invalid_scale() {
 	});
 }
 
+fn chain_id_request(uri: &str, chain_id: &str) -> PendingRequest {
+	let request = JsonRpcRequest::new("eth_chainId", None);
+	let response = JsonRpcResponse::<&str> {
+		jsonrpc: "2.0".into(),
+		id: 1,
+		error: None,
+		result: Some(chain_id),
+	};
+	PendingRequest {
+		method: "POST".into(),
+		uri: uri.into(),
+		headers: vec![("Content-Type".into(), "application/json".into())],
+		body: request.to_bytes(),
+		response: Some(serde_json::to_vec(&response).unwrap()),
+		response_headers: vec![("Content-Type".into(), "application/json".into())],
+		sent: true,
+		..Default::default()
+	}
+}
+
+#[test]
+fn blockchain_checked_rpc_url_rejects_wrong_chain_id() {
+	ExtBuilder::default().build_offchain_and_execute_with_state(|state, _| {
+		set_unchecked_rpc_uri(&Blockchain::Ethereum, "dummy");
+		state.write().expect_request(chain_id_request("dummy", "0x5"));
+
+		assert_matches!(
+			Blockchain::Ethereum.checked_rpc_url(),
+			Err(OffchainError::IncorrectChainId)
+		);
+	});
+}
+
+#[test]
+fn blockchain_checked_rpc_url_caches_chain_id() {
+	ExtBuilder::default().build_offchain_and_execute_with_state(|state, _| {
+		set_unchecked_rpc_uri(&Blockchain::Ethereum, "dummy");
+		state.write().expect_request(chain_id_request("dummy", "0x1"));
+
+		assert_eq!(Blockchain::Ethereum.checked_rpc_url().unwrap(), "dummy");
+		// served from the cache, no more requests are expected
+		assert_eq!(Blockchain::Ethereum.checked_rpc_url().unwrap(), "dummy");
+	});
+}
+
+#[test]
+fn blockchain_checked_rpc_url_skips_unknown_chains() {
+	ExtBuilder::default().build_offchain_and_execute(|| {
+		set_unchecked_rpc_uri(&Blockchain::Luniverse, "dummy");
+
+		assert_eq!(Blockchain::Luniverse.checked_rpc_url().unwrap(), "dummy");
+	});
+}
+
+#[test]
+fn blockchain_checked_rpc_url_uses_configured_chain_id() {
+	ExtBuilder::default().build_offchain_and_execute_with_state(|state, _| {
+		set_unchecked_rpc_uri(&Blockchain::Luniverse, "dummy");
+		StorageValueRef::persistent(b"luniverse-rpc-chain-id").set(&42u64);
+		state.write().expect_request(chain_id_request("dummy", "0x1"));
+
+		assert_eq!(Blockchain::Luniverse.rpc_chain_id(), Some(42));
+		assert_matches!(
+			Blockchain::Luniverse.checked_rpc_url(),
+			Err(OffchainError::IncorrectChainId)
+		);
+	});
+}
+
+#[test]
+fn blockchain_rpc_urls_falls_back_to_single_uri() {
+	ExtBuilder::default().build_offchain_and_execute(|| {
+		set_rpc_uri(&Blockchain::Luniverse, "dummy");
+		assert_eq!(Blockchain::Luniverse.rpc_urls().unwrap(), vec!["dummy".to_string()]);
+
+		set_rpc_uris(&Blockchain::Luniverse, &["a", "b"]);
+		assert_eq!(
+			Blockchain::Luniverse.rpc_urls().unwrap(),
+			vec!["a".to_string(), "b".to_string()]
+		);
+		assert_eq!(Blockchain::Luniverse.rpc_quorum(), None);
+	});
+}
+
+#[test]
+fn blockchain_with_failover_skips_failing_endpoints() {
+	ExtBuilder::default().build_offchain_and_execute_with_state(|state, _| {
+		set_rpc_uris(&Blockchain::Ethereum, &["wrong-chain", "good"]);
+		state.write().expect_request(chain_id_request("wrong-chain", "0x5"));
+		state.write().expect_request(chain_id_request("good", "0x1"));
+
+		assert_eq!(Blockchain::Ethereum.with_failover(|url| Ok(url.to_string())).unwrap(), "good");
+	});
+}
+
+#[test]
+fn blockchain_with_failover_does_not_retry_verification_failures() {
+	ExtBuilder::default().build_offchain_and_execute(|| {
+		set_rpc_uris(&Blockchain::Luniverse, &["a", "b"]);
+
+		let mut queried = Vec::new();
+		let result = Blockchain::Luniverse.with_failover(|url| -> OffchainResult<()> {
+			queried.push(url.to_string());
+			Err(TransactionNotFound.into())
+		});
+		assert_matches!(result, Err(OffchainError::InvalidTask(TransactionNotFound)));
+		assert_eq!(queried, vec!["a".to_string()]);
+	});
+}
+
+#[test]
+fn blockchain_with_quorum_returns_agreeing_responses() {
+	ExtBuilder::default().build_offchain_and_execute(|| {
+		set_rpc_uris(&Blockchain::Luniverse, &["a", "b", "c"]);
+		set_rpc_quorum(&Blockchain::Luniverse, 2);
+		assert_eq!(Blockchain::Luniverse.rpc_quorum(), Some(2));
+
+		set_rpc_quorum(&Blockchain::Luniverse, 5);
+		assert_eq!(Blockchain::Luniverse.rpc_quorum(), Some(3));
+
+		let responses = Blockchain::Luniverse
+			.with_quorum(2, |url| Ok(if url == "b" { 2 } else { 1 }), |a, b| a == b)
+			.unwrap();
+		assert_eq!(responses, vec![1, 1]);
+	});
+}
+
+#[test]
+fn blockchain_with_quorum_ignores_a_single_dissenting_endpoint() {
+	ExtBuilder::default().build_offchain_and_execute(|| {
+		set_rpc_uris(&Blockchain::Luniverse, &["a", "b", "c"]);
+
+		let result = Blockchain::Luniverse.with_quorum(
+			2,
+			|url| match url {
+				"a" => Err(TransactionNotFound.into()),
+				"b" => Ok(1),
+				_ => Ok(2),
+			},
+			|a, b| a == b,
+		);
+		assert_matches!(result, Err(OffchainError::QuorumNotReached));
+	});
+}
+
+#[test]
+fn blockchain_with_quorum_fails_when_the_quorum_agrees_on_the_failure() {
+	ExtBuilder::default().build_offchain_and_execute(|| {
+		set_rpc_uris(&Blockchain::Luniverse, &["a", "b"]);
+
+		let result = Blockchain::Luniverse.with_quorum(
+			2,
+			|_| -> OffchainResult<()> { Err(TransactionNotFound.into()) },
+			|_, _| true,
+		);
+		assert_matches!(result, Err(OffchainError::InvalidTask(TransactionNotFound)));
+	});
+}
+
+#[test]
+fn incorrect_chain_id_is_a_scheduler_error() {
+	ExtBuilder::default().build_offchain_and_execute_with_state(|state, _| {
+		let (unverified, _) = set_up_verify_transfer_env(false);
+		set_unchecked_rpc_uri(&Blockchain::Rinkeby, "dummy");
+		state.write().expect_request(chain_id_request("dummy", "0x1"));
+
+		let id = TaskV2::<Test>::to_id(&unverified);
+		assert_matches!(
+			TaskV2::<Test>::persistence_call(&unverified, unverified.deadline, &id),
+			Err(TaskError::Scheduler(SchedulerError::IncorrectChainId))
+		);
+	});
+}
+
+#[test]
+fn expired_transfer_task_fails_verification_with_timeout() {
+	ExtBuilder::default().build_offchain_and_execute(|| {
+		roll_to(1);
+		let (unverified, _) = set_up_verify_transfer_env(false);
+		let id = TaskV2::<Test>::to_id(&unverified);
+		let transfer_id = TransferId::from(id);
+		TaskScheduler::insert(&TaskScheduler::deadline(), &id, unverified.into());
+
+		// rescheduled until the end of its lifetime
+		roll_to(1 + <Test as pallet_offchain_task_scheduler::Config>::MaxTaskLifetime::get());
+
+		assert_eq!(TaskScheduler::task_lifetimes(id), None);
+		let events: Vec<_> = System::<Test>::events().into_iter().map(|r| r.event).collect();
+		assert!(events.contains(&crate::mock::RuntimeEvent::TaskScheduler(
+			pallet_offchain_task_scheduler::Event::TaskExpired(id, 1)
+		)));
+		assert_eq!(
+			events.last(),
+			Some(&crate::mock::RuntimeEvent::Creditcoin(crate::Event::TransferFailedVerification(
+				transfer_id,
+				Timeout
+			)))
+		);
+	});
+}
+
+#[test]
+fn rescheduled_transfer_task_carries_its_new_deadline() {
+	ExtBuilder::default().build_offchain_and_execute(|| {
+		roll_to(1);
+		let (unverified, _) = set_up_verify_transfer_env(false);
+		let id = TaskV2::<Test>::to_id(&unverified);
+		TaskScheduler::insert(&unverified.deadline, &id, unverified.clone().into());
+
+		roll_to(unverified.deadline);
+
+		let (deadline, task) = TaskScheduler::find(&id).expect("rescheduled");
+		assert!(deadline > unverified.deadline);
+		let rescheduled: crate::UnverifiedTransferFor<Test> = task.try_into().unwrap();
+		assert_eq!(rescheduled.deadline, deadline);
+		assert!(TaskScheduler::is_pending(&id));
+	});
+}
+
 #[test]
 fn blockchain_supports_etherlike() {
 	assert!(Blockchain::Ethereum.supports(&crate::TransferKind::Native));
@@ -517,7 +824,10 @@ fn verify_transfer_ocw_works() {
 
 		requests.mock_all(&mut state.write());
 
-		assert_matches!(crate::Pallet::<Test>::verify_transfer_ocw(&unverified), Ok(Some(_)));
+		assert_matches!(crate::Pallet::<Test>::verify_transfer_ocw(&unverified), Ok(verified) => {
+			assert_eq!(verified.timestamp, Some(get_mock_timestamp()));
+			assert_eq!(verified.evidence, Some(get_mock_evidence()));
+		});
 	});
 }
 
@@ -607,7 +917,96 @@ fn verify_transfer_get_block_by_number_error() {
 
 		requests.mock_all(&mut state.write());
 
-		assert_matches!(crate::Pallet::<Test>::verify_transfer_ocw(&unverified), Ok(None));
+		// the evidence doesn't depend on the block
+		assert_matches!(crate::Pallet::<Test>::verify_transfer_ocw(&unverified), Ok(verified) => {
+			assert_eq!(verified.timestamp, None);
+			assert_eq!(verified.evidence, Some(get_mock_evidence()));
+		});
+	});
+}
+
+fn set_up_reverify_transfer_env(
+	evidence: TransferEvidence,
+) -> (TransferReverificationFor<Test>, MockedRpcRequests) {
+	let (unverified, requests) = set_up_verify_transfer_env(false);
+	let transfer = unverified.transfer;
+	let task = TransferReverification {
+		transfer_id: TransferId::new::<Test>(&transfer.blockchain, &transfer.tx_id),
+		blockchain: transfer.blockchain,
+		tx_id: transfer.tx_id,
+		evidence,
+		due: 1,
+	};
+	(task, requests)
+}
+
+#[test]
+fn reverify_transfer_confirms_an_unchanged_block() {
+	ExtBuilder::default().build_offchain_and_execute_with_state(|state, _pool| {
+		crate::mock::roll_to(1);
+		let (task, mut requests) = set_up_reverify_transfer_env(get_mock_evidence());
+
+		state.write().expect_request(requests.get_block_by_number.take().unwrap());
+
+		assert_matches!(crate::Pallet::<Test>::reverify_transfer_ocw(&task), Ok(outcome) => {
+			assert_eq!(outcome, Reverification::Confirmed(get_mock_evidence()));
+		});
+	});
+}
+
+#[test]
+fn reverify_transfer_follows_a_transaction_to_its_new_block() {
+	ExtBuilder::default().build_offchain_and_execute_with_state(|state, _pool| {
+		crate::mock::roll_to(1);
+		// verified in a block that was reorged away
+		let stale = TransferEvidence { block_hash: H256::repeat_byte(1), ..get_mock_evidence() };
+		let (task, mut requests) = set_up_reverify_transfer_env(stale);
+
+		let mut state = state.write();
+		state.expect_request(requests.get_block_by_number.take().unwrap());
+		requests.mock_get_block_number(&mut state);
+
+		let moved = TransferEvidence { log_index: None, ..get_mock_evidence() };
+		assert_matches!(crate::Pallet::<Test>::reverify_transfer_ocw(&task), Ok(outcome) => {
+			assert_eq!(outcome, Reverification::Confirmed(moved));
+		});
+	});
+}
+
+#[test]
+fn reverify_transfer_detects_a_reorged_transaction() {
+	ExtBuilder::default().build_offchain_and_execute_with_state(|state, _pool| {
+		crate::mock::roll_to(1);
+		let stale = TransferEvidence { block_hash: H256::repeat_byte(1), ..get_mock_evidence() };
+		let (task, mut requests) = set_up_reverify_transfer_env(stale);
+		requests.get_transaction.set_empty_response();
+
+		let mut state = state.write();
+		state.expect_request(requests.get_block_by_number.take().unwrap());
+		requests.mock_get_transaction(&mut state);
+
+		assert_matches!(crate::Pallet::<Test>::reverify_transfer_ocw(&task), Ok(outcome) => {
+			assert_eq!(outcome, Reverification::Reorged);
+		});
+	});
+}
+
+#[test]
+fn attest_header_returns_the_hash_of_a_confirmed_block() {
+	ExtBuilder::default().build_offchain_and_execute_with_state(|state, _pool| {
+		crate::mock::roll_to(1);
+		let (_, mut requests) = set_up_verify_transfer_env(false);
+		let evidence = get_mock_evidence();
+		let task =
+			HeaderAttestation { blockchain: Blockchain::Rinkeby, number: evidence.block_number };
+
+		let mut state = state.write();
+		state.expect_request(requests.get_block_by_number.take().unwrap());
+		state.expect_request(requests.get_block_number.take().unwrap());
+
+		assert_matches!(crate::Pallet::<Test>::attest_header_ocw(&task), Ok(hash) => {
+			assert_eq!(hash, evidence.block_hash);
+		});
 	});
 }
 
@@ -714,7 +1113,8 @@ fn unconfirmed_verify_transfer_retries() {
 		let id = TransferId::leaked_inner_hash::<Test>(&blockchain, &tx_hash.hex_to_address());
 		let task = TaskScheduler::pending_tasks(deadline, id).unwrap();
 
-		let err = ForwardTask::<Test>::forward_task(&task, deadline).expect_err("TaskUnconfirmed");
+		let err = ForwardTask::<Test>::forward_task(&task, deadline, None, &Default::default())
+			.expect_err("TaskUnconfirmed");
 		assert_matches!(err, TaskError::Evaluation(_));
 	});
 }
@@ -767,7 +1167,8 @@ fn luniverse_succeeds_with_fake_nonce() {
 			Call::Creditcoin(crate::Call::fail_task {
 				task_id: transfer_id.clone().into(),
 				deadline,
-				cause: VerificationFailureCause::IncorrectNonce
+				cause: VerificationFailureCause::IncorrectNonce,
+				assignment: None,
 			})
 		);
 
@@ -800,6 +1201,7 @@ fn luniverse_succeeds_with_fake_nonce() {
 			account_id: lender,
 			tx_id: tx_hash.hex_to_address(),
 			timestamp: Some(get_mock_timestamp()),
+			evidence: Some(get_mock_evidence()),
 		};
 
 		//We expect the guard to expire on the next roll, sleep to meet time requirements.
@@ -816,7 +1218,8 @@ fn luniverse_succeeds_with_fake_nonce() {
 			verify_tx.call,
 			Call::Creditcoin(crate::Call::persist_task_output {
 				task_output: (transfer_id, expected_transfer).into(),
-				deadline: deadline_2
+				deadline: deadline_2,
+				assignment: None,
 			})
 		);
 	});
@@ -11,18 +11,22 @@ use crate::{
 	types::{DoubleMapExt, OwnershipProof},
 	AddressId, AskOrder, AskOrderId, BidOrder, BidOrderId, Blockchain, DealOrder, DealOrderId,
 	DealOrders, Duration, ExternalAddress, ExternalAmount, Guid, Id, LegacySighash, LoanTerms,
-	Offer, OfferId, OrderId, Transfer, TransferId, TransferKind, Transfers, WeightInfo,
+	Offer, OfferId, OrderId, Reverification, Transfer, TransferEvidence, TransferId, TransferKind,
+	Transfers, WeightInfo,
 };
 use assert_matches::assert_matches;
-use bstr::B;
 use ethereum_types::{BigEndianHash, H256, U256};
-use frame_support::{assert_noop, assert_ok, BoundedVec};
+use frame_support::{
+	assert_noop, assert_ok,
+	dispatch::DispatchResultWithPostInfo,
+	traits::{Currency, Get, Hooks},
+	BoundedVec,
+};
 use frame_system::RawOrigin;
 use pallet_offchain_task_scheduler::authority::AuthorityController;
 use parity_scale_codec::Encode;
 use sp_core::Pair;
 use sp_runtime::{
-	offchain::storage::StorageValueRef,
 	traits::{BadOrigin, IdentifyAccount},
 	AccountId32, MultiSigner,
 };
@@ -329,6 +333,7 @@ impl TestInfo {
 				is_processed: false,
 				account_id: from.account_id.clone(),
 				timestamp: None,
+				evidence: None,
 			},
 		)
 	}
@@ -517,8 +522,7 @@ fn verify_ethless_transfer() {
 		let tx_hash = get_mock_tx_hash();
 		let contract = get_mock_contract().hex_to_address();
 		let tx_block_num = get_mock_tx_block_num();
-		let rpc_url_storage = StorageValueRef::persistent(B("rinkeby-rpc-uri"));
-		rpc_url_storage.set(&dummy_url);
+		set_rpc_uri(&Blockchain::Rinkeby, dummy_url);
 
 		MockedRpcRequests::new(dummy_url, &tx_hash, &tx_block_num, &ETHLESS_RESPONSES)
 			.mock_all(&mut state.write());
@@ -2388,7 +2392,8 @@ fn verify_transfer_should_error_when_not_signed() {
 			Creditcoin::persist_task_output(
 				Origin::none(),
 				deadline,
-				(transfer_id, transfer).into()
+				(transfer_id, transfer).into(),
+				None
 			),
 			BadOrigin
 		);
@@ -2407,6 +2412,7 @@ fn verify_transfer_should_error_when_signer_not_authorized() {
 				Origin::signed(test_info.lender.account_id),
 				deadline,
 				(transfer_id, transfer).into(),
+				None,
 			),
 			crate::Error::<Test>::InsufficientAuthority,
 		);
@@ -2434,6 +2440,7 @@ fn verify_transfer_should_error_when_transfer_has_already_been_registered() {
 				Origin::signed(test_info.lender.account_id),
 				deadline,
 				(transfer_id, transfer).into(),
+				None,
 			),
 			non_paying_error(crate::Error::<Test>::TransferAlreadyRegistered),
 		);
@@ -2471,6 +2478,7 @@ fn verify_transfer_should_work() {
 			is_processed: false,
 			account_id: test_info.lender.account_id.clone(),
 			timestamp: None,
+			evidence: None,
 		};
 		let deadline = Test::unverified_transfer_deadline();
 
@@ -2478,6 +2486,7 @@ fn verify_transfer_should_work() {
 			Origin::signed(test_info.lender.account_id),
 			deadline,
 			(transfer_id.clone(), transfer.clone()).into(),
+			None,
 		));
 
 		let mut all_events = <frame_system::Pallet<Test>>::events();
@@ -2495,6 +2504,281 @@ fn verify_transfer_should_work() {
 	});
 }
 
+#[test]
+fn verify_transfer_should_wait_for_attestation_threshold() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+
+		let test_info = TestInfo::new_defaults();
+		let authorities =
+			[test_info.lender.account_id.clone(), test_info.borrower.account_id.clone()];
+		for authority in &authorities {
+			assert_ok!(Creditcoin::add_authority(RawOrigin::Root.into(), authority.clone()));
+		}
+		assert_ok!(Creditcoin::set_attestation_threshold(RawOrigin::Root.into(), 2));
+
+		let (deal_order_id, deal_order) = test_info.create_deal_order();
+		let tx = "0xafafaf".as_bytes().into_bounded();
+		let transfer_id = TransferId::new::<Test>(&Blockchain::Rinkeby, &tx);
+		let transfer = Transfer {
+			blockchain: test_info.blockchain.clone(),
+			kind: TransferKind::Native,
+			from: test_info.lender.address_id.clone(),
+			to: test_info.borrower.address_id.clone(),
+			order_id: OrderId::Deal(deal_order_id),
+			amount: deal_order.terms.amount,
+			tx_id: tx,
+			block: System::block_number(),
+			is_processed: false,
+			account_id: test_info.lender.account_id.clone(),
+			timestamp: None,
+			evidence: None,
+		};
+		let deadline = Test::unverified_transfer_deadline();
+
+		assert_ok!(Creditcoin::persist_task_output(
+			Origin::signed(authorities[0].clone()),
+			deadline,
+			(transfer_id.clone(), transfer.clone()).into(),
+			None,
+		));
+		assert!(!Transfers::<Test>::contains_key(&transfer_id));
+
+		assert_noop!(
+			Creditcoin::persist_task_output(
+				Origin::signed(authorities[0].clone()),
+				deadline,
+				(transfer_id.clone(), transfer.clone()).into(),
+				None,
+			),
+			non_paying_error(crate::Error::<Test>::AlreadyAttested),
+		);
+
+		assert_ok!(Creditcoin::persist_task_output(
+			Origin::signed(authorities[1].clone()),
+			deadline,
+			(transfer_id.clone(), transfer.clone()).into(),
+			None,
+		));
+		assert_eq!(Transfers::<Test>::get(&transfer_id), Some(transfer));
+	});
+}
+
+#[test]
+fn set_attestation_threshold_errors_for_non_root() {
+	ExtBuilder::default().build_and_execute(|| {
+		let acct: AccountId = AccountId::new([0; 32]);
+
+		assert_noop!(Creditcoin::set_attestation_threshold(Origin::signed(acct), 2), BadOrigin);
+	});
+}
+
+#[test]
+fn set_attestation_threshold_should_error_when_zero() {
+	ExtBuilder::default().build_and_execute(|| {
+		assert_noop!(
+			Creditcoin::set_attestation_threshold(RawOrigin::Root.into(), 0),
+			crate::Error::<Test>::InvalidAttestationThreshold
+		);
+	});
+}
+
+fn unregistered_transfer(
+	test_info: &TestInfo,
+) -> (TransferId<H256>, Transfer<AccountId, u64, H256, u64>) {
+	let (deal_order_id, deal_order) = test_info.create_deal_order();
+	let tx = "0xafafaf".as_bytes().into_bounded();
+	let transfer_id = TransferId::new::<Test>(&Blockchain::Rinkeby, &tx);
+	let transfer = Transfer {
+		blockchain: test_info.blockchain.clone(),
+		kind: TransferKind::Native,
+		from: test_info.lender.address_id.clone(),
+		to: test_info.borrower.address_id.clone(),
+		order_id: OrderId::Deal(deal_order_id),
+		amount: deal_order.terms.amount,
+		tx_id: tx,
+		block: System::block_number(),
+		is_processed: false,
+		account_id: test_info.lender.account_id.clone(),
+		timestamp: None,
+		evidence: None,
+	};
+	(transfer_id, transfer)
+}
+
+#[test]
+fn verify_transfer_should_error_when_authority_is_not_bonded() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+		AuthorityBond::set(&1_000);
+
+		let test_info = TestInfo::new_defaults();
+		let authority = test_info.lender.account_id.clone();
+		assert_ok!(Creditcoin::add_authority(RawOrigin::Root.into(), authority.clone()));
+		let (transfer_id, transfer) = unregistered_transfer(&test_info);
+		let deadline = Test::unverified_transfer_deadline();
+
+		assert_noop!(
+			Creditcoin::persist_task_output(
+				Origin::signed(authority.clone()),
+				deadline,
+				(transfer_id.clone(), transfer.clone()).into(),
+				None,
+			),
+			crate::Error::<Test>::InsufficientBond,
+		);
+
+		Balances::make_free_balance_be(&authority, 1_000_000);
+		assert_ok!(Creditcoin::bond_authority(Origin::signed(authority.clone()), 1_000));
+		assert_ok!(Creditcoin::persist_task_output(
+			Origin::signed(authority),
+			deadline,
+			(transfer_id.clone(), transfer.clone()).into(),
+			None,
+		));
+		assert_eq!(Transfers::<Test>::get(&transfer_id), Some(transfer));
+	});
+}
+
+#[test]
+fn verify_transfer_should_reward_the_authority() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+		TaskReward::set(&100);
+
+		let test_info = TestInfo::new_defaults();
+		let authority = test_info.lender.account_id.clone();
+		assert_ok!(Creditcoin::add_authority(RawOrigin::Root.into(), authority.clone()));
+		Balances::make_free_balance_be(&TaskScheduler::reward_pot(), 1_000_000);
+		Balances::make_free_balance_be(&authority, 1_000_000);
+		let (transfer_id, transfer) = unregistered_transfer(&test_info);
+
+		assert_ok!(Creditcoin::persist_task_output(
+			Origin::signed(authority.clone()),
+			Test::unverified_transfer_deadline(),
+			(transfer_id, transfer).into(),
+			None,
+		));
+		assert_eq!(Balances::free_balance(&authority), 1_000_100);
+	});
+}
+
+#[test]
+fn slash_authority_errors_for_non_root() {
+	ExtBuilder::default().build_and_execute(|| {
+		let acct: AccountId = AccountId::new([0; 32]);
+
+		assert_noop!(
+			Creditcoin::slash_authority(Origin::signed(acct.clone()), acct, H256::zero(), 1),
+			BadOrigin
+		);
+	});
+}
+
+#[test]
+fn slash_authority_should_slash_the_bond() {
+	ExtBuilder::default().build_and_execute(|| {
+		let authority: AccountId = AccountId::new([1; 32]);
+		Balances::make_free_balance_be(&authority, 1_000_000);
+		assert_ok!(Creditcoin::bond_authority(Origin::signed(authority.clone()), 10_000));
+
+		assert_ok!(Creditcoin::slash_authority(
+			RawOrigin::Root.into(),
+			authority.clone(),
+			H256::zero(),
+			4_000
+		));
+		assert_eq!(Balances::reserved_balance(&authority), 6_000);
+		assert_eq!(Balances::free_balance(TaskScheduler::reward_pot()), 4_000);
+	});
+}
+
+fn rotation_signature(
+	new_key: &sp_core::sr25519::Pair,
+	old: &AccountId,
+	new: &AccountId,
+) -> crate::mock::Signature {
+	let payload = crate::helpers::signing::domain_separated_payload::<Test>(
+		crate::helpers::SigningDomain::RotateAuthority,
+		&(old, new).encode(),
+	);
+	new_key.sign(&payload).into()
+}
+
+#[test]
+fn rotate_authority_should_work_for_root() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+		let (old, new) = (AccountId::new([1; 32]), AccountId::new([2; 32]));
+		assert_ok!(Creditcoin::add_authority(RawOrigin::Root.into(), old.clone()));
+
+		assert_ok!(Creditcoin::rotate_authority(
+			RawOrigin::Root.into(),
+			old.clone(),
+			new.clone(),
+			None
+		));
+
+		assert!(!TaskScheduler::is_authority(&old));
+		assert!(TaskScheduler::is_authority(&new));
+		assert_noop!(
+			Creditcoin::rotate_authority(RawOrigin::Root.into(), old, new, None),
+			crate::Error::<Test>::NotAnAuthority
+		);
+	});
+}
+
+#[test]
+fn rotate_authority_should_work_for_the_old_authority_with_the_new_key() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+		let new_key = sp_core::sr25519::Pair::from_seed(&[2; 32]);
+		let (old, new) = (AccountId::new([1; 32]), AccountId::new(new_key.public().0));
+		assert_ok!(Creditcoin::add_authority(RawOrigin::Root.into(), old.clone()));
+		let signature = rotation_signature(&new_key, &old, &new);
+
+		assert_noop!(
+			Creditcoin::rotate_authority(
+				Origin::signed(new.clone()),
+				old.clone(),
+				new.clone(),
+				Some(signature.clone())
+			),
+			BadOrigin
+		);
+		assert_noop!(
+			Creditcoin::rotate_authority(
+				Origin::signed(old.clone()),
+				old.clone(),
+				new.clone(),
+				None
+			),
+			crate::Error::<Test>::InvalidSignature
+		);
+		let wrong_key = sp_core::sr25519::Pair::from_seed(&[3; 32]);
+		assert_noop!(
+			Creditcoin::rotate_authority(
+				Origin::signed(old.clone()),
+				old.clone(),
+				new.clone(),
+				Some(rotation_signature(&wrong_key, &old, &new))
+			),
+			crate::Error::<Test>::InvalidSignature
+		);
+
+		assert_ok!(Creditcoin::rotate_authority(
+			Origin::signed(old.clone()),
+			old.clone(),
+			new.clone(),
+			Some(signature)
+		));
+		assert!(TaskScheduler::is_authority(&new));
+		System::assert_last_event(
+			pallet_offchain_task_scheduler::Event::<Test>::AuthorityRotated(old, new).into(),
+		);
+	});
+}
+
 #[test]
 fn fail_transfer_should_work() {
 	ExtBuilder::default().build_and_execute(|| {
@@ -2508,19 +2792,18 @@ fn fail_transfer_should_work() {
 			test_info.lender.account_id.clone(),
 		));
 
-		let _ = test_info.create_deal_order();
-
-		let tx = "0xafafaf".hex_to_address();
-		let transfer_id = TransferId::new::<Test>(&Blockchain::Rinkeby, &tx);
+		let (deal_order_id, _) = test_info.create_deal_order();
+		let (transfer_id, pending) =
+			register_funding_transfer_for_verification(&test_info, &deal_order_id);
 
 		let failure_cause = crate::ocw::errors::VerificationFailureCause::TaskFailed;
-		let deadline = Test::unverified_transfer_deadline();
 
 		assert_ok!(Creditcoin::fail_task(
 			Origin::signed(test_info.lender.account_id),
-			deadline,
+			pending.deadline,
 			transfer_id.clone().into(),
-			failure_cause
+			failure_cause,
+			None
 		));
 
 		let mut all_events = System::events();
@@ -2532,6 +2815,112 @@ fn fail_transfer_should_work() {
 				assert_eq!(cause, failure_cause);
 			}
 		);
+		assert!(!TaskScheduler::is_pending(&transfer_id.into_inner()));
+	})
+}
+
+#[test]
+fn fail_transfer_should_error_when_task_not_pending() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+
+		let test_info = TestInfo::new_defaults();
+		assert_ok!(Creditcoin::add_authority(
+			RawOrigin::Root.into(),
+			test_info.lender.account_id.clone(),
+		));
+
+		let tx = "0xafafaf".hex_to_address();
+		let transfer_id = TransferId::new::<Test>(&Blockchain::Rinkeby, &tx);
+
+		assert_noop!(
+			Creditcoin::fail_task(
+				Origin::signed(test_info.lender.account_id),
+				Test::unverified_transfer_deadline(),
+				transfer_id.into(),
+				crate::ocw::errors::VerificationFailureCause::TaskFailed,
+				None
+			),
+			crate::Error::<Test>::TaskNotPending
+		);
+	})
+}
+
+#[test]
+fn fail_transfer_should_wait_for_the_attestation_threshold() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+
+		let test_info = TestInfo::new_defaults();
+		let authorities = [AccountId::new([1; 32]), AccountId::new([2; 32])];
+		for authority in &authorities {
+			assert_ok!(Creditcoin::add_authority(RawOrigin::Root.into(), authority.clone()));
+		}
+		assert_ok!(Creditcoin::set_attestation_threshold(RawOrigin::Root.into(), 2));
+
+		let (deal_order_id, _) = test_info.create_deal_order();
+		let (transfer_id, pending) =
+			register_funding_transfer_for_verification(&test_info, &deal_order_id);
+		let fail = |authority: &AccountId| {
+			Creditcoin::fail_task(
+				Origin::signed(authority.clone()),
+				pending.deadline,
+				transfer_id.clone().into(),
+				crate::ocw::errors::VerificationFailureCause::TaskFailed,
+				None,
+			)
+		};
+
+		assert_ok!(fail(&authorities[0]));
+		assert_eq!(Creditcoin::failed_transfers(&transfer_id), None);
+		assert!(TaskScheduler::is_pending(&transfer_id.clone().into_inner()));
+		assert_noop!(fail(&authorities[0]), crate::Error::<Test>::AlreadyAttested);
+
+		assert_ok!(fail(&authorities[1]));
+		assert!(Creditcoin::failed_transfers(&transfer_id).is_some());
+		assert!(!TaskScheduler::is_pending(&transfer_id.into_inner()));
+	})
+}
+
+#[test]
+fn fail_transfer_should_reward_only_the_agreeing_authorities() {
+	use crate::ocw::errors::VerificationFailureCause::{TaskFailed, TaskNonexistent};
+
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+		TaskReward::set(&100);
+		Balances::make_free_balance_be(&TaskScheduler::reward_pot(), 1_000_000);
+
+		let test_info = TestInfo::new_defaults();
+		let authorities =
+			[AccountId::new([1; 32]), AccountId::new([2; 32]), AccountId::new([3; 32])];
+		for authority in &authorities {
+			assert_ok!(Creditcoin::add_authority(RawOrigin::Root.into(), authority.clone()));
+			Balances::make_free_balance_be(authority, 1_000_000);
+		}
+		assert_ok!(Creditcoin::set_attestation_threshold(RawOrigin::Root.into(), 2));
+
+		let (deal_order_id, _) = test_info.create_deal_order();
+		let (transfer_id, pending) =
+			register_funding_transfer_for_verification(&test_info, &deal_order_id);
+		let fail = |authority: &AccountId, cause| {
+			Creditcoin::fail_task(
+				Origin::signed(authority.clone()),
+				pending.deadline,
+				transfer_id.clone().into(),
+				cause,
+				None,
+			)
+		};
+
+		assert_ok!(fail(&authorities[0], TaskFailed));
+		assert_eq!(Balances::free_balance(&authorities[0]), 1_000_000);
+		assert_ok!(fail(&authorities[2], TaskNonexistent));
+
+		assert_ok!(fail(&authorities[1], TaskFailed));
+		assert_eq!(Balances::free_balance(&authorities[0]), 1_000_100);
+		assert_eq!(Balances::free_balance(&authorities[1]), 1_000_100);
+		assert_eq!(Balances::free_balance(&authorities[2]), 1_000_000);
 	})
 }
 
@@ -2551,7 +2940,13 @@ fn fail_transfer_should_error_when_not_signed() {
 		let deadline = Test::unverified_transfer_deadline();
 
 		assert_noop!(
-			Creditcoin::fail_task(Origin::none(), deadline, transfer_id.into(), failure_cause),
+			Creditcoin::fail_task(
+				Origin::none(),
+				deadline,
+				transfer_id.into(),
+				failure_cause,
+				None
+			),
 			BadOrigin
 		);
 	})
@@ -2577,7 +2972,8 @@ fn fail_transfer_should_error_when_not_authority() {
 				Origin::signed(test_info.lender.account_id),
 				deadline,
 				transfer_id.into(),
-				failure_cause
+				failure_cause,
+				None
 			),
 			crate::Error::<Test>::InsufficientAuthority
 		);
@@ -2609,56 +3005,385 @@ fn fail_transfer_should_error_when_transfer_registered() {
 				Origin::signed(test_info.lender.account_id),
 				deadline,
 				transfer_id.into(),
-				failure_cause
+				failure_cause,
+				None
 			),
 			crate::Error::<Test>::TransferAlreadyRegistered
 		);
 	})
 }
 
+fn register_funding_transfer_for_verification(
+	test_info: &TestInfo,
+	deal_order_id: &TestDealOrderId,
+) -> (TransferId<Hash>, crate::UnverifiedTransferFor<Test>) {
+	let tx = "0xfafafa";
+	assert_ok!(Creditcoin::register_funding_transfer(
+		Origin::signed(test_info.lender.account_id.clone()),
+		TransferKind::Native,
+		deal_order_id.clone(),
+		tx.as_bytes().into_bounded()
+	));
+	let transfer_id = TransferId::new::<Test>(&test_info.blockchain, tx.as_bytes());
+	let (_, task, _) = TaskScheduler::find_pending_task(&transfer_id.clone().into_inner()).unwrap();
+	(transfer_id, task.try_into().unwrap())
+}
+
 #[test]
-fn on_initialize_removes_expired_deals_without_transfers() {
-	ExtBuilder::default().build_offchain_and_execute(|| {
+fn cancel_transfer_should_only_be_allowed_for_the_registrant() {
+	ExtBuilder::default().build_and_execute(|| {
 		System::set_block_number(1);
-		let mut expected_deal_orders = Vec::new();
-
-		let now = System::block_number();
-		for expiration_block in now..=20 {
-			let seed1 = format!("{:02}0", expiration_block.clone());
-			let seed2 = format!("{:02}1", expiration_block.clone());
 
-			let test_info = TestInfo {
-				lender: RegisteredAddress::new(&seed1, Blockchain::Rinkeby),
-				borrower: RegisteredAddress::new(&seed2, Blockchain::Rinkeby),
-				blockchain: Blockchain::Rinkeby,
-				loan_terms: LoanTerms {
-					amount: 2_000_000u64.into(),
-					interest_rate: Default::default(),
-					term_length: Duration::from_millis(1_000_000),
-				},
-				ask_guid: format!("{:?}-ask-guid", expiration_block.clone())
-					.as_bytes()
-					.into_bounded(),
-				bid_guid: format!("{:?}-bid-guid", expiration_block.clone())
-					.as_bytes()
-					.into_bounded(),
-				expiration_block,
-			};
+		let test_info = TestInfo::new_defaults();
+		let (deal_order_id, _) = test_info.create_deal_order();
+		let (transfer_id, _) =
+			register_funding_transfer_for_verification(&test_info, &deal_order_id);
 
-			let (offer_id, _) = test_info.create_offer();
-			assert_ok!(Creditcoin::add_deal_order(
+		assert_noop!(
+			Creditcoin::cancel_transfer(
 				Origin::signed(test_info.borrower.account_id.clone()),
-				offer_id.clone(),
-				expiration_block,
-			));
-			let deal_order_id = DealOrderId::new::<Test>(expiration_block, &offer_id);
-			let deal_order =
-				Creditcoin::deal_orders(deal_order_id.expiration(), deal_order_id.hash()).unwrap();
+				transfer_id.clone()
+			),
+			crate::Error::<Test>::TransferAccountMismatch
+		);
 
-			// fund only deal orders which expire at even blocks
-			if expiration_block % 2 == 0 {
-				let tx = format!("0xfafafa{:02}", expiration_block.clone());
-				assert_ok!(Creditcoin::register_funding_transfer(
+		assert_ok!(Creditcoin::cancel_transfer(
+			Origin::signed(test_info.lender.account_id.clone()),
+			transfer_id.clone()
+		));
+		System::assert_last_event(
+			crate::Event::<Test>::TransferCancelled(transfer_id.clone()).into(),
+		);
+		assert!(TaskScheduler::find_pending_task(&transfer_id.clone().into_inner()).is_none());
+
+		assert_noop!(
+			Creditcoin::cancel_transfer(
+				Origin::signed(test_info.lender.account_id.clone()),
+				transfer_id.clone()
+			),
+			crate::Error::<Test>::NonExistentTransfer
+		);
+
+		// the same transfer can be registered again right away
+		register_funding_transfer_for_verification(&test_info, &deal_order_id);
+	});
+}
+
+#[test]
+fn outputs_of_a_cancelled_transfer_task_are_rejected() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+
+		let test_info = TestInfo::new_defaults();
+		let authority = AccountId::new([1; 32]);
+		assert_ok!(Creditcoin::add_authority(RawOrigin::Root.into(), authority.clone()));
+
+		let (deal_order_id, _) = test_info.create_deal_order();
+		let (transfer_id, pending) =
+			register_funding_transfer_for_verification(&test_info, &deal_order_id);
+		assert_ok!(Creditcoin::cancel_transfer(
+			Origin::signed(test_info.lender.account_id.clone()),
+			transfer_id.clone()
+		));
+
+		let stale = Transfer { amount: ExternalAmount::from(1), ..pending.transfer.clone() };
+		let persist = |transfer: &Transfer<_, _, _, _>| {
+			Creditcoin::persist_task_output(
+				Origin::signed(authority.clone()),
+				pending.deadline,
+				(transfer_id.clone(), transfer.clone()).into(),
+				None,
+			)
+		};
+		let fail = || {
+			Creditcoin::fail_task(
+				Origin::signed(authority.clone()),
+				pending.deadline,
+				transfer_id.clone().into(),
+				crate::ocw::errors::VerificationFailureCause::TaskFailed,
+				None,
+			)
+		};
+		assert_noop!(persist(&stale), non_paying_error(crate::Error::<Test>::StaleTaskOutput));
+		assert_noop!(fail(), crate::Error::<Test>::StaleTaskOutput);
+
+		System::set_block_number(2);
+		let (_, registered_again) =
+			register_funding_transfer_for_verification(&test_info, &deal_order_id);
+		assert_ne!(registered_again.deadline, pending.deadline);
+		assert_noop!(persist(&stale), non_paying_error(crate::Error::<Test>::StaleTaskOutput));
+		// a failure of the cancelled registration can't fail the new one
+		assert_noop!(fail(), crate::Error::<Test>::StaleTaskOutput);
+		assert_ok!(persist(&registered_again.transfer));
+		assert!(!crate::CancelledTransfers::<Test>::contains_key(&transfer_id));
+	});
+}
+
+#[test]
+fn cancellations_of_transfers_not_registered_again_are_pruned() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+
+		let test_info = TestInfo::new_defaults();
+		let (deal_order_id, _) = test_info.create_deal_order();
+		let (transfer_id, _) =
+			register_funding_transfer_for_verification(&test_info, &deal_order_id);
+		assert_ok!(Creditcoin::cancel_transfer(
+			Origin::signed(test_info.lender.account_id.clone()),
+			transfer_id.clone()
+		));
+		assert_eq!(crate::CancelledTransfers::<Test>::get(&transfer_id), Some(1));
+
+		// the mock keeps cancellations for 10 blocks
+		Creditcoin::on_initialize(10);
+		assert!(crate::CancelledTransfers::<Test>::contains_key(&transfer_id));
+		Creditcoin::on_initialize(11);
+		assert!(!crate::CancelledTransfers::<Test>::contains_key(&transfer_id));
+		assert!(crate::CancelledTransfersAt::<Test>::get(1).is_empty());
+	});
+}
+
+#[test]
+fn failed_transfers_are_recorded_and_fatal_failures_cannot_be_resubmitted() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+
+		let test_info = TestInfo::new_defaults();
+		let authority = AccountId::new([1; 32]);
+		assert_ok!(Creditcoin::add_authority(RawOrigin::Root.into(), authority.clone()));
+
+		let (deal_order_id, _) = test_info.create_deal_order();
+		let fail = |cause| {
+			let (transfer_id, pending) =
+				register_funding_transfer_for_verification(&test_info, &deal_order_id);
+			assert_ok!(Creditcoin::fail_task(
+				Origin::signed(authority.clone()),
+				pending.deadline,
+				transfer_id.clone().into(),
+				cause,
+				None,
+			));
+			transfer_id
+		};
+
+		let unconfirmed = crate::ocw::errors::VerificationFailureCause::TaskUnconfirmed;
+		let transfer_id = fail(unconfirmed);
+		assert_eq!(
+			Creditcoin::failed_transfers(&transfer_id),
+			Some(crate::FailedTransfer {
+				cause: unconfirmed,
+				authority: Some(authority.clone()),
+				block: 1,
+			})
+		);
+
+		// missing confirmations may be retried, and so may timeouts
+		let timeout = crate::ocw::errors::VerificationFailureCause::Timeout;
+		System::set_block_number(2);
+		fail(timeout);
+		assert_eq!(Creditcoin::failed_transfers(&transfer_id).unwrap().cause, timeout);
+
+		let incorrect_amount = crate::ocw::errors::VerificationFailureCause::IncorrectAmount;
+		System::set_block_number(3);
+		fail(incorrect_amount);
+		assert_eq!(Creditcoin::failed_transfers(&transfer_id).unwrap().cause, incorrect_amount);
+
+		assert_noop!(
+			Creditcoin::register_funding_transfer(
+				Origin::signed(test_info.lender.account_id.clone()),
+				TransferKind::Native,
+				deal_order_id.clone(),
+				"0xfafafa".as_bytes().into_bounded()
+			),
+			crate::Error::<Test>::TransferVerificationFailed
+		);
+	});
+}
+
+/// Verifies a funding transfer for `deal_order_id` with evidence, which schedules its
+/// re-verification.
+fn verify_funding_transfer_with_evidence(
+	test_info: &TestInfo,
+	deal_order_id: &TestDealOrderId,
+	authority: &AccountId,
+) -> TransferId<Hash> {
+	let (transfer_id, pending) =
+		register_funding_transfer_for_verification(test_info, deal_order_id);
+	let transfer = Transfer { evidence: Some(get_mock_evidence()), ..pending.transfer };
+	assert_ok!(Creditcoin::persist_task_output(
+		Origin::signed(authority.clone()),
+		pending.deadline,
+		crate::TaskOutput::VerifyTransfer(transfer_id.clone(), transfer),
+		None,
+	));
+	transfer_id
+}
+
+fn persist_reverification(
+	authority: &AccountId,
+	transfer_id: &TransferId<Hash>,
+	outcome: Reverification,
+) -> DispatchResultWithPostInfo {
+	let (deadline, ..) = TaskScheduler::find_pending_task(&transfer_id.reverification_id::<Test>())
+		.expect("re-verification scheduled");
+	Creditcoin::persist_task_output(
+		Origin::signed(authority.clone()),
+		deadline,
+		crate::TaskOutput::ReverifyTransfer(transfer_id.clone(), outcome),
+		None,
+	)
+}
+
+#[test]
+fn verified_transfers_with_evidence_are_reverified() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+
+		let test_info = TestInfo::new_defaults();
+		let authority = AccountId::new([1; 32]);
+		assert_ok!(Creditcoin::add_authority(RawOrigin::Root.into(), authority.clone()));
+		let (deal_order_id, _) = test_info.create_deal_order();
+
+		let transfer_id =
+			verify_funding_transfer_with_evidence(&test_info, &deal_order_id, &authority);
+
+		let (deadline, task, _) =
+			TaskScheduler::find_pending_task(&transfer_id.reverification_id::<Test>())
+				.expect("re-verification scheduled");
+		let window: u64 = <Test as crate::Config>::TransferReverificationWindow::get();
+		let timeout: u64 = <Test as crate::Config>::UnverifiedTaskTimeout::get();
+		assert_eq!(deadline, 1 + window + timeout);
+		let task: crate::TransferReverificationFor<Test> = task.try_into().ok().unwrap();
+		assert_eq!(task.transfer_id, transfer_id);
+		assert_eq!(task.evidence, get_mock_evidence());
+		assert_eq!(task.due, 1 + window);
+
+		let moved = TransferEvidence { block_number: 7, ..get_mock_evidence() };
+		assert_noop!(
+			persist_reverification(&authority, &transfer_id, Reverification::Confirmed(moved)),
+			non_paying_error(crate::Error::<Test>::ReverificationNotDue)
+		);
+
+		System::set_block_number(1 + window);
+		assert_ok!(persist_reverification(
+			&authority,
+			&transfer_id,
+			Reverification::Confirmed(moved)
+		));
+		assert_eq!(Creditcoin::transfers(&transfer_id).unwrap().evidence, Some(moved));
+		assert_eq!(
+			System::events().pop().expect("an event").event,
+			crate::mock::RuntimeEvent::Creditcoin(crate::Event::TransferReverified(
+				transfer_id.clone()
+			))
+		);
+
+		assert_noop!(
+			Creditcoin::persist_task_output(
+				Origin::signed(authority),
+				deadline,
+				crate::TaskOutput::ReverifyTransfer(transfer_id, Reverification::Reorged),
+				None,
+			),
+			non_paying_error(crate::Error::<Test>::ReverificationNotPending)
+		);
+	});
+}
+
+#[test]
+fn reorged_transfers_are_revoked_unless_processed() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+
+		let test_info = TestInfo::new_defaults();
+		let authority = AccountId::new([1; 32]);
+		assert_ok!(Creditcoin::add_authority(RawOrigin::Root.into(), authority.clone()));
+		let (deal_order_id, _) = test_info.create_deal_order();
+
+		let transfer_id =
+			verify_funding_transfer_with_evidence(&test_info, &deal_order_id, &authority);
+		assert_ok!(persist_reverification(&authority, &transfer_id, Reverification::Reorged));
+
+		assert_eq!(Creditcoin::transfers(&transfer_id), None);
+		assert_eq!(Creditcoin::flagged_deal_orders(&deal_order_id), None);
+		assert_eq!(
+			System::events().pop().expect("an event").event,
+			crate::mock::RuntimeEvent::Creditcoin(crate::Event::TransferRevoked(
+				transfer_id.clone()
+			))
+		);
+
+		// verified again and used to fund the deal before the reorg is noticed
+		let transfer_id =
+			verify_funding_transfer_with_evidence(&test_info, &deal_order_id, &authority);
+		Transfers::<Test>::mutate(&transfer_id, |transfer| {
+			transfer.as_mut().unwrap().is_processed = true;
+		});
+		assert_ok!(persist_reverification(&authority, &transfer_id, Reverification::Reorged));
+
+		assert!(Creditcoin::transfers(&transfer_id).is_some());
+		assert_eq!(Creditcoin::flagged_deal_orders(&deal_order_id), Some(transfer_id.clone()));
+		assert_eq!(
+			System::events().pop().expect("an event").event,
+			crate::mock::RuntimeEvent::Creditcoin(crate::Event::DealOrderFlagged(
+				deal_order_id.clone(),
+				transfer_id
+			))
+		);
+
+		assert_noop!(
+			Creditcoin::lock_deal_order(
+				Origin::signed(test_info.borrower.account_id),
+				deal_order_id
+			),
+			crate::Error::<Test>::DealOrderFlagged
+		);
+	});
+}
+
+#[test]
+fn on_initialize_removes_expired_deals_without_transfers() {
+	ExtBuilder::default().build_offchain_and_execute(|| {
+		System::set_block_number(1);
+		let mut expected_deal_orders = Vec::new();
+
+		let now = System::block_number();
+		for expiration_block in now..=20 {
+			let seed1 = format!("{:02}0", expiration_block.clone());
+			let seed2 = format!("{:02}1", expiration_block.clone());
+
+			let test_info = TestInfo {
+				lender: RegisteredAddress::new(&seed1, Blockchain::Rinkeby),
+				borrower: RegisteredAddress::new(&seed2, Blockchain::Rinkeby),
+				blockchain: Blockchain::Rinkeby,
+				loan_terms: LoanTerms {
+					amount: 2_000_000u64.into(),
+					interest_rate: Default::default(),
+					term_length: Duration::from_millis(1_000_000),
+				},
+				ask_guid: format!("{:?}-ask-guid", expiration_block.clone())
+					.as_bytes()
+					.into_bounded(),
+				bid_guid: format!("{:?}-bid-guid", expiration_block.clone())
+					.as_bytes()
+					.into_bounded(),
+				expiration_block,
+			};
+
+			let (offer_id, _) = test_info.create_offer();
+			assert_ok!(Creditcoin::add_deal_order(
+				Origin::signed(test_info.borrower.account_id.clone()),
+				offer_id.clone(),
+				expiration_block,
+			));
+			let deal_order_id = DealOrderId::new::<Test>(expiration_block, &offer_id);
+			let deal_order =
+				Creditcoin::deal_orders(deal_order_id.expiration(), deal_order_id.hash()).unwrap();
+
+			// fund only deal orders which expire at even blocks
+			if expiration_block % 2 == 0 {
+				let tx = format!("0xfafafa{:02}", expiration_block.clone());
+				assert_ok!(Creditcoin::register_funding_transfer(
 					Origin::signed(test_info.lender.account_id.clone()),
 					TransferKind::Native,
 					deal_order_id.clone(),
@@ -2928,6 +3653,32 @@ fn exercise_weightinfo_functions() {
 
 	let result = super::weights::WeightInfo::<Test>::register_address_v2();
 	assert!(result.ref_time() > 0);
+
+	let result = super::weights::WeightInfo::<Test>::register_contract_address();
+	assert!(result.ref_time() > 0);
+
+	let result = super::weights::WeightInfo::<Test>::set_legacy_signing_deadline();
+	assert!(result.ref_time() > 0);
+
+	let result = super::weights::WeightInfo::<Test>::set_attestation_threshold();
+	assert!(result.ref_time() > 0);
+
+	let result = super::weights::WeightInfo::<Test>::bond_authority();
+	assert!(result.ref_time() > 0);
+
+	let result = super::weights::WeightInfo::<Test>::unbond_authority();
+	assert!(result.ref_time() > 0);
+
+	let result = super::weights::WeightInfo::<Test>::slash_authority();
+	assert!(result.ref_time() > 0);
+	let result = super::weights::WeightInfo::<Test>::rotate_authority();
+	assert!(result.ref_time() > 0);
+	let result = super::weights::WeightInfo::<Test>::cancel_transfer();
+	assert!(result.ref_time() > 0);
+	let result = super::weights::WeightInfo::<Test>::request_header_attestation();
+	assert!(result.ref_time() > 0);
+	let result = super::weights::WeightInfo::<Test>::verify_transfer_proof();
+	assert!(result.ref_time() > 0);
 }
 
 #[test]
@@ -3248,3 +3999,563 @@ fn gate_faucet_account_storage_should_return_none_when_not_set() {
 		assert!(gate_faucet.is_none());
 	});
 }
+
+#[test]
+fn register_address_v2_should_work_with_domain_separated_payload() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+
+		let key_pair = generate_keypair_from_seed("owner");
+		let address = external_address_from_keypair(key_pair.clone());
+		let who = account_from_keypair(key_pair.clone());
+		let payload = crate::helpers::signing::domain_separated_payload::<Test>(
+			crate::helpers::SigningDomain::RegisterAddressV2,
+			who.encode().as_slice(),
+		);
+		let proof = OwnershipProof::EthSign(key_pair.sign(&sp_io::hashing::sha2_256(&payload)));
+
+		assert_ok!(Creditcoin::register_address_v2(
+			Origin::signed(who),
+			Blockchain::Rinkeby,
+			address,
+			proof,
+		));
+	});
+}
+
+#[test]
+fn register_address_v2_should_reject_payload_signed_for_another_call() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+
+		let key_pair = generate_keypair_from_seed("owner");
+		let address = external_address_from_keypair(key_pair.clone());
+		let who = account_from_keypair(key_pair.clone());
+		let payload = crate::helpers::signing::domain_separated_payload::<Test>(
+			crate::helpers::SigningDomain::RegisterAddress,
+			who.encode().as_slice(),
+		);
+		let proof = OwnershipProof::EthSign(key_pair.sign(&sp_io::hashing::sha2_256(&payload)));
+
+		assert_noop!(
+			Creditcoin::register_address_v2(
+				Origin::signed(who),
+				Blockchain::Rinkeby,
+				address,
+				proof
+			),
+			crate::Error::<Test>::OwnershipNotSatisfied
+		);
+	});
+}
+
+#[test]
+fn register_address_v2_should_error_with_legacy_payload_after_deadline() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(2);
+		crate::LegacySigningDeadline::<Test>::put(1);
+
+		let (who, address, ownership_proof, _) = generate_address_with_proof("owner");
+		let proof = OwnershipProof::EthSign(ownership_proof);
+
+		assert_noop!(
+			Creditcoin::register_address_v2(
+				Origin::signed(who),
+				Blockchain::Rinkeby,
+				address,
+				proof
+			),
+			crate::Error::<Test>::LegacySignatureExpired
+		);
+	});
+}
+
+#[test]
+fn register_deal_order_should_work_with_domain_separated_payload() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(2);
+		crate::LegacySigningDeadline::<Test>::put(1);
+
+		let (_, _, ownership_proof, key_pair) = generate_address_with_proof("borrower2");
+		let pub_key = key_pair.public();
+
+		let test_info = TestInfo {
+			borrower: RegisteredAddress::from_pubkey(pub_key, Blockchain::Rinkeby, ownership_proof),
+			..TestInfo::new_defaults()
+		};
+
+		let message = crate::helpers::signing::domain_separated_payload::<Test>(
+			crate::helpers::SigningDomain::RegisterDealOrder,
+			&test_info.get_register_deal_msg(),
+		);
+		let compliance_proof = key_pair.sign(&message);
+
+		assert_ok!(Creditcoin::register_deal_order(
+			Origin::signed(test_info.lender.account_id),
+			test_info.lender.address_id,
+			test_info.borrower.address_id,
+			test_info.loan_terms,
+			test_info.expiration_block,
+			test_info.ask_guid,
+			test_info.bid_guid,
+			pub_key.into(),
+			compliance_proof.into(),
+		));
+	});
+}
+
+#[test]
+fn set_legacy_signing_deadline_errors_for_non_root() {
+	ExtBuilder::default().build_and_execute(|| {
+		let acct: AccountId = AccountId::new([0; 32]);
+
+		assert_noop!(
+			Creditcoin::set_legacy_signing_deadline(Origin::signed(acct), Some(1)),
+			BadOrigin
+		);
+	});
+}
+
+#[test]
+fn set_legacy_signing_deadline_works_for_root() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+
+		assert_ok!(Creditcoin::set_legacy_signing_deadline(RawOrigin::Root.into(), Some(10)));
+		assert_eq!(crate::LegacySigningDeadline::<Test>::get(), Some(10));
+
+		let event = <frame_system::Pallet<Test>>::events().pop().expect("an event").event;
+		assert_eq!(
+			event,
+			crate::mock::RuntimeEvent::Creditcoin(crate::Event::LegacySigningDeadlineSet(Some(10)))
+		);
+	});
+}
+
+#[test]
+fn register_address_v2_with_erc1271_proof_schedules_verification() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+
+		let who = AccountId::new([3; 32]);
+		let address = "0x09231da7b19A016f9e576d23B16277062F4d46A8".hex_to_address();
+		let blockchain = Blockchain::Rinkeby;
+		let proof = OwnershipProof::Erc1271(vec![7; 65].try_into().unwrap());
+
+		assert_ok!(Creditcoin::register_address_v2(
+			Origin::signed(who.clone()),
+			blockchain.clone(),
+			address.clone(),
+			proof.clone(),
+		));
+
+		let address_id = AddressId::new::<Test>(&blockchain, &address);
+		assert_eq!(Creditcoin::addresses(&address_id), None);
+
+		let deadline = Test::unverified_transfer_deadline();
+		assert!(TaskScheduler::is_scheduled(&deadline, &address_id.clone().into_inner()));
+
+		let event = System::events().pop().expect("an event").event;
+		assert_matches!(
+			event,
+			crate::mock::RuntimeEvent::Creditcoin(crate::Event::<Test>::AddressRegistrationPending(id, pending)) => {
+				assert_eq!(id, address_id);
+				assert_eq!(pending.owner, who);
+			}
+		);
+
+		assert_noop!(
+			Creditcoin::register_address_v2(Origin::signed(who), blockchain, address, proof),
+			crate::Error::<Test>::AddressRegistrationAlreadyPending
+		);
+	});
+}
+
+#[test]
+fn persist_contract_ownership_registers_the_address() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+
+		let authority = AccountId::new([1; 32]);
+		assert_ok!(Creditcoin::add_authority(RawOrigin::Root.into(), authority.clone()));
+
+		let blockchain = Blockchain::Rinkeby;
+		let value = "0x09231da7b19A016f9e576d23B16277062F4d46A8".hex_to_address();
+		let address_id = AddressId::new::<Test>(&blockchain, &value);
+		let address = crate::Address { blockchain, value, owner: AccountId::new([3; 32]) };
+		let deadline = Test::unverified_transfer_deadline();
+
+		assert_ok!(Creditcoin::persist_task_output(
+			Origin::signed(authority.clone()),
+			deadline,
+			crate::TaskOutput::VerifyContractOwnership(address_id.clone(), address.clone()),
+			None,
+		));
+
+		assert_eq!(Creditcoin::addresses(&address_id), Some(address.clone()));
+		let event = System::events().pop().expect("an event").event;
+		assert_eq!(
+			event,
+			crate::mock::RuntimeEvent::Creditcoin(crate::Event::AddressRegistered(
+				address_id.clone(),
+				address.clone()
+			))
+		);
+
+		assert_noop!(
+			Creditcoin::persist_task_output(
+				Origin::signed(authority),
+				deadline,
+				crate::TaskOutput::VerifyContractOwnership(address_id, address),
+				None,
+			),
+			non_paying_error(crate::Error::<Test>::AddressAlreadyRegistered)
+		);
+	});
+}
+
+#[test]
+fn fail_contract_ownership_should_work() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+
+		let authority = AccountId::new([1; 32]);
+		assert_ok!(Creditcoin::add_authority(RawOrigin::Root.into(), authority.clone()));
+
+		let value = "0x09231da7b19A016f9e576d23B16277062F4d46A8".hex_to_address();
+		let address_id = AddressId::new::<Test>(&Blockchain::Rinkeby, &value);
+		let failure_cause = crate::ocw::errors::VerificationFailureCause::InvalidContractSignature;
+		assert_ok!(Creditcoin::register_address_v2(
+			Origin::signed(AccountId::new([3; 32])),
+			Blockchain::Rinkeby,
+			value,
+			OwnershipProof::Erc1271(vec![7; 65].try_into().unwrap()),
+		));
+
+		assert_ok!(Creditcoin::fail_task(
+			Origin::signed(authority),
+			Test::unverified_transfer_deadline(),
+			address_id.clone().into(),
+			failure_cause,
+			None
+		));
+
+		assert_matches!(
+			System::events().pop().unwrap().event,
+			crate::mock::RuntimeEvent::Creditcoin(crate::Event::<Test>::AddressFailedVerification(id, cause)) => {
+				assert_eq!(id, address_id);
+				assert_eq!(cause, failure_cause);
+			}
+		);
+	});
+}
+
+#[test]
+fn request_header_attestation_schedules_once() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+		let who = AccountId::new([7; 32]);
+
+		assert_ok!(Creditcoin::request_header_attestation(
+			Origin::signed(who.clone()),
+			Blockchain::Ethereum,
+			42
+		));
+		let header_id = crate::HeaderId::new::<Test>(&Blockchain::Ethereum, 42);
+		let (_, task, _) = TaskScheduler::find_pending_task(&header_id.clone().into_inner())
+			.expect("attestation scheduled");
+		let task: crate::HeaderAttestation = task.try_into().ok().unwrap();
+		assert_eq!(task, crate::HeaderAttestation { blockchain: Blockchain::Ethereum, number: 42 });
+		System::assert_last_event(
+			crate::Event::<Test>::HeaderAttestationRequested(header_id, task).into(),
+		);
+
+		assert_noop!(
+			Creditcoin::request_header_attestation(
+				Origin::signed(who.clone()),
+				Blockchain::Ethereum,
+				42
+			),
+			crate::Error::<Test>::HeaderAttestationAlreadyPending
+		);
+		assert_noop!(
+			Creditcoin::request_header_attestation(Origin::signed(who), Blockchain::Bitcoin, 42),
+			crate::Error::<Test>::UnsupportedBlockchain
+		);
+	});
+}
+
+#[test]
+fn persist_header_attestation_stores_the_hash() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+		let authority = AccountId::new([1; 32]);
+		assert_ok!(Creditcoin::add_authority(RawOrigin::Root.into(), authority.clone()));
+		assert_ok!(Creditcoin::request_header_attestation(
+			Origin::signed(authority.clone()),
+			Blockchain::Ethereum,
+			42
+		));
+		let header_id = crate::HeaderId::new::<Test>(&Blockchain::Ethereum, 42);
+		let (deadline, ..) =
+			TaskScheduler::find_pending_task(&header_id.clone().into_inner()).unwrap();
+		let hash = H256::repeat_byte(9);
+
+		assert_ok!(Creditcoin::persist_task_output(
+			Origin::signed(authority.clone()),
+			deadline,
+			crate::TaskOutput::AttestHeader(header_id.clone(), hash),
+			None,
+		));
+		assert_eq!(Creditcoin::attested_headers(&header_id), Some(hash));
+		System::assert_last_event(crate::Event::<Test>::HeaderAttested(header_id, hash).into());
+
+		assert_noop!(
+			Creditcoin::request_header_attestation(
+				Origin::signed(authority),
+				Blockchain::Ethereum,
+				42
+			),
+			crate::Error::<Test>::HeaderAlreadyAttested
+		);
+	});
+}
+
+#[test]
+fn attested_headers_are_bounded_per_block_and_pruned_after_their_ttl() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+		let authority = AccountId::new([1; 32]);
+		assert_ok!(Creditcoin::add_authority(RawOrigin::Root.into(), authority.clone()));
+		let attest = |number| {
+			assert_ok!(Creditcoin::request_header_attestation(
+				Origin::signed(authority.clone()),
+				Blockchain::Ethereum,
+				number
+			));
+			let header_id = crate::HeaderId::new::<Test>(&Blockchain::Ethereum, number);
+			let (deadline, ..) =
+				TaskScheduler::find_pending_task(&header_id.clone().into_inner()).unwrap();
+			let persisted = Creditcoin::persist_task_output(
+				Origin::signed(authority.clone()),
+				deadline,
+				crate::TaskOutput::AttestHeader(header_id.clone(), H256::repeat_byte(9)),
+				None,
+			);
+			(header_id, persisted)
+		};
+
+		// the mock attests 2 headers per block and keeps them for 10 blocks
+		let (first, persisted) = attest(1);
+		assert_ok!(persisted);
+		let (second, persisted) = attest(2);
+		assert_ok!(persisted);
+		let (third, persisted) = attest(3);
+		assert_eq!(persisted, Err(non_paying_error(crate::Error::<Test>::TooManyAttestedHeaders)));
+		assert_eq!(Creditcoin::attested_headers(&third), None);
+
+		Creditcoin::on_initialize(11);
+		assert_eq!(Creditcoin::attested_headers(&first), None);
+		assert_eq!(Creditcoin::attested_headers(&second), None);
+		assert!(crate::AttestedHeadersAt::<Test>::get(1).is_empty());
+	});
+}
+
+/// Registers an ethless funding transfer of the deal order and builds a proof for its
+/// transaction, sent to the contract with `input`.
+fn register_proven_funding_transfer(
+	test_info: &TestInfo,
+	deal_order_id: &TestDealOrderId,
+	input: impl FnOnce(ethereum_types::H160, ethereum_types::H160, U256) -> Vec<u8>,
+) -> (TransferId<Hash>, crate::TransferProof, H256) {
+	register_proven_funding_transaction(test_info, deal_order_id, |contract, from, to, nonce| {
+		crate::helpers::eth_proof::test_utils::transfer_proof(
+			100,
+			1_700_000_000,
+			contract,
+			&input(from, to, nonce),
+		)
+	})
+}
+
+/// Like [`register_proven_funding_transfer`], with `prove` building the proof from the
+/// contract, the sender, the receiver and the nonce.
+fn register_proven_funding_transaction(
+	test_info: &TestInfo,
+	deal_order_id: &TestDealOrderId,
+	prove: impl FnOnce(
+		ethereum_types::H160,
+		ethereum_types::H160,
+		ethereum_types::H160,
+		U256,
+	) -> (crate::TransferProof, H256, H256),
+) -> (TransferId<Hash>, crate::TransferProof, H256) {
+	let external = |id| {
+		let address = Creditcoin::addresses(id).unwrap().value;
+		ethereum_types::H160::from_slice(&address)
+	};
+	let from = external(&test_info.lender.address_id);
+	let to = external(&test_info.borrower.address_id);
+	let contract = ethereum_types::H160::repeat_byte(0xaa);
+	let (proof, header_hash, tx_hash) = prove(contract, from, to, deal_order_id.hash().into_uint());
+	assert_ok!(Creditcoin::register_funding_transfer(
+		Origin::signed(test_info.lender.account_id.clone()),
+		TransferKind::Ethless(contract.as_bytes().into_bounded()),
+		deal_order_id.clone(),
+		tx_hash.as_bytes().into_bounded()
+	));
+	let transfer_id = TransferId::new::<Test>(&test_info.blockchain, tx_hash.as_bytes());
+	(transfer_id, proof, header_hash)
+}
+
+#[test]
+fn verify_transfer_proof_verifies_against_an_attested_header() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+		let test_info = TestInfo::new_defaults();
+		let (deal_order_id, _) = test_info.create_deal_order();
+		let amount = test_info.loan_terms.amount;
+		let (transfer_id, proof, header_hash) =
+			register_proven_funding_transfer(&test_info, &deal_order_id, |from, to, nonce| {
+				crate::helpers::eth_proof::test_utils::ethless_input(from, to, amount, nonce)
+			});
+		let who = Origin::signed(test_info.borrower.account_id.clone());
+
+		assert_noop!(
+			Creditcoin::verify_transfer_proof(who.clone(), transfer_id.clone(), proof.clone()),
+			crate::Error::<Test>::HeaderNotAttested
+		);
+
+		let header_id = crate::HeaderId::new::<Test>(&test_info.blockchain, 100);
+		crate::AttestedHeaders::<Test>::insert(&header_id, header_hash);
+		// an earlier registration of the transaction was cancelled
+		crate::CancelledTransfers::<Test>::insert(&transfer_id, 1);
+		assert_ok!(Creditcoin::verify_transfer_proof(who, transfer_id.clone(), proof));
+
+		let transfer = Creditcoin::transfers(&transfer_id).expect("transfer verified");
+		assert_eq!(transfer.timestamp, Some(1_700_000_000));
+		let evidence = transfer.evidence.expect("evidence recorded");
+		assert_eq!(evidence.block_number, 100);
+		assert_eq!(evidence.block_hash, header_hash);
+		assert_eq!(evidence.transaction_index, 0);
+		assert_eq!(evidence.log_index, None);
+		assert!(TaskScheduler::find_pending_task(&transfer_id.clone().into_inner()).is_none());
+		assert!(
+			TaskScheduler::find_pending_task(&transfer_id.reverification_id::<Test>()).is_some()
+		);
+		assert!(!crate::CancelledTransfers::<Test>::contains_key(&transfer_id));
+		System::assert_last_event(crate::Event::<Test>::TransferVerified(transfer_id).into());
+	});
+}
+
+/// Proves an ethless transfer sent through a proxy, whose receipt has the contract's transfer
+/// log when `logged`.
+fn prove_proxied_transfer(
+	amount: U256,
+	logged: bool,
+) -> impl FnOnce(
+	ethereum_types::H160,
+	ethereum_types::H160,
+	ethereum_types::H160,
+	U256,
+) -> (crate::TransferProof, H256, H256) {
+	use crate::helpers::eth_proof::test_utils::{ethless_input, transaction_proof, transfer_log};
+	use crate::ocw::tasks::verify_transfer::ethless_transfer_function_abi;
+
+	move |contract, from, to, nonce| {
+		let proxy = ethereum_types::H160::repeat_byte(0xbb);
+		let calldata: Vec<u8> = [0xde, 0xad, 0xbe, 0xef]
+			.into_iter()
+			.chain(ethless_transfer_function_abi().short_signature())
+			.chain(ethless_input(from, to, amount, nonce))
+			.collect();
+		let logs = if logged { vec![transfer_log(contract, from, to, amount)] } else { vec![] };
+		transaction_proof(100, 1_700_000_000, proxy, &calldata, &logs)
+	}
+}
+
+#[test]
+fn verify_transfer_proof_follows_the_transfer_log_through_a_proxy() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+		let test_info = TestInfo::new_defaults();
+		let (deal_order_id, _) = test_info.create_deal_order();
+		let amount = test_info.loan_terms.amount;
+		let (transfer_id, proof, header_hash) = register_proven_funding_transaction(
+			&test_info,
+			&deal_order_id,
+			prove_proxied_transfer(amount, true),
+		);
+		let header_id = crate::HeaderId::new::<Test>(&test_info.blockchain, 100);
+		crate::AttestedHeaders::<Test>::insert(&header_id, header_hash);
+
+		assert_ok!(Creditcoin::verify_transfer_proof(
+			Origin::signed(test_info.lender.account_id.clone()),
+			transfer_id.clone(),
+			proof
+		));
+		let transfer = Creditcoin::transfers(&transfer_id).expect("transfer verified");
+		assert_eq!(transfer.evidence.expect("evidence recorded").log_index, Some(0));
+	});
+}
+
+#[test]
+fn verify_transfer_proof_rejects_a_proxied_call_without_the_transfer_log() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+		let test_info = TestInfo::new_defaults();
+		let (deal_order_id, _) = test_info.create_deal_order();
+		let amount = test_info.loan_terms.amount;
+		let (transfer_id, proof, header_hash) = register_proven_funding_transaction(
+			&test_info,
+			&deal_order_id,
+			prove_proxied_transfer(amount, false),
+		);
+		let header_id = crate::HeaderId::new::<Test>(&test_info.blockchain, 100);
+		crate::AttestedHeaders::<Test>::insert(&header_id, header_hash);
+
+		assert_noop!(
+			Creditcoin::verify_transfer_proof(
+				Origin::signed(test_info.lender.account_id.clone()),
+				transfer_id,
+				proof
+			),
+			crate::Error::<Test>::TransferProofMismatch
+		);
+	});
+}
+
+#[test]
+fn verify_transfer_proof_rejects_a_mismatched_transaction() {
+	ExtBuilder::default().build_and_execute(|| {
+		System::set_block_number(1);
+		let test_info = TestInfo::new_defaults();
+		let (deal_order_id, _) = test_info.create_deal_order();
+		let amount = test_info.loan_terms.amount + 1;
+		let (transfer_id, proof, header_hash) =
+			register_proven_funding_transfer(&test_info, &deal_order_id, |from, to, nonce| {
+				crate::helpers::eth_proof::test_utils::ethless_input(from, to, amount, nonce)
+			});
+		let header_id = crate::HeaderId::new::<Test>(&test_info.blockchain, 100);
+		crate::AttestedHeaders::<Test>::insert(&header_id, header_hash);
+
+		let mut tampered = proof.clone();
+		tampered.transaction_index = 1;
+		assert_noop!(
+			Creditcoin::verify_transfer_proof(
+				Origin::signed(test_info.lender.account_id.clone()),
+				transfer_id.clone(),
+				tampered
+			),
+			crate::Error::<Test>::InvalidTransferProof
+		);
+		assert_noop!(
+			Creditcoin::verify_transfer_proof(
+				Origin::signed(test_info.lender.account_id.clone()),
+				transfer_id,
+				proof
+			),
+			crate::Error::<Test>::TransferProofMismatch
+		);
+	});
+}
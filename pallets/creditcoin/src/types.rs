@@ -1,11 +1,17 @@
 mod cleanup;
 pub mod collect_coins;
+mod contract_ownership;
+mod header_attestation;
 pub mod loan_terms;
+mod reverification;
 mod transfer;
 
 pub use cleanup::{StorageCleanupState, StorageItemCleanupState};
 
+pub use contract_ownership::*;
+pub use header_attestation::*;
 pub use loan_terms::*;
+pub use reverification::*;
 pub use transfer::*;
 
 use crate::ocw::VerificationFailureCause;
@@ -39,6 +45,8 @@ type OtherChainLen = ConstU32<256>;
 pub type OtherChain = BoundedVec<u8, OtherChainLen>;
 type OtherTransferKindLen = ConstU32<256>;
 pub type OtherTransferKind = BoundedVec<u8, OtherTransferKindLen>;
+type ContractSignatureLen = ConstU32<1024>;
+pub type ContractSignature = BoundedVec<u8, ContractSignatureLen>;
 
 #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
 pub enum Blockchain {
@@ -69,12 +77,26 @@ impl Blockchain {
 			Blockchain::Other(chain) => chain.as_slice(),
 		}
 	}
+
+	/// The built-in EIP-155 chain ID the RPC endpoint of this chain has to report, or `None`
+	/// when there is nothing to check it against. Luniverse side chains each have their own
+	/// chain ID, so theirs has to be configured, see `Blockchain::rpc_chain_id`.
+	pub fn expected_chain_id(&self) -> Option<u64> {
+		match self {
+			Blockchain::Ethereum => Some(1),
+			Blockchain::Rinkeby => Some(4),
+			Blockchain::Luniverse | Blockchain::Bitcoin | Blockchain::Other(_) => None,
+		}
+	}
 }
 
 #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
 pub enum OwnershipProof {
 	PersonalSign(sp_core::ecdsa::Signature),
 	EthSign(sp_core::ecdsa::Signature),
+	/// Signature checked by the address itself through ERC-1271 `isValidSignature`.
+	/// Verified offchain by the authorities.
+	Erc1271(ContractSignature),
 }
 
 #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
@@ -227,6 +249,16 @@ impl<H> AddressId<H> {
 		let key = concatenate!(blockchain.as_bytes(), address);
 		AddressId(Config::Hashing::hash(&key))
 	}
+
+	pub fn into_inner(self) -> H {
+		self.0
+	}
+}
+
+impl<H> From<H> for AddressId<H> {
+	fn from(hash: H) -> Self {
+		Self(hash)
+	}
 }
 
 impl<B, H> AskOrderId<B, H> {
@@ -476,15 +508,9 @@ impl<'de> serde::Deserialize<'de> for LegacySighash {
 	}
 }
 
-#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
-pub enum Task<AccountId, BlockNum, Hash, Moment> {
-	VerifyTransfer(UnverifiedTransfer<AccountId, BlockNum, Hash, Moment>),
-}
-
 #[cfg(feature = "runtime-benchmarks")]
 impl<T: SystemConfig + TimestampConfig + crate::Config>
-	pallet_offchain_task_scheduler::benchmarking::TaskDefault<T>
-	for Task<T::AccountId, T::BlockNumber, T::Hash, T::Moment>
+	pallet_offchain_task_scheduler::benchmarking::TaskDefault<T> for UnverifiedTransferFor<T>
 {
 	fn generate_from_seed(seed: u32) -> Self {
 		use crate::benchmarking::generate_fake_unverified_transfer;
@@ -492,23 +518,14 @@ impl<T: SystemConfig + TimestampConfig + crate::Config>
 		use sp_runtime::traits::One;
 		let who = account("{seed}", 0, 0);
 		whitelist!(who);
-		let pending_transfer =
-			generate_fake_unverified_transfer::<T>(&who, T::BlockNumber::one(), seed);
-		pending_transfer.into()
-	}
-}
-
-impl<AccountId, BlockNum, Hash, Moment> From<UnverifiedTransfer<AccountId, BlockNum, Hash, Moment>>
-	for Task<AccountId, BlockNum, Hash, Moment>
-{
-	fn from(transfer: UnverifiedTransfer<AccountId, BlockNum, Hash, Moment>) -> Self {
-		Task::VerifyTransfer(transfer)
+		generate_fake_unverified_transfer::<T>(&who, T::BlockNumber::one(), seed)
 	}
 }
 
 #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
 pub enum TaskId<Hash> {
 	VerifyTransfer(TransferId<Hash>),
+	VerifyContractOwnership(AddressId<Hash>),
 }
 
 impl<Hash> From<TransferId<Hash>> for TaskId<Hash> {
@@ -517,9 +534,27 @@ impl<Hash> From<TransferId<Hash>> for TaskId<Hash> {
 	}
 }
 
+impl<Hash> From<AddressId<Hash>> for TaskId<Hash> {
+	fn from(id: AddressId<Hash>) -> Self {
+		TaskId::VerifyContractOwnership(id)
+	}
+}
+
+impl<Hash> TaskId<Hash> {
+	pub fn into_inner(self) -> Hash {
+		match self {
+			TaskId::VerifyTransfer(id) => id.into_inner(),
+			TaskId::VerifyContractOwnership(id) => id.into_inner(),
+		}
+	}
+}
+
 #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
 pub enum TaskOutput<AccountId, BlockNum, Hash, Moment> {
 	VerifyTransfer(TransferId<Hash>, Transfer<AccountId, BlockNum, Hash, Moment>),
+	VerifyContractOwnership(AddressId<Hash>, Address<AccountId>),
+	ReverifyTransfer(TransferId<Hash>, Reverification),
+	AttestHeader(HeaderId<Hash>, sp_core::H256),
 }
 
 impl<AccountId, BlockNum, Hash, Moment>
@@ -533,6 +568,20 @@ impl<AccountId, BlockNum, Hash, Moment>
 	}
 }
 
+impl<AccountId, BlockNum, Hash: Clone + Encode, Moment>
+	TaskOutput<AccountId, BlockNum, Hash, Moment>
+{
+	/// The id of the task this is the output of.
+	pub fn task_id<T: crate::Config<Hash = Hash>>(&self) -> Hash {
+		match self {
+			TaskOutput::VerifyTransfer(id, _) => id.clone().into_inner(),
+			TaskOutput::VerifyContractOwnership(id, _) => id.clone().into_inner(),
+			TaskOutput::ReverifyTransfer(id, _) => id.reverification_id::<T>(),
+			TaskOutput::AttestHeader(id, _) => id.clone().into_inner(),
+		}
+	}
+}
+
 #[cfg(test)]
 pub(crate) mod test {
 	use crate::{loan_terms::InvalidTermLengthError, mock, tests::TestInfo, *};
@@ -654,6 +703,15 @@ pub(crate) mod test {
 		}
 	}
 
+	fn create_unverified_contract_ownership() -> UnverifiedContractOwnership<AccountId, BlockNum> {
+		UnverifiedContractOwnership {
+			address: create_address(),
+			message_hash: [1; 32],
+			signature: ContractSignature::try_from(vec![2; 65]).unwrap(),
+			deadline: 1_000_000,
+		}
+	}
+
 	fn create_address() -> Address<AccountId> {
 		Address {
 			blockchain: Blockchain::Rinkeby,
@@ -671,6 +729,7 @@ pub(crate) mod test {
 	address: Address<AccountId> : create_address(),
 	transfer: Transfer<AccountId, BlockNum, Hash, Moment> : create_funding_transfer().1,
 	unverified_transfer: UnverifiedTransfer<AccountId, BlockNum, Hash, Moment> : create_unverified_transfer(),
+	unverified_contract_ownership: UnverifiedContractOwnership<AccountId, BlockNum> : create_unverified_contract_ownership(),
 	offer: Offer<AccountId, BlockNum, Hash> : TestInfo::new_defaults().create_offer().1,
 	ask_order: AskOrder<AccountId, BlockNum, Hash> : TestInfo::new_defaults().create_ask_order().1,
 	bid_order: BidOrder<AccountId, BlockNum, Hash> : TestInfo::new_defaults().create_bid_order().1,
@@ -688,6 +747,8 @@ pub(crate) mod test {
 	task_output: TaskOutput<AccountId, BlockNum, Hash, Moment> : TaskOutput::<AccountId, BlockNum, Hash, Moment>::from(
 		create_funding_transfer()
 	),
+	reverification: Reverification : Reverification::Reorged,
+	header_attestation: HeaderAttestation : HeaderAttestation { blockchain: Blockchain::Ethereum, number: 42 },
 
 	// from types/loan_terms.rs
 	duration: Duration : Duration::from_millis(100),
@@ -0,0 +1,76 @@
+use super::*;
+use crate::ocw::errors::SchedulerError;
+use crate::ocw::tasks::OffchainVerification;
+use crate::Config;
+use crate::TaskOutput;
+use pallet_offchain_task_scheduler::tasks::error::TaskError;
+use pallet_offchain_task_scheduler::tasks::TaskV2;
+
+/// An address registration backed by an ERC-1271 signature. The address is only registered
+/// once an authority has confirmed that the contract at `address.value` accepts `signature`
+/// for `message_hash`.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct UnverifiedContractOwnership<AccountId, BlockNum> {
+	pub address: Address<AccountId>,
+	pub message_hash: [u8; 32],
+	pub signature: ContractSignature,
+	pub deadline: BlockNum,
+}
+
+pub type UnverifiedContractOwnershipFor<T> =
+	UnverifiedContractOwnership<<T as SystemConfig>::AccountId, <T as SystemConfig>::BlockNumber>;
+
+impl<T: Config> TaskV2<T> for UnverifiedContractOwnershipFor<T>
+where
+	UnverifiedContractOwnershipFor<T>: OffchainVerification<T>,
+{
+	type Call = crate::pallet::Call<T>;
+	type EvaluationError = VerificationFailureCause;
+	type SchedulerError = SchedulerError;
+
+	fn to_id(&self) -> T::Hash {
+		let Address { blockchain, value, .. } = &self.address;
+		AddressId::new::<T>(blockchain, value).into_inner()
+	}
+
+	fn persistence_call(
+		&self,
+		deadline: T::BlockNumber,
+		id: &T::Hash,
+	) -> Result<Self::Call, TaskError<Self::EvaluationError, Self::SchedulerError>> {
+		use crate::ocw::OffchainError::*;
+		match self.verify() {
+			Ok(_) => {
+				let id = AddressId::from(*id);
+				Ok(Self::Call::persist_task_output {
+					deadline,
+					task_output: TaskOutput::VerifyContractOwnership(id, self.address.clone()),
+					assignment: None,
+				})
+			},
+			Err(InvalidTask(cause)) if cause.is_fatal() => {
+				log::warn!("Failed to verify pending task {:?} : {:?}", self, cause);
+				let id = AddressId::from(*id);
+				Ok(Self::Call::fail_task { deadline, task_id: id.into(), cause, assignment: None })
+			},
+			Err(InvalidTask(e)) => Err(TaskError::Evaluation(e)),
+			Err(NoRpcUrl(e)) => Err(TaskError::Scheduler(e.into())),
+			Err(RpcError(e)) => Err(TaskError::Scheduler(e.into())),
+			Err(IncorrectChainId) => Err(TaskError::Scheduler(SchedulerError::IncorrectChainId)),
+			Err(QuorumNotReached) => Err(TaskError::Scheduler(SchedulerError::QuorumNotReached)),
+		}
+	}
+
+	fn is_persisted(id: &T::Hash) -> bool {
+		let id = AddressId::from(*id);
+		crate::pallet::Addresses::<T>::contains_key(id)
+	}
+}
+
+impl<T: Config> OffchainVerification<T> for UnverifiedContractOwnershipFor<T> {
+	type Output = ();
+
+	fn verify(&self) -> VerificationResult<Self::Output> {
+		crate::Pallet::<T>::verify_contract_ownership_ocw(self)
+	}
+}
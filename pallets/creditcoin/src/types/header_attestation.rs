@@ -0,0 +1,103 @@
+use super::*;
+use crate::ocw::errors::SchedulerError;
+use crate::ocw::tasks::OffchainVerification;
+use crate::types::concatenate;
+use crate::Config;
+use crate::TaskOutput;
+use pallet_offchain_task_scheduler::tasks::error::TaskError;
+use pallet_offchain_task_scheduler::tasks::TaskV2;
+use sp_core::H256;
+use sp_runtime::traits::Hash;
+
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct HeaderId<Hash>(Hash);
+
+impl<H> HeaderId<H> {
+	pub fn new<C: SystemConfig>(blockchain: &Blockchain, number: u64) -> HeaderId<H>
+	where
+		<C as SystemConfig>::Hashing: Hash<Output = H>,
+	{
+		let key = concatenate!(blockchain.as_bytes(), &number.to_be_bytes());
+		HeaderId(<C::Hashing as Hash>::hash(&key))
+	}
+
+	pub fn into_inner(self) -> H {
+		self.0
+	}
+}
+
+impl<H> From<H> for HeaderId<H> {
+	fn from(hash: H) -> Self {
+		Self(hash)
+	}
+}
+
+/// Authorities looking up the hash of the block at `number` once it is deep enough in the
+/// chain, so that proofs against its header can be checked onchain.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct HeaderAttestation {
+	pub blockchain: Blockchain,
+	pub number: u64,
+}
+
+pub type MaxHeaderLen = ConstU32<1024>;
+pub type MaxProofNodes = ConstU32<16>;
+pub type MaxProofNodeLen = ConstU32<8192>;
+pub type ProofNode = BoundedVec<u8, MaxProofNodeLen>;
+
+/// Proves that a transaction and its receipt are in a block whose header was attested, by the
+/// trie nodes from the header's transaction and receipt roots down to the entries at
+/// `transaction_index`.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct TransferProof {
+	/// The RLP encoded header.
+	pub header: BoundedVec<u8, MaxHeaderLen>,
+	pub transaction_index: u64,
+	pub transaction: BoundedVec<ProofNode, MaxProofNodes>,
+	pub receipt: BoundedVec<ProofNode, MaxProofNodes>,
+}
+
+impl<T: Config> TaskV2<T> for HeaderAttestation
+where
+	HeaderAttestation: OffchainVerification<T, Output = H256>,
+{
+	type Call = crate::pallet::Call<T>;
+	type EvaluationError = VerificationFailureCause;
+	type SchedulerError = SchedulerError;
+
+	fn to_id(&self) -> T::Hash {
+		HeaderId::new::<T>(&self.blockchain, self.number).into_inner()
+	}
+
+	fn persistence_call(
+		&self,
+		deadline: T::BlockNumber,
+		id: &T::Hash,
+	) -> Result<Self::Call, TaskError<Self::EvaluationError, Self::SchedulerError>> {
+		use crate::ocw::OffchainError::*;
+		match self.verify() {
+			Ok(hash) => Ok(Self::Call::persist_task_output {
+				deadline,
+				task_output: TaskOutput::AttestHeader(HeaderId::from(*id), hash),
+				assignment: None,
+			}),
+			Err(InvalidTask(e)) => Err(TaskError::Evaluation(e)),
+			Err(NoRpcUrl(e)) => Err(TaskError::Scheduler(e.into())),
+			Err(RpcError(e)) => Err(TaskError::Scheduler(e.into())),
+			Err(IncorrectChainId) => Err(TaskError::Scheduler(SchedulerError::IncorrectChainId)),
+			Err(QuorumNotReached) => Err(TaskError::Scheduler(SchedulerError::QuorumNotReached)),
+		}
+	}
+
+	fn is_persisted(id: &T::Hash) -> bool {
+		crate::pallet::AttestedHeaders::<T>::contains_key(HeaderId::from(*id))
+	}
+}
+
+impl<T: Config> OffchainVerification<T> for HeaderAttestation {
+	type Output = H256;
+
+	fn verify(&self) -> VerificationResult<Self::Output> {
+		crate::Pallet::<T>::attest_header_ocw(self)
+	}
+}
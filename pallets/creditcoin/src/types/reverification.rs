@@ -0,0 +1,91 @@
+use super::*;
+use crate::ocw::errors::SchedulerError;
+use crate::ocw::tasks::OffchainVerification;
+use crate::Config;
+use crate::TaskOutput;
+use pallet_offchain_task_scheduler::tasks::error::TaskError;
+use pallet_offchain_task_scheduler::tasks::TaskV2;
+use sp_runtime::traits::Hash;
+
+/// A verified transfer checked again while its chain may still reorg. Authorities check it
+/// every block and revoke it as soon as its transaction is no longer on the chain. Finding it
+/// still there only confirms it from `due` on, earlier checks are retried.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct TransferReverification<BlockNum, Hash> {
+	pub transfer_id: TransferId<Hash>,
+	pub blockchain: Blockchain,
+	pub tx_id: ExternalTxId,
+	pub evidence: TransferEvidence,
+	pub due: BlockNum,
+}
+
+pub type TransferReverificationFor<T> =
+	TransferReverification<<T as SystemConfig>::BlockNumber, <T as SystemConfig>::Hash>;
+
+/// What the authorities found checking a verified transfer again.
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum Reverification {
+	/// The transaction is still on the chain, possibly mined again in another block.
+	Confirmed(TransferEvidence),
+	/// The transaction is no longer on the chain.
+	Reorged,
+}
+
+impl<H: Encode> TransferId<H> {
+	/// The id of the transfer's re-verification task, which can't be the id of its verification.
+	pub fn reverification_id<T: Config<Hash = H>>(&self) -> H {
+		T::Hashing::hash_of(&(b"reverify", self))
+	}
+}
+
+impl<T: Config> TaskV2<T> for TransferReverificationFor<T>
+where
+	TransferReverificationFor<T>: OffchainVerification<T, Output = Reverification>,
+{
+	type Call = crate::pallet::Call<T>;
+	type EvaluationError = VerificationFailureCause;
+	type SchedulerError = SchedulerError;
+
+	fn to_id(&self) -> T::Hash {
+		self.transfer_id.reverification_id::<T>()
+	}
+
+	fn persistence_call(
+		&self,
+		deadline: T::BlockNumber,
+		_id: &T::Hash,
+	) -> Result<Self::Call, TaskError<Self::EvaluationError, Self::SchedulerError>> {
+		use crate::ocw::OffchainError::*;
+		match self.verify() {
+			// a reorg can still happen, `persist_task_output` rejects early confirmations
+			Ok(Reverification::Confirmed(_))
+				if frame_system::Pallet::<T>::block_number() < self.due =>
+			{
+				Err(TaskError::Evaluation(VerificationFailureCause::TaskUnconfirmed))
+			},
+			Ok(outcome) => Ok(Self::Call::persist_task_output {
+				deadline,
+				task_output: TaskOutput::ReverifyTransfer(self.transfer_id.clone(), outcome),
+				assignment: None,
+			}),
+			Err(InvalidTask(e)) => Err(TaskError::Evaluation(e)),
+			Err(NoRpcUrl(e)) => Err(TaskError::Scheduler(e.into())),
+			Err(RpcError(e)) => Err(TaskError::Scheduler(e.into())),
+			Err(IncorrectChainId) => Err(TaskError::Scheduler(SchedulerError::IncorrectChainId)),
+			Err(QuorumNotReached) => Err(TaskError::Scheduler(SchedulerError::QuorumNotReached)),
+		}
+	}
+
+	/// Completing the task removes it, there is nothing else to look up.
+	fn is_persisted(_id: &T::Hash) -> bool {
+		false
+	}
+}
+
+impl<T: Config> OffchainVerification<T> for TransferReverificationFor<T> {
+	type Output = Reverification;
+
+	fn verify(&self) -> VerificationResult<Self::Output> {
+		crate::Pallet::<T>::reverify_transfer_ocw(self)
+	}
+}
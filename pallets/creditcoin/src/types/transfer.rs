@@ -7,6 +7,7 @@ use crate::TaskOutput;
 use pallet_offchain_task_scheduler::tasks::error::TaskError;
 use pallet_offchain_task_scheduler::tasks::TaskV2;
 use pallet_timestamp::Config as TimestampConfig;
+use sp_core::H256;
 use sp_runtime::traits::Hash;
 use sp_runtime::traits::UniqueSaturatedFrom;
 use sp_runtime::traits::UniqueSaturatedInto;
@@ -24,6 +25,34 @@ pub struct Transfer<AccountId, BlockNum, Hash, Moment> {
 	pub is_processed: bool,
 	pub account_id: AccountId,
 	pub timestamp: Option<Moment>,
+	/// Where the transfer was found on its chain, `None` for transfers verified before it was
+	/// recorded.
+	pub evidence: Option<TransferEvidence>,
+}
+
+/// The external block a transfer was verified in, enough to check it again independently.
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct TransferEvidence {
+	pub block_number: u64,
+	pub block_hash: H256,
+	/// The position of the transaction in its block.
+	pub transaction_index: u64,
+	/// The position among its transaction's logs of the log the transfer was read from, `None`
+	/// when it was decoded from the transaction input.
+	pub log_index: Option<u64>,
+}
+
+/// What an authority found verifying a transfer.
+#[derive(Clone, Copy, Eq, PartialEq, RuntimeDebug)]
+pub struct TransferVerification<Moment> {
+	pub timestamp: Option<Moment>,
+	pub evidence: Option<TransferEvidence>,
+}
+
+impl<Moment> TransferVerification<Moment> {
+	pub fn map_timestamp<M>(self, f: impl FnOnce(Moment) -> M) -> TransferVerification<M> {
+		TransferVerification { timestamp: self.timestamp.map(f), evidence: self.evidence }
+	}
 }
 
 #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
@@ -41,18 +70,29 @@ where
 {
 	pub fn into_output<T: Config>(
 		self,
-		timestamp: Option<T::Moment>,
+		verified: TransferVerification<T::Moment>,
 	) -> Transfer<AccountId, BlockNum, Hash, Moment>
 	where
 		T: Config<AccountId = AccountId, BlockNumber = BlockNum, Hash = Hash, Moment = Moment>,
 	{
-		Transfer { timestamp, ..self.transfer }
+		let TransferVerification { timestamp, evidence } = verified;
+		Transfer { timestamp, evidence, ..self.transfer }
 	}
 }
 
 #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
 pub struct TransferId<Hash>(Hash);
 
+/// A transfer that failed verification, kept in `FailedTransfers` for `FailedTransferTtl`
+/// blocks.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct FailedTransfer<AccountId, BlockNum> {
+	pub cause: VerificationFailureCause,
+	/// The authority that reported the failure, `None` if the verification timed out.
+	pub authority: Option<AccountId>,
+	pub block: BlockNum,
+}
+
 impl<H> TransferId<H> {
 	#[cfg(test)]
 	pub fn leaked_inner_hash<C: SystemConfig>(blockchain: &Blockchain, blockchain_tx_id: &[u8]) -> H
@@ -89,7 +129,7 @@ impl<H> From<H> for TransferId<H> {
 	}
 }
 
-type UnverifiedTransferFor<T> = UnverifiedTransfer<
+pub type UnverifiedTransferFor<T> = UnverifiedTransfer<
 	<T as SystemConfig>::AccountId,
 	<T as SystemConfig>::BlockNumber,
 	<T as SystemConfig>::Hash,
@@ -99,7 +139,8 @@ type UnverifiedTransferFor<T> = UnverifiedTransfer<
 impl<T: Config> TaskV2<T> for UnverifiedTransferFor<T>
 where
 	UnverifiedTransferFor<T>: OffchainVerification<T>,
-	<UnverifiedTransferFor<T> as OffchainVerification<T>>::Output: Into<Option<T::Moment>>,
+	<UnverifiedTransferFor<T> as OffchainVerification<T>>::Output:
+		Into<TransferVerification<T::Moment>>,
 {
 	type Call = crate::pallet::Call<T>;
 	type EvaluationError = VerificationFailureCause;
@@ -115,25 +156,7 @@ where
 		deadline: T::BlockNumber,
 		id: &T::Hash,
 	) -> Result<Self::Call, TaskError<Self::EvaluationError, Self::SchedulerError>> {
-		use crate::ocw::OffchainError::*;
-		match self.verify() {
-			Ok(timestamp) => {
-				let transfer = self.clone().into_output::<T>(timestamp.into());
-				let id = TransferId::from(*id);
-				Ok(Self::Call::persist_task_output {
-					deadline,
-					task_output: TaskOutput::from((id, transfer)),
-				})
-			},
-			Err(InvalidTask(cause)) if cause.is_fatal() => {
-				log::warn!("Failed to verify pending task {:?} : {:?}", self, cause);
-				let id = TransferId::from(*id);
-				Ok(Self::Call::fail_task { deadline, task_id: id.into(), cause })
-			},
-			Err(InvalidTask(e)) => Err(TaskError::Evaluation(e)),
-			Err(NoRpcUrl(e)) => Err(TaskError::Scheduler(e.into())),
-			Err(RpcError(e)) => Err(TaskError::Scheduler(e.into())),
-		}
+		verified_transfer_call::<T>(self, deadline, id, self.verify().map(Into::into))
 	}
 
 	fn is_persisted(id: &T::Hash) -> bool {
@@ -142,8 +165,44 @@ where
 	}
 }
 
+/// The call completing the verification task of `transfer` with the outcome `verified`.
+pub(crate) fn verified_transfer_call<T: Config>(
+	transfer: &UnverifiedTransferFor<T>,
+	deadline: T::BlockNumber,
+	id: &T::Hash,
+	verified: VerificationResult<TransferVerification<T::Moment>>,
+) -> Result<crate::pallet::Call<T>, TaskError<VerificationFailureCause, SchedulerError>> {
+	use crate::ocw::OffchainError::*;
+	match verified {
+		Ok(verified) => {
+			let transfer = transfer.clone().into_output::<T>(verified);
+			let id = TransferId::from(*id);
+			Ok(crate::pallet::Call::persist_task_output {
+				deadline,
+				task_output: TaskOutput::from((id, transfer)),
+				assignment: None,
+			})
+		},
+		Err(InvalidTask(cause)) if cause.is_fatal() => {
+			log::warn!("Failed to verify pending task {:?} : {:?}", transfer, cause);
+			let id = TransferId::from(*id);
+			Ok(crate::pallet::Call::fail_task {
+				deadline,
+				task_id: id.into(),
+				cause,
+				assignment: None,
+			})
+		},
+		Err(InvalidTask(e)) => Err(TaskError::Evaluation(e)),
+		Err(NoRpcUrl(e)) => Err(TaskError::Scheduler(e.into())),
+		Err(RpcError(e)) => Err(TaskError::Scheduler(e.into())),
+		Err(IncorrectChainId) => Err(TaskError::Scheduler(SchedulerError::IncorrectChainId)),
+		Err(QuorumNotReached) => Err(TaskError::Scheduler(SchedulerError::QuorumNotReached)),
+	}
+}
+
 impl<T: Config> OffchainVerification<T> for UnverifiedTransferFor<T> {
-	type Output = Option<T::Moment>;
+	type Output = TransferVerification<T::Moment>;
 
 	fn verify(&self) -> VerificationResult<Self::Output> {
 		crate::Pallet::<T>::verify_transfer_ocw(self)
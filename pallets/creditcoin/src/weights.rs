@@ -377,4 +377,67 @@ impl<T: frame_system::Config> crate::WeightInfo for WeightInfo<T> {
 			.saturating_add(T::DbWeight::get().reads(1))
 			.saturating_add(T::DbWeight::get().writes(1))
 	}
+	fn register_contract_address() -> Weight {
+		// Not benchmarked yet: `register_address_v2`, with the ownership verification task's
+		// TaskLifetimes (r:1 w:1), TaskKindStats (r:1 w:1) and PendingTasks (w:1) written instead
+		// of the address.
+		<Self as crate::WeightInfo>::register_address_v2()
+			.saturating_add(T::DbWeight::get().reads_writes(2, 2))
+	}
+	fn set_legacy_signing_deadline() -> Weight {
+		// Not benchmarked yet: a root call writing a single value, like `remove_authority`.
+		<Self as crate::WeightInfo>::remove_authority()
+	}
+	fn set_attestation_threshold() -> Weight {
+		// Not benchmarked yet: a root call writing a single value, like `remove_authority`.
+		<Self as crate::WeightInfo>::remove_authority()
+	}
+	fn bond_authority() -> Weight {
+		// Not benchmarked yet: an `add_authority` for the bond, plus reserving the funds
+		// (r:1 w:1).
+		<Self as crate::WeightInfo>::add_authority()
+			.saturating_add(T::DbWeight::get().reads_writes(1, 1))
+	}
+	fn unbond_authority() -> Weight {
+		// Not benchmarked yet: a `remove_authority` checking the caller isn't an authority, plus
+		// removing the bond and unreserving it (r:2 w:2).
+		<Self as crate::WeightInfo>::remove_authority()
+			.saturating_add(T::DbWeight::get().reads_writes(2, 2))
+	}
+	fn slash_authority() -> Weight {
+		// Not benchmarked yet: an `add_authority` for the bond, plus slashing the reserve into
+		// the reward pot's account (r:2 w:2).
+		<Self as crate::WeightInfo>::add_authority()
+			.saturating_add(T::DbWeight::get().reads_writes(2, 2))
+	}
+	fn rotate_authority() -> Weight {
+		// Not benchmarked yet: an `add_authority` and a `remove_authority`, plus checking the
+		// session and suspended authorities, moving the bond, its reserve and the stats, and
+		// recording the rotation (r:7 w:8).
+		<Self as crate::WeightInfo>::add_authority()
+			.saturating_add(<Self as crate::WeightInfo>::remove_authority())
+			.saturating_add(T::DbWeight::get().reads_writes(7, 8))
+	}
+	fn cancel_transfer() -> Weight {
+		// Not benchmarked yet: a `fail_transfer`, which also drops the pending task, plus
+		// finding it, clearing its lifetime and votes, and recording the cancellation
+		// (r:1 w:5).
+		<Self as crate::WeightInfo>::fail_transfer()
+			.saturating_add(T::DbWeight::get().reads_writes(1, 5))
+	}
+	fn request_header_attestation() -> Weight {
+		// Not benchmarked yet: an `add_authority` for checking the header isn't attested yet,
+		// plus checking no attestation is pending and scheduling one: TaskLifetimes (r:1 w:1),
+		// TaskKindStats (r:1 w:1) and PendingTasks (w:1).
+		<Self as crate::WeightInfo>::add_authority()
+			.saturating_add(T::DbWeight::get().reads_writes(2, 3))
+	}
+	fn verify_transfer_proof() -> Weight {
+		// Not benchmarked yet: a `persist_transfer`, plus finding the pending task and the
+		// attested header (r:3), completing the task (r:1 w:3), scheduling its re-verification
+		// (r:1 w:3) and clearing its cancellation (w:1). Checking the proof itself is charged
+		// by its length in the call.
+		<Self as crate::WeightInfo>::persist_transfer()
+			.saturating_add(T::DbWeight::get().reads_writes(5, 7))
+	}
 }
@@ -1,11 +1,56 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use parity_scale_codec::Codec;
+use parity_scale_codec::{Codec, Decode, Encode};
+use scale_info::TypeInfo;
 extern crate alloc;
 use alloc::vec::Vec;
 
+/// A task waiting to be completed by the authorities.
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode, TypeInfo)]
+pub struct PendingTask<BlockNumber, Hash, Task> {
+	pub id: Hash,
+	pub deadline: BlockNumber,
+	/// `None` for tasks scheduled before their lifetime was tracked.
+	pub scheduled_at: Option<BlockNumber>,
+	pub reschedules: u32,
+	pub task: Task,
+}
+
+/// How an authority has been keeping up with its tasks.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Encode, Decode, TypeInfo)]
+pub struct AuthorityLiveness<BlockNumber> {
+	pub is_authority: bool,
+	/// The block it was suspended at for missing too many deadlines.
+	pub suspended_at: Option<BlockNumber>,
+	pub completed: u32,
+	pub missed: u32,
+	pub consecutive_missed: u32,
+	pub last_active: Option<BlockNumber>,
+}
+
+/// How the tasks of one registered kind have fared.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Encode, Decode, TypeInfo)]
+pub struct TaskKindStats {
+	pub kind: u8,
+	pub scheduled: u32,
+	pub completed: u32,
+	pub rescheduled: u32,
+	pub expired: u32,
+	/// `ref_time` consumed rescheduling and expiring them.
+	pub weight: u64,
+}
+
 sp_api::decl_runtime_apis! {
-	pub trait TaskApi<AccountId: Codec> {
+	#[api_version(4)]
+	pub trait TaskApi<AccountId: Codec, BlockNumber: Codec, Hash: Codec, Task: Codec> {
 		fn offchain_nonce_key(acc: &AccountId) -> Vec<u8>;
+		/// Since version 2, callers check the runtime's version with `ApiExt::api_version`.
+		fn pending_tasks() -> Vec<PendingTask<BlockNumber, Hash, Task>>;
+		/// Since version 2.
+		fn pending_task(id: Hash) -> Option<PendingTask<BlockNumber, Hash, Task>>;
+		/// Since version 3.
+		fn authority_liveness(authority: AccountId) -> AuthorityLiveness<BlockNumber>;
+		/// Since version 4.
+		fn task_kind_stats() -> Vec<TaskKindStats>;
 	}
 }
@@ -0,0 +1,331 @@
+use super::pallet::{Authorities, Pallet, TaskLifetimes};
+use super::Config;
+use frame_support::pallet_prelude::{Decode, Encode, MaxEncodedLen, RuntimeDebug, TypeInfo};
+use frame_support::traits::Get;
+use primitives::vrf;
+use sp_core::{sr25519::Public, H256};
+use sp_runtime::traits::{One, Saturating};
+use sp_runtime::{PerThing, Perquintill};
+
+/// An authority's VRF output and proof over a task, showing it was selected to work on it in
+/// the task's current round. An assignment for the round before is accepted too, so output
+/// submitted right before a reschedule can still be included after it.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug, Encode, Decode, TypeInfo, MaxEncodedLen)]
+pub struct VrfAssignment {
+	pub output: [u8; 32],
+	pub proof: [u8; 64],
+}
+
+/// Restricts who may submit a task's output to a random subset of the authorities.
+pub trait TaskAssignment {
+	type AccountId;
+	type Hash;
+	/// Whether `authority` may submit the output of the task `id`. While the selection is
+	/// restricted only an `assignment` proving the authority was selected is accepted.
+	fn is_assigned(
+		id: &Self::Hash,
+		authority: &Self::AccountId,
+		assignment: Option<&VrfAssignment>,
+	) -> bool;
+}
+
+/// The local authority's standing for a task.
+pub(crate) enum LocalAssignment {
+	/// The selection isn't restricted, no proof is needed.
+	Everyone,
+	Selected(VrfAssignment),
+	NotSelected,
+}
+
+/// The VRF input of a task's current round.
+struct Selection {
+	/// `None` until the seed block is in the chain, nobody is selected before that.
+	pre_hash: Option<H256>,
+	round: u32,
+	task_id: H256,
+	authorities: u128,
+}
+
+impl<T: Config> Pallet<T> {
+	/// The chance that at least one authority is selected for a task that has been rescheduled
+	/// `reschedules` times. Every missed deadline squares the chance of nobody being selected,
+	/// until eventually every authority is.
+	pub fn sample_size(reschedules: u32) -> Perquintill {
+		let mut none_selected = T::TaskSampleSize::get().left_from_one();
+		for _ in 0..reschedules {
+			if none_selected.is_zero() {
+				break;
+			}
+			none_selected = none_selected.square();
+		}
+		none_selected.left_from_one()
+	}
+
+	/// `None` when every authority may work on the task, which is always the case in the last
+	/// round before it expires. The randomness is seeded with the hash of the block after the
+	/// one the task was scheduled in, so whoever schedules it can't know the seed beforehand, and
+	/// the round is the number of reschedules.
+	fn selection(id: &T::Hash) -> Option<Selection> {
+		let lifetime = TaskLifetimes::<T>::get(id)?;
+		let sample_size = Self::sample_size(lifetime.reschedules);
+		let end_of_life = lifetime.scheduled_at.saturating_add(T::MaxTaskLifetime::get());
+		if sample_size.is_one() || lifetime.deadline >= end_of_life {
+			return None;
+		}
+
+		let seed_block = lifetime.scheduled_at.saturating_add(One::one());
+		let pre_hash = if seed_block < frame_system::Pallet::<T>::block_number() {
+			let hash = frame_system::Pallet::<T>::block_hash(seed_block);
+			Some(H256::decode(&mut hash.as_ref()).ok()?)
+		} else {
+			None
+		};
+		let task_id = H256::decode(&mut id.as_ref()).ok()?;
+		let authorities = Authorities::<T>::count().max(1);
+
+		Some(Selection {
+			pre_hash,
+			round: lifetime.reschedules,
+			task_id,
+			authorities: authorities.into(),
+		})
+	}
+
+	/// Whether `assignment` selects `public` in the given `round` of the task.
+	fn verify_assignment(
+		selection: &Selection,
+		round: u32,
+		public: &Public,
+		assignment: &VrfAssignment,
+	) -> bool {
+		let Some(pre_hash) = selection.pre_hash else {
+			return false;
+		};
+		let threshold =
+			vrf::sortition::threshold(Self::sample_size(round), 1, selection.authorities);
+		vrf::verify_selection(
+			public,
+			pre_hash,
+			round.into(),
+			selection.task_id,
+			&assignment.output,
+			&assignment.proof,
+			threshold,
+		)
+	}
+
+	/// Whether only a random subset of the authorities may work on the task in its current round.
+	pub(crate) fn is_restricted(id: &T::Hash) -> bool {
+		Self::selection(id).is_some()
+	}
+
+	pub(crate) fn local_assignment(id: &T::Hash, public: &Public) -> LocalAssignment {
+		let Some(selection) = Self::selection(id) else {
+			return LocalAssignment::Everyone;
+		};
+		let Some(pre_hash) = selection.pre_hash else {
+			return LocalAssignment::NotSelected;
+		};
+
+		let assignment = vrf::vrf::generate_vrf(
+			crate::KEY_TYPE,
+			public,
+			pre_hash,
+			selection.round.into(),
+			selection.task_id,
+		)
+		.and_then(|(output, proof)| {
+			Some(VrfAssignment {
+				output: Decode::decode(&mut &output.encode()[..]).ok()?,
+				proof: Decode::decode(&mut &proof.encode()[..]).ok()?,
+			})
+		});
+
+		match assignment {
+			Some(assignment)
+				if Self::verify_assignment(&selection, selection.round, public, &assignment) =>
+			{
+				LocalAssignment::Selected(assignment)
+			},
+			_ => LocalAssignment::NotSelected,
+		}
+	}
+}
+
+impl<Runtime: Config> TaskAssignment for Pallet<Runtime> {
+	type AccountId = Runtime::AccountId;
+	type Hash = Runtime::Hash;
+
+	fn is_assigned(
+		id: &Self::Hash,
+		authority: &Self::AccountId,
+		assignment: Option<&VrfAssignment>,
+	) -> bool {
+		let Some(selection) = Self::selection(id) else {
+			return true;
+		};
+		// authorities sign with their sr25519 key, their account id is the public key
+		let (Some(assignment), Ok(public)) =
+			(assignment, Public::decode(&mut &authority.encode()[..]))
+		else {
+			return false;
+		};
+		Self::verify_assignment(&selection, selection.round, &public, assignment)
+			|| selection.round.checked_sub(1).map_or(false, |previous| {
+				Self::verify_assignment(&selection, previous, &public, assignment)
+			})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{LocalAssignment, TaskAssignment};
+	use crate::mock::{
+		generate_authority,
+		runtime::{AccountId, Runtime, TaskSampleSize, TaskScheduler},
+	};
+	use crate::mocked_task::MockTask;
+	use crate::tasks::{TaskScheduler as _, TaskV2};
+	use crate::TaskLifetimes;
+	use frame_support::traits::Get;
+	use runtime_utils::{ExtBuilder, RollTo, Trivial};
+	use sp_runtime::traits::IdentifyAccount;
+	use sp_runtime::{PerThing, Perquintill};
+
+	#[test]
+	fn sample_size_widens_with_every_reschedule() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			TaskSampleSize::set(&Perquintill::from_percent(90));
+
+			assert_eq!(TaskScheduler::sample_size(0), Perquintill::from_percent(90));
+			assert_eq!(TaskScheduler::sample_size(1), Perquintill::from_percent(99));
+			assert_eq!(TaskScheduler::sample_size(2), Perquintill::from_rational(9_999u64, 10_000));
+			assert!(TaskScheduler::sample_size(5).is_one());
+		});
+	}
+
+	#[test]
+	fn every_authority_is_assigned_when_selection_is_unrestricted() {
+		let mut ext_builder = ExtBuilder::default().with_keystore();
+		let pubkey = generate_authority(&mut ext_builder, 0);
+		ext_builder.build::<Runtime>().execute_with(|| {
+			Trivial::<TaskScheduler, Runtime>::roll_to(1);
+			let task = MockTask::Remark(0);
+			let id = TaskV2::<Runtime>::to_id(&task);
+			TaskScheduler::insert(&TaskScheduler::deadline(), &id, task);
+
+			let account = AccountId::new(pubkey.into_account().0);
+			assert!(TaskScheduler::is_assigned(&id, &account, None));
+			assert!(matches!(
+				TaskScheduler::local_assignment(&id, &pubkey),
+				LocalAssignment::Everyone
+			));
+		});
+	}
+
+	#[test]
+	fn the_last_round_is_open_to_every_authority() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			TaskSampleSize::set(&Perquintill::from_percent(50));
+			Trivial::<TaskScheduler, Runtime>::roll_to(1);
+			let task = MockTask::Remark(0);
+			let id = TaskV2::<Runtime>::to_id(&task);
+			let end_of_life = 1 + <Runtime as crate::Config>::MaxTaskLifetime::get();
+			TaskScheduler::insert(&end_of_life, &id, task);
+
+			assert!(TaskScheduler::is_assigned(&id, &AccountId::new([1; 32]), None));
+		});
+	}
+
+	#[test]
+	fn nobody_is_assigned_before_the_seed_block() {
+		let mut ext_builder = ExtBuilder::default().with_keystore();
+		let pubkey = generate_authority(&mut ext_builder, 0);
+		ext_builder.build::<Runtime>().execute_with(|| {
+			TaskSampleSize::set(&Perquintill::from_float(0.999_999));
+			Trivial::<TaskScheduler, Runtime>::roll_to(1);
+			let task = MockTask::Remark(0);
+			let id = TaskV2::<Runtime>::to_id(&task);
+			TaskScheduler::insert(&TaskScheduler::deadline(), &id, task);
+
+			Trivial::<TaskScheduler, Runtime>::roll_to(2);
+			assert!(matches!(
+				TaskScheduler::local_assignment(&id, &pubkey),
+				LocalAssignment::NotSelected
+			));
+
+			Trivial::<TaskScheduler, Runtime>::roll_to(3);
+			assert!(matches!(
+				TaskScheduler::local_assignment(&id, &pubkey),
+				LocalAssignment::Selected(_)
+			));
+		});
+	}
+
+	#[test]
+	fn only_a_valid_assignment_is_accepted() {
+		let mut ext_builder = ExtBuilder::default().with_keystore();
+		let pubkey = generate_authority(&mut ext_builder, 0);
+		ext_builder.build::<Runtime>().execute_with(|| {
+			// a single authority with a high sample size is almost surely selected
+			TaskSampleSize::set(&Perquintill::from_float(0.999_999));
+			Trivial::<TaskScheduler, Runtime>::roll_to(1);
+			let task = MockTask::Remark(0);
+			let id = TaskV2::<Runtime>::to_id(&task);
+			TaskScheduler::insert(&TaskScheduler::deadline(), &id, task);
+			// the seed is the hash of block 2
+			Trivial::<TaskScheduler, Runtime>::roll_to(3);
+
+			let account = AccountId::new(pubkey.into_account().0);
+			let LocalAssignment::Selected(assignment) =
+				TaskScheduler::local_assignment(&id, &pubkey)
+			else {
+				panic!("expected to be selected");
+			};
+
+			assert!(TaskScheduler::is_assigned(&id, &account, Some(&assignment)));
+			assert!(!TaskScheduler::is_assigned(&id, &account, None));
+			assert!(!TaskScheduler::is_assigned(&id, &AccountId::new([1; 32]), Some(&assignment)));
+
+			let mut forged = assignment;
+			forged.output[0] ^= 1;
+			assert!(!TaskScheduler::is_assigned(&id, &account, Some(&forged)));
+		});
+	}
+
+	#[test]
+	fn an_assignment_outlives_one_reschedule() {
+		let mut ext_builder = ExtBuilder::default().with_keystore();
+		let pubkey = generate_authority(&mut ext_builder, 0);
+		ext_builder.build::<Runtime>().execute_with(|| {
+			TaskSampleSize::set(&Perquintill::from_float(0.999_999));
+			Trivial::<TaskScheduler, Runtime>::roll_to(1);
+			let task = MockTask::Remark(0);
+			let id = TaskV2::<Runtime>::to_id(&task);
+			TaskScheduler::insert(&TaskScheduler::deadline(), &id, task);
+			// the seed is the hash of block 2
+			Trivial::<TaskScheduler, Runtime>::roll_to(3);
+
+			let account = AccountId::new(pubkey.into_account().0);
+			let LocalAssignment::Selected(assignment) =
+				TaskScheduler::local_assignment(&id, &pubkey)
+			else {
+				panic!("expected to be selected");
+			};
+
+			let reschedule = || {
+				TaskLifetimes::<Runtime>::mutate(id, |lifetime| {
+					lifetime.as_mut().expect("scheduled").reschedules += 1
+				})
+			};
+
+			reschedule();
+			assert!(TaskScheduler::is_assigned(&id, &account, Some(&assignment)));
+
+			// keeps the selection restricted two rounds later
+			TaskSampleSize::set(&Perquintill::from_percent(90));
+			reschedule();
+			assert!(!TaskScheduler::is_assigned(&id, &account, Some(&assignment)));
+		});
+	}
+}
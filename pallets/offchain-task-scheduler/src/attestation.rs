@@ -0,0 +1,201 @@
+use super::pallet::{
+	AttestationThreshold, Authorities, ConflictingVotes, Event, Pallet, TaskVotes,
+};
+use super::Config;
+use sp_runtime::Saturating;
+use sp_std::vec::Vec;
+
+/// The state of a task's output after an authority voted for it.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Attestation<AccountId> {
+	/// The output has `votes` votes but needs `threshold` before it can be persisted.
+	Pending { votes: u32, threshold: u32 },
+	/// Enough authorities agree on the output, the caller should persist it. `agreeing` are the
+	/// authorities that voted for it, including the last one.
+	Reached { agreeing: Vec<AccountId> },
+	/// The authority has already voted on this task.
+	AlreadyVoted,
+}
+
+/// M-of-N agreement between authorities on the output of a task.
+pub trait Attestations {
+	type AccountId;
+	type Hash;
+	/// Records `authority`'s vote for the output hashing to `output` of the task `id`. Votes
+	/// disagreeing with earlier ones are kept in [`ConflictingVotes`] until the task's votes are
+	/// cleared, which happens once the threshold is reached. Reaching it is noted for the
+	/// authorities' liveness.
+	fn attest(
+		id: &Self::Hash,
+		authority: &Self::AccountId,
+		output: Self::Hash,
+	) -> Attestation<Self::AccountId>;
+	fn set_attestation_threshold(threshold: u32);
+}
+
+impl<Runtime: Config> Pallet<Runtime> {
+	/// The configured threshold, capped by the number of authorities so tasks can still be
+	/// completed after authorities are removed.
+	pub fn effective_attestation_threshold() -> u32 {
+		let authorities = Authorities::<Runtime>::count();
+		AttestationThreshold::<Runtime>::get().min(authorities).max(1)
+	}
+
+	/// Drops the task's votes, conflicting ones included, once it's completed, cancelled or
+	/// its deadline passed.
+	pub(crate) fn clear_votes(id: &Runtime::Hash) {
+		let _ = TaskVotes::<Runtime>::clear_prefix(id, u32::MAX, None);
+		let _ = ConflictingVotes::<Runtime>::clear_prefix(id, u32::MAX, None);
+	}
+}
+
+impl<Runtime: Config> Attestations for Pallet<Runtime> {
+	type AccountId = Runtime::AccountId;
+	type Hash = Runtime::Hash;
+
+	fn attest(
+		id: &Self::Hash,
+		authority: &Self::AccountId,
+		output: Self::Hash,
+	) -> Attestation<Self::AccountId> {
+		if TaskVotes::<Runtime>::contains_key(id, authority) {
+			return Attestation::AlreadyVoted;
+		}
+
+		let mut votes = 1u32;
+		let mut agreeing = Vec::new();
+		let mut conflicting = false;
+		for (other, vote) in TaskVotes::<Runtime>::iter_prefix(id) {
+			if vote == output {
+				votes.saturating_inc();
+				agreeing.push(other);
+			} else {
+				conflicting = true;
+			}
+		}
+
+		if conflicting {
+			ConflictingVotes::<Runtime>::insert(id, authority, output);
+			Pallet::<Runtime>::deposit_event(Event::<Runtime>::ConflictingVote(
+				*id,
+				authority.clone(),
+				output,
+			));
+		}
+
+		let threshold = Self::effective_attestation_threshold();
+		if votes >= threshold {
+			agreeing.push(authority.clone());
+			Self::note_agreement(id, &agreeing);
+			Self::clear_votes(id);
+			return Attestation::Reached { agreeing };
+		}
+
+		TaskVotes::<Runtime>::insert(id, authority, output);
+		Attestation::Pending { votes, threshold }
+	}
+
+	fn set_attestation_threshold(threshold: u32) {
+		AttestationThreshold::<Runtime>::put(threshold);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Attestation, Attestations};
+	use crate::authority::AuthorityController;
+	use crate::mock::runtime::{AccountId, Runtime, System, TaskScheduler};
+	use runtime_utils::{ExtBuilder, RollTo, Trivial};
+	use sp_core::H256;
+
+	fn authorities(n: u8) -> Vec<AccountId> {
+		(0..n)
+			.map(|i| {
+				let account = AccountId::new([i; 32]);
+				TaskScheduler::insert_authority(&account);
+				account
+			})
+			.collect()
+	}
+
+	#[test]
+	fn single_vote_is_enough_by_default() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			let [a, _b] = &authorities(2)[..] else { unreachable!() };
+
+			assert_eq!(
+				TaskScheduler::attest(&H256::zero(), a, H256::repeat_byte(1)),
+				Attestation::Reached { agreeing: vec![a.clone()] }
+			);
+		});
+	}
+
+	#[test]
+	fn output_is_attested_once_threshold_agrees() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			let [a, b, c] = &authorities(3)[..] else { unreachable!() };
+			TaskScheduler::set_attestation_threshold(2);
+			let (id, output) = (H256::zero(), H256::repeat_byte(1));
+
+			assert_eq!(
+				TaskScheduler::attest(&id, a, output),
+				Attestation::Pending { votes: 1, threshold: 2 }
+			);
+			assert_eq!(TaskScheduler::attest(&id, a, output), Attestation::AlreadyVoted);
+			assert_eq!(
+				TaskScheduler::attest(&id, b, output),
+				Attestation::Reached { agreeing: vec![a.clone(), b.clone()] }
+			);
+			assert!(TaskScheduler::task_votes(id, a).is_none());
+
+			// a late vote starts over
+			assert_eq!(
+				TaskScheduler::attest(&id, c, output),
+				Attestation::Pending { votes: 1, threshold: 2 }
+			);
+		});
+	}
+
+	#[test]
+	fn conflicting_votes_are_recorded() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			Trivial::<TaskScheduler, Runtime>::roll_to(1);
+			let [a, b, c] = &authorities(3)[..] else { unreachable!() };
+			TaskScheduler::set_attestation_threshold(2);
+			let id = H256::zero();
+
+			TaskScheduler::attest(&id, a, H256::repeat_byte(1));
+			assert_eq!(
+				TaskScheduler::attest(&id, b, H256::repeat_byte(2)),
+				Attestation::Pending { votes: 1, threshold: 2 }
+			);
+			assert_eq!(TaskScheduler::conflicting_votes(id, b), Some(H256::repeat_byte(2)));
+			System::assert_last_event(
+				crate::Event::<Runtime>::ConflictingVote(id, b.clone(), H256::repeat_byte(2))
+					.into(),
+			);
+
+			// only the authorities that agree on the output are reported
+			assert_eq!(
+				TaskScheduler::attest(&id, c, H256::repeat_byte(1)),
+				Attestation::Reached { agreeing: vec![a.clone(), c.clone()] }
+			);
+			// the event keeps the record
+			assert!(TaskScheduler::conflicting_votes(id, b).is_none());
+		});
+	}
+
+	#[test]
+	fn threshold_is_capped_by_the_number_of_authorities() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			let [a] = &authorities(1)[..] else { unreachable!() };
+			TaskScheduler::set_attestation_threshold(3);
+
+			assert_eq!(TaskScheduler::effective_attestation_threshold(), 1);
+			assert_eq!(
+				TaskScheduler::attest(&H256::zero(), a, H256::repeat_byte(1)),
+				Attestation::Reached { agreeing: vec![a.clone()] }
+			);
+		});
+	}
+}
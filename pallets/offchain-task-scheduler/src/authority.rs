@@ -1,32 +1,66 @@
-use super::pallet::{Authorities, Pallet};
+use super::pallet::{
+	Authorities, AuthorityStats, Error, Event, Pallet, RotatedFrom, SessionAuthorities,
+	SuspendedAuthorities,
+};
 use super::Config;
+use frame_support::dispatch::DispatchResult;
+use frame_support::ensure;
 
 pub trait AuthorityController {
 	type AccountId;
 	fn insert_authority(authority: &Self::AccountId);
 	fn remove_authority(authority: &Self::AccountId);
 	fn is_authority(authority: &Self::AccountId) -> bool;
+	/// Replaces `old` with `new` in one go, handing over its bond and liveness record.
+	fn rotate_authority(old: &Self::AccountId, new: &Self::AccountId) -> DispatchResult;
 }
 
 impl<Runtime: Config> AuthorityController for Pallet<Runtime> {
 	type AccountId = Runtime::AccountId;
 
 	fn insert_authority(authority: &Self::AccountId) {
+		Pallet::<Runtime>::reinstate(authority);
 		Authorities::<Runtime>::insert(authority, ());
 	}
 	fn remove_authority(authority: &Self::AccountId) {
 		Authorities::<Runtime>::remove(authority);
+		RotatedFrom::<Runtime>::remove(authority);
 	}
 	fn is_authority(authority: &Self::AccountId) -> bool {
 		Authorities::<Runtime>::contains_key(authority)
 	}
+	fn rotate_authority(old: &Self::AccountId, new: &Self::AccountId) -> DispatchResult {
+		// the next session would undo the rotation, validators set new session keys instead
+		ensure!(
+			!SessionAuthorities::<Runtime>::contains_key(old),
+			Error::<Runtime>::SessionAuthority
+		);
+		// a suspended account only comes back when root adds it
+		ensure!(!SuspendedAuthorities::<Runtime>::contains_key(new), Error::<Runtime>::Suspended);
+
+		Pallet::<Runtime>::transfer_bond(old, new)?;
+		Authorities::<Runtime>::remove(old);
+		Authorities::<Runtime>::insert(new, ());
+		AuthorityStats::<Runtime>::insert(new, AuthorityStats::<Runtime>::take(old));
+		RotatedFrom::<Runtime>::remove(old);
+		RotatedFrom::<Runtime>::insert(new, old.clone());
+
+		Pallet::<Runtime>::deposit_event(Event::<Runtime>::AuthorityRotated(
+			old.clone(),
+			new.clone(),
+		));
+		Ok(())
+	}
 }
 
 #[cfg(test)]
 mod tests {
 	use crate::authority::AuthorityController;
-	use crate::mock::runtime::{AccountId, Runtime, TaskScheduler};
-	use runtime_utils::ExtBuilder;
+	use crate::incentives::AuthorityIncentives;
+	use crate::mock::runtime::{AccountId, Balances, Runtime, System, TaskScheduler};
+	use crate::{SessionAuthorities, SuspendedAuthorities};
+	use frame_support::{assert_noop, assert_ok, traits::Currency};
+	use runtime_utils::{ExtBuilder, RollTo, Trivial};
 
 	#[test]
 	fn insert_check_and_remove() {
@@ -47,4 +81,77 @@ mod tests {
 			assert_eq!(value, None)
 		});
 	}
+
+	#[test]
+	fn rotate_hands_over_bond_and_record() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			Trivial::<TaskScheduler, Runtime>::roll_to(1);
+			let (old, new) = (AccountId::new([1; 32]), AccountId::new([2; 32]));
+			Balances::make_free_balance_be(&old, 1_000_000);
+			Balances::make_free_balance_be(&new, 1_000);
+			TaskScheduler::insert_authority(&old);
+			assert_ok!(TaskScheduler::bond(&old, 10_000));
+			TaskScheduler::note_completed(&old);
+
+			assert_ok!(TaskScheduler::rotate_authority(&old, &new));
+
+			assert!(!TaskScheduler::is_authority(&old));
+			assert!(TaskScheduler::is_authority(&new));
+			assert_eq!(TaskScheduler::bonds(&new), 10_000);
+			assert_eq!(Balances::reserved_balance(&new), 10_000);
+			assert_eq!(Balances::reserved_balance(&old), 0);
+			assert_eq!(TaskScheduler::authority_stats(&new).completed, 1);
+			assert_eq!(TaskScheduler::rotated_from(&new), Some(old.clone()));
+			System::assert_last_event(
+				crate::Event::<Runtime>::AuthorityRotated(old, new.clone()).into(),
+			);
+
+			TaskScheduler::remove_authority(&new);
+			assert_eq!(TaskScheduler::rotated_from(&new), None);
+		});
+	}
+
+	#[test]
+	fn rotation_record_is_dropped_once_the_new_authority_completes_a_task() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			Trivial::<TaskScheduler, Runtime>::roll_to(1);
+			let (old, new) = (AccountId::new([1; 32]), AccountId::new([2; 32]));
+			Balances::make_free_balance_be(&old, 1_000_000);
+			TaskScheduler::insert_authority(&old);
+			assert_ok!(TaskScheduler::bond(&old, 10_000));
+			assert_ok!(TaskScheduler::rotate_authority(&old, &new));
+
+			TaskScheduler::note_completed(&new);
+
+			assert_eq!(TaskScheduler::rotated_from(&new), None);
+		});
+	}
+
+	#[test]
+	fn suspended_accounts_are_not_rotated_to() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			let (old, new) = (AccountId::new([1; 32]), AccountId::new([2; 32]));
+			TaskScheduler::insert_authority(&old);
+			SuspendedAuthorities::<Runtime>::insert(&new, 1);
+
+			assert_noop!(
+				TaskScheduler::rotate_authority(&old, &new),
+				crate::Error::<Runtime>::Suspended
+			);
+		});
+	}
+
+	#[test]
+	fn session_authorities_are_not_rotated() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			let (old, new) = (AccountId::new([1; 32]), AccountId::new([2; 32]));
+			TaskScheduler::insert_authority(&old);
+			SessionAuthorities::<Runtime>::insert(&old, ());
+
+			assert_noop!(
+				TaskScheduler::rotate_authority(&old, &new),
+				crate::Error::<Runtime>::SessionAuthority
+			);
+		});
+	}
 }
@@ -25,7 +25,7 @@ benchmarks! {
 	 }
 	on_initialize {
 		//insert t transfers
-		let t in 0..1024;
+		let t in 0..T::MaxExpiriesPerBlock::get();
 
 		<Timestamp<T>>::set_timestamp(1u32.into());
 
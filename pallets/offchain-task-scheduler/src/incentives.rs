@@ -0,0 +1,236 @@
+use super::pallet::{Authorities, Bonds, Error, Event, Pallet};
+use super::Config;
+use frame_support::dispatch::DispatchResult;
+use frame_support::ensure;
+use frame_support::traits::{
+	BalanceStatus, Currency, ExistenceRequirement, Get, Imbalance, OnUnbalanced, ReservableCurrency,
+};
+use sp_runtime::traits::{AccountIdConversion, Saturating, Zero};
+use tracing as log;
+
+pub type BalanceOf<T> =
+	<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+pub type NegativeImbalanceOf<T> = <<T as Config>::Currency as Currency<
+	<T as frame_system::Config>::AccountId,
+>>::NegativeImbalance;
+
+/// Bonds put up by authorities, rewards for their work and slashing of their bonds.
+pub trait AuthorityIncentives {
+	type AccountId;
+	type Hash;
+	type Balance;
+	/// Reserves `amount` more of `authority`'s funds as its bond.
+	fn bond(authority: &Self::AccountId, amount: Self::Balance) -> DispatchResult;
+	/// Releases the whole bond. Only accounts that are no longer authorities can unbond.
+	fn unbond(authority: &Self::AccountId) -> DispatchResult;
+	/// Whether `authority`'s bond covers the required minimum. Session authorities need a bond
+	/// too, slashing only reaches bonds.
+	fn is_bonded(authority: &Self::AccountId) -> bool;
+	/// Pays the task reward out of the reward pot.
+	fn reward(authority: &Self::AccountId);
+	/// Slashes up to `amount` of the bond into the reward pot for a wrong attestation on the
+	/// task `id`. Returns the amount actually slashed.
+	fn slash(authority: &Self::AccountId, id: &Self::Hash, amount: Self::Balance) -> Self::Balance;
+}
+
+impl<T: Config> Pallet<T> {
+	/// The account rewards are paid from.
+	pub fn reward_pot() -> T::AccountId {
+		T::PalletId::get().into_account_truncating()
+	}
+
+	/// Moves `from`'s bond, still reserved, to `to`. `to` must already exist.
+	pub(crate) fn transfer_bond(from: &T::AccountId, to: &T::AccountId) -> DispatchResult {
+		let bond = Bonds::<T>::take(from);
+		if bond.is_zero() {
+			return Ok(());
+		}
+
+		let unmoved = T::Currency::repatriate_reserved(from, to, bond, BalanceStatus::Reserved)?;
+		Bonds::<T>::mutate(to, |b| b.saturating_accrue(bond.saturating_sub(unmoved)));
+		Ok(())
+	}
+}
+
+impl<Runtime: Config> AuthorityIncentives for Pallet<Runtime> {
+	type AccountId = Runtime::AccountId;
+	type Hash = Runtime::Hash;
+	type Balance = BalanceOf<Runtime>;
+
+	fn bond(authority: &Self::AccountId, amount: Self::Balance) -> DispatchResult {
+		Runtime::Currency::reserve(authority, amount)?;
+		let total = Bonds::<Runtime>::mutate(authority, |bond| {
+			bond.saturating_accrue(amount);
+			*bond
+		});
+		Pallet::<Runtime>::deposit_event(Event::<Runtime>::AuthorityBonded(
+			authority.clone(),
+			total,
+		));
+		Ok(())
+	}
+
+	fn unbond(authority: &Self::AccountId) -> DispatchResult {
+		ensure!(
+			!Authorities::<Runtime>::contains_key(authority),
+			Error::<Runtime>::StillAnAuthority
+		);
+		let bond = Bonds::<Runtime>::get(authority);
+		ensure!(!bond.is_zero(), Error::<Runtime>::NotBonded);
+
+		Bonds::<Runtime>::remove(authority);
+
+		let _ = Runtime::Currency::unreserve(authority, bond);
+		Pallet::<Runtime>::deposit_event(Event::<Runtime>::AuthorityUnbonded(
+			authority.clone(),
+			bond,
+		));
+		Ok(())
+	}
+
+	fn is_bonded(authority: &Self::AccountId) -> bool {
+		Bonds::<Runtime>::get(authority) >= Runtime::AuthorityBond::get()
+	}
+
+	fn reward(authority: &Self::AccountId) {
+		let reward = Runtime::TaskReward::get();
+		if reward.is_zero() {
+			return;
+		}
+
+		match Runtime::Currency::transfer(
+			&Pallet::<Runtime>::reward_pot(),
+			authority,
+			reward,
+			ExistenceRequirement::KeepAlive,
+		) {
+			Ok(()) => Pallet::<Runtime>::deposit_event(Event::<Runtime>::AuthorityRewarded(
+				authority.clone(),
+				reward,
+			)),
+			Err(e) => log::warn!("Failed to reward authority {authority:?}: {e:?}"),
+		}
+	}
+
+	fn slash(authority: &Self::AccountId, id: &Self::Hash, amount: Self::Balance) -> Self::Balance {
+		let bond = Bonds::<Runtime>::get(authority);
+		let (imbalance, _) = Runtime::Currency::slash_reserved(authority, amount.min(bond));
+		let slashed = imbalance.peek();
+
+		Bonds::<Runtime>::insert(authority, bond.saturating_sub(slashed));
+		RewardPot::<Runtime>::on_unbalanced(imbalance);
+		Pallet::<Runtime>::deposit_event(Event::<Runtime>::AuthoritySlashed(
+			authority.clone(),
+			*id,
+			slashed,
+		));
+		slashed
+	}
+}
+
+/// Collects imbalances, e.g. slashed bonds, into the reward pot.
+pub struct RewardPot<T>(sp_std::marker::PhantomData<T>);
+
+impl<T: Config> OnUnbalanced<NegativeImbalanceOf<T>> for RewardPot<T> {
+	fn on_nonzero_unbalanced(amount: NegativeImbalanceOf<T>) {
+		T::Currency::resolve_creating(&Pallet::<T>::reward_pot(), amount);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::AuthorityIncentives;
+	use crate::authority::AuthorityController;
+	use crate::mock::runtime::{
+		AccountId, AuthorityBond, Balances, Runtime, System, TaskScheduler,
+	};
+	use frame_support::{assert_noop, assert_ok, traits::Currency};
+	use runtime_utils::{ExtBuilder, RollTo, Trivial};
+	use sp_core::H256;
+
+	fn funded(seed: u8) -> AccountId {
+		let account = AccountId::new([seed; 32]);
+		Balances::make_free_balance_be(&account, 1_000_000);
+		account
+	}
+
+	#[test]
+	fn bond_and_unbond() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			AuthorityBond::set(&1_000);
+			let authority = funded(1);
+			TaskScheduler::insert_authority(&authority);
+			assert!(!TaskScheduler::is_bonded(&authority));
+
+			assert_ok!(TaskScheduler::bond(&authority, 600));
+			assert_ok!(TaskScheduler::bond(&authority, 400));
+			assert!(TaskScheduler::is_bonded(&authority));
+			assert_eq!(Balances::reserved_balance(&authority), 1_000);
+
+			assert_noop!(
+				TaskScheduler::unbond(&authority),
+				crate::Error::<Runtime>::StillAnAuthority
+			);
+			TaskScheduler::remove_authority(&authority);
+			assert_ok!(TaskScheduler::unbond(&authority));
+			assert_eq!(Balances::reserved_balance(&authority), 0);
+			assert_noop!(TaskScheduler::unbond(&authority), crate::Error::<Runtime>::NotBonded);
+		});
+	}
+
+	#[test]
+	fn session_authorities_need_a_bond() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			AuthorityBond::set(&1_000);
+			let validator = funded(1);
+			TaskScheduler::insert_authority(&validator);
+			crate::SessionAuthorities::<Runtime>::insert(&validator, ());
+			assert!(!TaskScheduler::is_bonded(&validator));
+
+			assert_ok!(TaskScheduler::bond(&validator, 1_000));
+			assert!(TaskScheduler::is_bonded(&validator));
+		});
+	}
+
+	#[test]
+	fn reward_is_paid_from_the_pot() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			Trivial::<TaskScheduler, Runtime>::roll_to(1);
+			let authority = funded(1);
+
+			// an empty pot pays nothing
+			TaskScheduler::reward(&authority);
+			assert_eq!(Balances::free_balance(&authority), 1_000_000);
+
+			Balances::make_free_balance_be(&TaskScheduler::reward_pot(), 1_000);
+			TaskScheduler::reward(&authority);
+			assert_eq!(Balances::free_balance(&authority), 1_000_010);
+			System::assert_last_event(
+				crate::Event::<Runtime>::AuthorityRewarded(authority, 10).into(),
+			);
+		});
+	}
+
+	#[test]
+	fn slash_moves_bond_into_the_pot() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			Trivial::<TaskScheduler, Runtime>::roll_to(1);
+			AuthorityBond::set(&10_000);
+			let authority = funded(1);
+			assert_ok!(TaskScheduler::bond(&authority, 10_000));
+
+			assert_eq!(TaskScheduler::slash(&authority, &H256::zero(), 4_000), 4_000);
+			assert!(!TaskScheduler::is_bonded(&authority));
+			assert_eq!(TaskScheduler::bonds(&authority), 6_000);
+			assert_eq!(Balances::free_balance(TaskScheduler::reward_pot()), 4_000);
+			System::assert_last_event(
+				crate::Event::<Runtime>::AuthoritySlashed(authority.clone(), H256::zero(), 4_000)
+					.into(),
+			);
+
+			// never more than the bond
+			assert_eq!(TaskScheduler::slash(&authority, &H256::zero(), 100_000), 6_000);
+			assert_eq!(Balances::reserved_balance(&authority), 0);
+		});
+	}
+}
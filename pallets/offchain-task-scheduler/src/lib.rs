@@ -1,29 +1,46 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 extern crate alloc;
+use frame_support::pallet_prelude::{Decode, Encode, MaxEncodedLen, RuntimeDebug, TypeInfo};
 use frame_support::traits::Get;
+use frame_support::weights::Weight;
 use frame_system::offchain::AppCrypto;
 use frame_system::pallet_prelude::BlockNumberFor;
 use frame_system::Config as SystemConfig;
 pub use ocw::nonce::nonce_key;
 #[cfg(feature = "std")]
 pub use pallet::GenesisConfig;
-pub use pallet::{Authorities, Config, Error, Event, Pallet, WeightInfo};
+pub use pallet::{
+	AttestationThreshold, Authorities, AuthorityStats, Bonds, Config, ConflictingVotes, Error,
+	Event, Pallet, PendingTasks, RotatedFrom, SessionAuthorities, SuspendedAuthorities,
+	TaskKindStats, TaskLifetimes, TaskVotes, WeightInfo,
+};
 pub use pallet::{
 	__InherentHiddenInstance, __substrate_call_check, __substrate_event_check,
 	__substrate_genesis_config_check, tt_default_parts, tt_error_token,
 };
+use registry::TaskRegistry;
 use sp_core::offchain::KeyTypeId;
 use sp_runtime::traits::BlockNumberProvider;
 use sp_runtime::traits::Saturating;
+use sp_std::vec::Vec;
+use tasks::{ForwardTask, OnTaskExpired};
 use tracing as log;
 
+pub mod assignment;
+pub mod attestation;
 pub mod authority;
 pub mod authorship;
 pub mod benchmarking;
+pub mod incentives;
+pub mod liveness;
+pub mod migrations;
 pub mod mock;
 pub mod mocked_task;
 pub mod ocw;
+pub mod registry;
+pub mod session;
+pub mod submission;
 pub mod tasks;
 #[allow(clippy::unnecessary_cast)]
 pub mod weights;
@@ -61,21 +78,30 @@ pub mod crypto {
 #[frame_support::pallet]
 pub mod pallet {
 	use super::{
+		assignment::LocalAssignment,
 		authorship::Authorship,
+		incentives::BalanceOf,
+		liveness::Liveness,
 		log,
+		registry::{KindStats, TaskKindIndex, TaskRegistry},
+		submission::{self, SubmissionMode, TaskSubmissionFor},
 		tasks::{self, ForwardTask},
-		AppCrypto, Saturating, SystemConfig,
+		AppCrypto, SystemConfig, TaskLifetime,
 	};
-	use crate::ocw::RuntimePublicOf;
+	use crate::ocw::{RuntimePublicOf, WorkBudget};
+	use core::cmp::Reverse;
 	use core::fmt::Debug;
-	use frame_support::dispatch::Dispatchable;
 	use frame_support::dispatch::Vec;
+	use frame_support::dispatch::{Dispatchable, GetDispatchInfo, PostDispatchInfo};
 	use frame_support::pallet_prelude::*;
+	use frame_support::{traits::ReservableCurrency, PalletId};
 	use frame_system::offchain::CreateSignedTransaction;
 	use frame_system::pallet_prelude::*;
 	use scale_info::TypeInfo;
 	use sp_core::sr25519::Public;
 	use sp_runtime::codec::FullCodec;
+	use sp_runtime::traits::IdentifyAccount;
+	use sp_runtime::transaction_validity::TransactionPriority;
 
 	#[pallet::config]
 	pub trait Config:
@@ -86,10 +112,59 @@ pub mod pallet {
 			+ FullCodec
 			+ MaxEncodedLen
 			+ ForwardTask<Self, Call = Self::TaskCall>
+			+ TaskRegistry
 			+ Debug;
 		type UnverifiedTaskTimeout: Get<<Self as SystemConfig>::BlockNumber>;
+		/// A task that hasn't been completed by its deadline is rescheduled, with exponentially
+		/// growing deadlines, until it has been pending for this many blocks.
+		type MaxTaskLifetime: Get<<Self as SystemConfig>::BlockNumber>;
+		/// The most tasks rescheduled or expired in a block, the others are carried over to the
+		/// next blocks.
+		#[pallet::constant]
+		type MaxExpiriesPerBlock: Get<u32>;
+		type OnTaskExpired: tasks::OnTaskExpired<Self::Hash, Self::Task>;
+		/// The chance that at least one authority is selected to work on a task. Authorities
+		/// are drawn by VRF and the selection widens every time the task is rescheduled.
+		/// `Perquintill::one()` lets every authority work on every task.
+		type TaskSampleSize: Get<sp_runtime::Perquintill>;
+		type Currency: ReservableCurrency<Self::AccountId>;
+		/// Derives the account of the pot authorities are rewarded from.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+		/// Paid to an authority for every task output it submits that is accepted.
+		#[pallet::constant]
+		type TaskReward: Get<BalanceOf<Self>>;
+		/// The minimum bond an authority must hold to submit task outputs.
+		#[pallet::constant]
+		type AuthorityBond: Get<BalanceOf<Self>>;
+		/// Milliseconds the offchain worker may spend starting tasks in a block.
+		type OffchainTimeBudget: Get<u64>;
+		/// HTTP requests the offchain worker's tasks may make in a block, see
+		/// [`ForwardTask::http_requests`].
+		type OffchainRequestBudget: Get<u32>;
+		/// Consecutive missed deadlines after which an authority is reported as unresponsive.
+		/// Zero disables the warning.
+		type MissedTasksBeforeWarning: Get<u32>;
+		/// Consecutive missed deadlines after which an authority is suspended until root adds
+		/// it back. Zero disables suspension.
+		type MissedTasksBeforeSuspension: Get<u32>;
+		/// The most authorities an agreement on a task's output is noted for, agreeing or
+		/// absent, see [`AuthorityLiveness`](liveness::AuthorityLiveness).
+		#[pallet::constant]
+		type MaxAuthoritiesNoted: Get<u32>;
 		type WeightInfo: WeightInfo;
-		type TaskCall: Dispatchable<RuntimeOrigin = Self::RuntimeOrigin> + Clone;
+		type TaskCall: Dispatchable<RuntimeOrigin = Self::RuntimeOrigin, PostInfo = PostDispatchInfo>
+			+ GetDispatchInfo
+			+ Parameter
+			+ From<Call<Self>>;
+		/// Whether task outputs are submitted in signed or unsigned transactions.
+		type SubmissionMode: Get<SubmissionMode>;
+		/// The calls authorities may submit unsigned and the tasks they complete, see
+		/// [`Pallet::submit_task_output`].
+		type TaskOutputs: submission::TaskOutputs<Self::TaskCall, Self::BlockNumber, Self::Hash>;
+		/// The pool priority of unsigned task outputs.
+		#[pallet::constant]
+		type UnsignedPriority: Get<TransactionPriority>;
 		type AuthorityId: AppCrypto<Self::Public, Self::Signature>;
 		type Authorship: Authorship<
 			RuntimePublic = RuntimePublicOf<Self>,
@@ -102,9 +177,48 @@ pub mod pallet {
 	}
 
 	#[pallet::event]
-	pub enum Event<T: Config> {}
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A task has been scheduled and will be picked up by the authorities.
+		/// [task_id, deadline]
+		TaskScheduled(T::Hash, T::BlockNumber),
+		/// The task's result has been persisted and it is no longer pending.
+		/// [task_id]
+		TaskCompleted(T::Hash),
+		/// The task reached the end of its lifetime without being completed.
+		/// [task_id, scheduled_at]
+		TaskExpired(T::Hash, T::BlockNumber),
+		/// An authority voted for an output that disagrees with an earlier vote.
+		/// [task_id, authority, output_hash]
+		ConflictingVote(T::Hash, T::AccountId, T::Hash),
+		/// An authority added to its bond.
+		/// [authority, total_bond]
+		AuthorityBonded(T::AccountId, BalanceOf<T>),
+		/// A former authority released its bond.
+		/// [authority, amount]
+		AuthorityUnbonded(T::AccountId, BalanceOf<T>),
+		/// An authority was paid for an accepted task output.
+		/// [authority, amount]
+		AuthorityRewarded(T::AccountId, BalanceOf<T>),
+		/// An authority's bond was slashed for a wrong attestation.
+		/// [authority, task_id, amount]
+		AuthoritySlashed(T::AccountId, T::Hash, BalanceOf<T>),
+		/// An authority has missed several deadlines in a row.
+		/// [authority, consecutive_missed]
+		AuthorityUnresponsive(T::AccountId, u32),
+		/// An authority missed too many deadlines in a row and is no longer an authority.
+		/// [authority]
+		AuthoritySuspended(T::AccountId),
+		/// An authority's account was replaced.
+		/// [old_authority, new_authority]
+		AuthorityRotated(T::AccountId, T::AccountId),
+		/// The task was withdrawn before being completed.
+		/// [task_id]
+		TaskCancelled(T::Hash),
+	}
 
 	#[pallet::pallet]
+	#[pallet::storage_version(migrations::STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	#[pallet::storage]
@@ -112,9 +226,91 @@ pub mod pallet {
 	pub type PendingTasks<T: Config> =
 		StorageDoubleMap<_, Identity, T::BlockNumber, Identity, T::Hash, T::Task>;
 
+	/// The earliest deadline with tasks left over from a block that reached
+	/// `MaxExpiriesPerBlock`.
+	#[pallet::storage]
+	pub type ExpiryCursor<T: Config> = StorageValue<_, T::BlockNumber>;
+
+	/// When the pending task was scheduled, its current deadline and how often it has been
+	/// rescheduled.
+	#[pallet::storage]
+	#[pallet::getter(fn task_lifetimes)]
+	pub type TaskLifetimes<T: Config> =
+		StorageMap<_, Identity, T::Hash, TaskLifetime<T::BlockNumber>>;
+
 	#[pallet::storage]
 	#[pallet::getter(fn authorities)]
-	pub type Authorities<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, ()>;
+	pub type Authorities<T: Config> = CountedStorageMap<_, Blake2_128Concat, T::AccountId, ()>;
+
+	/// The authorities derived from validators' `gots` session keys, as opposed to those added
+	/// by root. They are replaced every time the validator set changes.
+	#[pallet::storage]
+	#[pallet::getter(fn session_authorities)]
+	pub type SessionAuthorities<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, ()>;
+
+	/// The `gots` key validators were given when it was added to the session keys, see
+	/// [`migrations::v4`]. They become authorities once they set a key of their own.
+	#[pallet::storage]
+	#[pallet::getter(fn inherited_keys)]
+	pub type InheritedKeys<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, crypto::Public>;
+
+	#[pallet::type_value]
+	pub fn DefaultAttestationThreshold() -> u32 {
+		1
+	}
+
+	/// How many authorities must agree on a task's output before it is persisted.
+	#[pallet::storage]
+	#[pallet::getter(fn attestation_threshold)]
+	pub type AttestationThreshold<T: Config> =
+		StorageValue<_, u32, ValueQuery, DefaultAttestationThreshold>;
+
+	/// In-flight votes on the output of pending tasks. [task_id, authority] -> output_hash
+	#[pallet::storage]
+	#[pallet::getter(fn task_votes)]
+	pub type TaskVotes<T: Config> =
+		StorageDoubleMap<_, Identity, T::Hash, Blake2_128Concat, T::AccountId, T::Hash>;
+
+	/// Funds reserved by authorities as their bond.
+	#[pallet::storage]
+	#[pallet::getter(fn bonds)]
+	pub type Bonds<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, BalanceOf<T>, ValueQuery>;
+
+	/// How each authority has been keeping up with its tasks.
+	#[pallet::storage]
+	#[pallet::getter(fn authority_stats)]
+	pub type AuthorityStats<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, Liveness<T::BlockNumber>, ValueQuery>;
+
+	/// Authorities suspended for missing too many deadlines, with the block they were suspended
+	/// at. Their session keys don't make them authorities again.
+	#[pallet::storage]
+	#[pallet::getter(fn suspended_authorities)]
+	pub type SuspendedAuthorities<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, T::BlockNumber>;
+
+	/// The account each rotated authority replaced. The offchain worker of the new account
+	/// drops the nonce it tracked for the old one before its first submission, so the entry is
+	/// removed once the new authority is credited with a task, or when it is removed or
+	/// suspended.
+	#[pallet::storage]
+	#[pallet::getter(fn rotated_from)]
+	pub type RotatedFrom<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, T::AccountId>;
+
+	/// Votes that disagreed with an earlier vote on the same task, kept while its votes are. The
+	/// `ConflictingVote` events are the lasting record.
+	#[pallet::storage]
+	#[pallet::getter(fn conflicting_votes)]
+	pub type ConflictingVotes<T: Config> =
+		StorageDoubleMap<_, Identity, T::Hash, Blake2_128Concat, T::AccountId, T::Hash>;
+
+	/// How the tasks of each registered kind fared, see [`TaskRegistry`].
+	#[pallet::storage]
+	#[pallet::getter(fn task_kind_stats)]
+	pub type TaskKindStats<T: Config> =
+		StorageMap<_, Twox64Concat, TaskKindIndex, KindStats, ValueQuery>;
 
 	#[derive(PartialEq, Eq)]
 	#[pallet::error]
@@ -125,6 +321,17 @@ pub mod pallet {
 		/// The node is an authority but there is no account to create a
 		/// callback transaction. This is likely an internal error.
 		NoLocalAcctForSignedTx,
+		/// The bond can only be released once the account is no longer an authority.
+		StillAnAuthority,
+		/// The account has no bond.
+		NotBonded,
+		/// The authority comes from a validator's session keys, rotate those instead.
+		SessionAuthority,
+		/// The account was suspended, root has to add it back as an authority.
+		Suspended,
+		/// Failed to send an unsigned transaction with a task output. This is likely an
+		/// internal error.
+		OffchainUnsignedTxFailed,
 	}
 
 	#[pallet::hooks]
@@ -134,21 +341,50 @@ pub mod pallet {
 			Into<T::Public> + AsRef<Public> + sp_std::fmt::Debug + Clone,
 	{
 		fn on_initialize(block_number: T::BlockNumber) -> Weight {
-			log::debug!("Cleaning up expired entries");
-
-			let mut unverified_task_count = 0u32;
-			let mut cursor: Option<Vec<u8>> = None;
-			loop {
-				let result =
-					PendingTasks::<T>::clear_prefix(block_number, u32::MAX, cursor.as_deref());
-				unverified_task_count.saturating_accrue(result.backend);
-				cursor = result.maybe_cursor;
-				if cursor.is_none() {
+			log::debug!("Rescheduling or cleaning up expired entries");
+
+			let limit = T::MaxExpiriesPerBlock::get();
+			let mut deadline = ExpiryCursor::<T>::take().unwrap_or(block_number);
+			let mut expired = Vec::new();
+			let mut blocks = 0u64;
+			while deadline <= block_number {
+				blocks.saturating_inc();
+				let left = limit.saturating_sub(expired.len() as u32) as usize;
+				let before = expired.len();
+				expired.extend(
+					PendingTasks::<T>::drain_prefix(deadline)
+						.take(left)
+						.map(|(id, task)| (deadline, id, task)),
+				);
+				if expired.len().saturating_sub(before) == left {
+					// the block may have more, it is picked up again next time
+					log::debug!("Carrying expired tasks of {deadline:?} over");
+					ExpiryCursor::<T>::put(deadline);
 					break;
 				}
+				deadline.saturating_inc();
+			}
+
+			let unverified_task_count = expired.len() as u32;
+			let mut weight = <T as Config>::WeightInfo::on_initialize(unverified_task_count)
+				.saturating_add(T::DbWeight::get().reads_writes(
+					2u64.saturating_mul(unverified_task_count.into()).saturating_add(blocks),
+					4u64.saturating_mul(unverified_task_count.into()).saturating_add(1),
+				));
+			for (deadline, id, task) in expired {
+				weight.saturating_accrue(Self::reschedule_or_expire(
+					block_number,
+					deadline,
+					id,
+					task,
+				));
 			}
 
-			<T as Config>::WeightInfo::on_initialize(unverified_task_count)
+			weight
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			migrations::migrate::<T>()
 		}
 
 		fn offchain_worker(block_number: T::BlockNumber) {
@@ -160,45 +396,141 @@ pub mod pallet {
 				},
 			};
 
-			for (deadline, id, task) in PendingTasks::<T>::iter() {
-				let storage_key = tasks::storage_key(&id);
-				let mut lock = tasks::task_lock::<T>(&storage_key);
+			let mut pending: Vec<_> = PendingTasks::<T>::iter().collect();
+			pending.sort_by_key(|(deadline, _, task)| (Reverse(task.priority()), *deadline));
+			let mut budget = WorkBudget::new::<T>();
+
+			let mut selected = Vec::new();
+			for (deadline, id, task) in pending {
+				if task.is_persisted(&id) {
+					log::debug!(target: "task", "Already handled Task {:8?}", id);
+					continue;
+				}
 
-				let guard = match lock.try_lock() {
-					Ok(g) => g,
-					Err(_) => continue,
+				let assignment = match Self::local_assignment(&id, signer.as_ref()) {
+					LocalAssignment::Everyone => None,
+					LocalAssignment::Selected(assignment) => Some(assignment),
+					LocalAssignment::NotSelected => {
+						log::trace!(target: "task", "Not selected for task {:8?}", id);
+						continue;
+					},
 				};
 
+				selected.push((deadline, id, task, assignment));
+			}
+
+			let storage_keys: Vec<_> = selected
+				.iter()
+				.map(|(_, id, ..)| {
+					let scheduled_at = TaskLifetimes::<T>::get(id).map(|l| l.scheduled_at);
+					tasks::storage_key(id, &scheduled_at)
+				})
+				.collect();
+			let mut locks: Vec<_> = storage_keys
+				.iter()
+				.zip(&selected)
+				.map(|(key, (_, _, task, _))| tasks::task_lock::<T>(key, Self::timeout_of(task)))
+				.collect();
+			let mut claimed = Vec::new();
+			for (selected, lock) in selected.into_iter().zip(locks.iter_mut()) {
+				// tasks that are still locked are being worked on by an earlier run and cost
+				// this one nothing
+				let Ok(guard) = lock.try_lock() else {
+					continue;
+				};
+				if !budget.try_spend(selected.2.http_requests()) {
+					log::debug!(target: "task", "@{block_number:?} Out of budget, deferring the remaining tasks");
+					break;
+				}
+				claimed.push((selected, guard));
+			}
+
+			let prefetched = {
+				let tasks: Vec<_> =
+					claimed.iter().map(|((_, id, task, _), _)| (id, task)).collect();
+				T::Task::prefetch(&tasks, budget.until())
+			};
+
+			for ((deadline, id, task, assignment), guard) in claimed {
 				log::trace!(target: "task", "@{block_number:?} Task {:8?}", id);
 
 				use tasks::error::TaskError::*;
-				match task.forward_task(deadline) {
+				match task.forward_task(deadline, assignment, &prefetched) {
 					Ok(call) => {
-						match Self::submit_txn_with_synced_nonce(signer.clone().into(), |_| {
-							call.clone()
-						}) {
-							Ok(_) => guard.forget(),
+						let submitted = match T::SubmissionMode::get() {
+							SubmissionMode::Signed => {
+								Self::submit_txn_with_synced_nonce(signer.clone().into(), |_| {
+									call.clone()
+								})
+							},
+							SubmissionMode::Unsigned => {
+								Self::offchain_unsigned_tx(signer.clone().into(), call)
+							},
+						};
+						match submitted {
+							Ok(_) => {
+								tasks::clear_attempts(&id);
+								guard.forget()
+							},
 							Err(e) => {
-								log::error!("Failed to send a dispatchable transaction: {:?}", e)
+								log::error!("Failed to send a dispatchable transaction: {:?}", e);
+								tasks::record_failed_attempt(&id, block_number, e);
 							},
 						}
 					},
 					Err(FinishedTask) => {
 						log::debug!("Already handled Task ({:?}, {:?}) {task:?}", deadline, id);
+						tasks::clear_attempts(&id);
 						guard.forget();
 						continue;
 					},
 					Err(Evaluation(cause)) => {
 						log::warn!("Failed to verify pending task {:?} : {:?}", task, cause);
+						tasks::record_failed_attempt(&id, block_number, cause);
 					},
 					Err(Scheduler(error)) => {
-						log::error!("Task verification encountered a processing error {:?}", error)
+						log::error!("Task verification encountered a processing error {:?}", error);
+						tasks::record_failed_attempt(&id, block_number, error);
 					},
 				}
 			}
 		}
 	}
 
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Dispatches a task output from the account of the authority that signed it, for
+		/// authorities that don't send their outputs in signed transactions.
+		#[pallet::call_index(0)]
+		#[pallet::weight({
+			let info = submission.output.get_dispatch_info();
+			(info.weight, info.class, Pays::No)
+		})]
+		pub fn submit_task_output(
+			origin: OriginFor<T>,
+			submission: TaskSubmissionFor<T>,
+			_signature: T::Signature,
+		) -> DispatchResultWithPostInfo {
+			ensure_none(origin)?;
+			let authority = submission.public.into_account();
+			submission.output.dispatch(frame_system::RawOrigin::Signed(authority).into())
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			match call {
+				Call::submit_task_output { submission, signature } => {
+					Self::validate_submission(submission, signature)
+				},
+				_ => InvalidTransaction::Call.into(),
+			}
+		}
+	}
+
 	#[pallet::genesis_config]
 	pub struct GenesisConfig<T: Config> {
 		pub authorities: Vec<T::AccountId>,
@@ -221,6 +553,101 @@ pub mod pallet {
 	}
 }
 
+/// Tracks a pending task across reschedules.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug, Encode, Decode, TypeInfo, MaxEncodedLen)]
+pub struct TaskLifetime<BlockNumber> {
+	pub scheduled_at: BlockNumber,
+	pub deadline: BlockNumber,
+	pub reschedules: u32,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Called for every task still pending at its deadline, at `block_number` or later when
+	/// the deadline's block had too many. Those are tasks that only failed with non-fatal
+	/// errors, or that no authority got to, so they are given another
+	/// `UnverifiedTaskTimeout * 2^reschedules` blocks, up to `MaxTaskLifetime` after they were
+	/// first scheduled. Missing the deadline isn't blamed on anyone, see
+	/// [`AuthorityLiveness`](liveness::AuthorityLiveness). Returns the weight consumed by
+	/// [`Config::OnTaskExpired`], which is also charged to the task's kind.
+	fn reschedule_or_expire(
+		block_number: T::BlockNumber,
+		deadline: T::BlockNumber,
+		id: T::Hash,
+		task: T::Task,
+	) -> Weight {
+		let kind = task.kind();
+		let lifetime = match TaskLifetimes::<T>::get(id) {
+			Some(lifetime) if lifetime.deadline != deadline => {
+				// an oversubscribed task, the other entry is the one being tracked
+				return Weight::zero();
+			},
+			Some(lifetime) => lifetime,
+			None => TaskLifetime {
+				scheduled_at: deadline.saturating_sub(Self::timeout_of(&task)),
+				deadline,
+				reschedules: 0,
+			},
+		};
+
+		// votes only count until the deadline, authorities vote again on the rescheduled task
+		Self::clear_votes(&id);
+
+		let end_of_life = lifetime.scheduled_at.saturating_add(T::MaxTaskLifetime::get());
+		if block_number >= end_of_life {
+			log::debug!("Task {id:?} expired");
+			TaskLifetimes::<T>::remove(id);
+			Self::deposit_event(Event::<T>::TaskExpired(id, lifetime.scheduled_at));
+			let weight = T::OnTaskExpired::on_task_expired(&id, task);
+			TaskKindStats::<T>::mutate(kind, |stats| {
+				stats.expired.saturating_inc();
+				stats.weight.saturating_accrue(weight);
+			});
+			return weight;
+		}
+
+		let backoff = Self::timeout_of(&task)
+			.saturating_mul(2u32.saturating_pow(lifetime.reschedules).into());
+		let deadline = block_number.saturating_add(backoff).min(end_of_life);
+		log::debug!("Rescheduling task {id:?} until {deadline:?}");
+		let mut task = task;
+		task.set_deadline(deadline);
+		PendingTasks::<T>::insert(deadline, id, task);
+		TaskLifetimes::<T>::insert(
+			id,
+			TaskLifetime {
+				deadline,
+				reschedules: lifetime.reschedules.saturating_add(1),
+				..lifetime
+			},
+		);
+		TaskKindStats::<T>::mutate(kind, |stats| stats.rescheduled.saturating_inc());
+		Weight::zero()
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	pub(crate) fn timeout_of(task: &T::Task) -> T::BlockNumber {
+		task.timeout().unwrap_or_else(T::UnverifiedTaskTimeout::get)
+	}
+
+	/// Every pending task with its deadline and, when it is tracked, its lifetime.
+	pub fn pending_task_list(
+	) -> Vec<(T::BlockNumber, T::Hash, T::Task, Option<TaskLifetime<T::BlockNumber>>)> {
+		PendingTasks::<T>::iter()
+			.map(|(deadline, id, task)| (deadline, id, task, TaskLifetimes::<T>::get(id)))
+			.collect()
+	}
+
+	/// Looks up a pending task by id, every pending task has a lifetime since the v1 migration.
+	pub fn find_pending_task(
+		id: &T::Hash,
+	) -> Option<(T::BlockNumber, T::Task, TaskLifetime<T::BlockNumber>)> {
+		let lifetime = TaskLifetimes::<T>::get(id)?;
+		let task = PendingTasks::<T>::get(lifetime.deadline, id)?;
+		Some((lifetime.deadline, task, lifetime))
+	}
+}
+
 type TaskFor<T> = <T as Config>::Task;
 type HashFor<T> = <T as SystemConfig>::Hash;
 
@@ -234,13 +661,47 @@ impl<Runtime: Config> tasks::TaskScheduler for Pallet<Runtime> {
 		let block = frame_system::Pallet::<Runtime>::current_block_number();
 		offset.saturating_add(block)
 	}
+	fn deadline_for(task: &TaskFor<Runtime>) -> BlockNumberFor<Runtime> {
+		let block = frame_system::Pallet::<Runtime>::current_block_number();
+		Pallet::<Runtime>::timeout_of(task).saturating_add(block)
+	}
 	fn is_scheduled(deadline: &BlockNumberFor<Runtime>, id: &HashFor<Runtime>) -> bool {
 		crate::pallet::PendingTasks::<Runtime>::contains_key(deadline, id)
 	}
+	fn is_pending(id: &HashFor<Runtime>) -> bool {
+		crate::pallet::TaskLifetimes::<Runtime>::contains_key(id)
+	}
 	fn insert(deadline: &BlockNumberFor<Runtime>, id: &HashFor<Runtime>, task: TaskFor<Runtime>) {
+		TaskKindStats::<Runtime>::mutate(task.kind(), |stats| stats.scheduled.saturating_inc());
 		crate::pallet::PendingTasks::<Runtime>::insert(deadline, id, task);
+		let scheduled_at = frame_system::Pallet::<Runtime>::current_block_number();
+		crate::pallet::TaskLifetimes::<Runtime>::insert(
+			id,
+			TaskLifetime { scheduled_at, deadline: *deadline, reschedules: 0 },
+		);
+		Pallet::<Runtime>::deposit_event(Event::<Runtime>::TaskScheduled(*id, *deadline));
 	}
 	fn remove(deadline: &BlockNumberFor<Runtime>, id: &HashFor<Runtime>) {
-		crate::pallet::PendingTasks::<Runtime>::remove(deadline, id);
+		let pending = crate::pallet::PendingTasks::<Runtime>::take(deadline, id);
+		// the task may have been rescheduled since the caller learned its deadline
+		let rescheduled = crate::pallet::TaskLifetimes::<Runtime>::take(id).and_then(|lifetime| {
+			crate::pallet::PendingTasks::<Runtime>::take(lifetime.deadline, id)
+		});
+		Pallet::<Runtime>::clear_votes(id);
+		if let Some(task) = pending.or(rescheduled) {
+			TaskKindStats::<Runtime>::mutate(task.kind(), |stats| stats.completed.saturating_inc());
+			Pallet::<Runtime>::deposit_event(Event::<Runtime>::TaskCompleted(*id));
+		}
+	}
+	fn find(id: &HashFor<Runtime>) -> Option<(BlockNumberFor<Runtime>, TaskFor<Runtime>)> {
+		Pallet::<Runtime>::find_pending_task(id).map(|(deadline, task, _)| (deadline, task))
+	}
+	fn cancel(id: &HashFor<Runtime>) -> Option<TaskFor<Runtime>> {
+		let (deadline, _, _) = Pallet::<Runtime>::find_pending_task(id)?;
+		let task = crate::pallet::PendingTasks::<Runtime>::take(deadline, id);
+		crate::pallet::TaskLifetimes::<Runtime>::remove(id);
+		Pallet::<Runtime>::clear_votes(id);
+		Pallet::<Runtime>::deposit_event(Event::<Runtime>::TaskCancelled(*id));
+		task
 	}
 }
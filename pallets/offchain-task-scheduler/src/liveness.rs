@@ -0,0 +1,246 @@
+use super::pallet::{
+	AttestationThreshold, Authorities, AuthorityStats, Event, Pallet, RotatedFrom,
+	SessionAuthorities, SuspendedAuthorities, TaskLifetimes, TaskVotes,
+};
+use super::Config;
+use frame_support::pallet_prelude::{Decode, Encode, MaxEncodedLen, RuntimeDebug, TypeInfo};
+use frame_support::traits::Get;
+use frame_support::weights::Weight;
+use sp_runtime::Saturating;
+use sp_std::vec::Vec;
+
+/// How an authority has been keeping up with its tasks.
+#[derive(
+	Clone, Copy, Default, PartialEq, Eq, RuntimeDebug, Encode, Decode, TypeInfo, MaxEncodedLen,
+)]
+pub struct Liveness<BlockNumber> {
+	/// Task outputs and failures it agreed on with enough other authorities to be accepted.
+	pub completed: u32,
+	/// Tasks that were agreed on without its vote, after being open to every authority for a
+	/// whole round.
+	pub missed: u32,
+	/// Tasks missed since it last agreed on one.
+	pub consecutive_missed: u32,
+	pub last_active: Option<BlockNumber>,
+}
+
+/// Tracks whether authorities are doing their work. It's noted when the authorities reach
+/// agreement on a task's output or on its fatal failure: the agreeing authorities are credited,
+/// and once the task has outlived a round open to every authority, those that didn't vote on it
+/// miss it. A task that stays pending is never blamed on anyone, it can stay pending for reasons
+/// outside of the authorities' control, e.g. a transfer that never gets confirmed, and a single
+/// authority voting on it mustn't get the others suspended.
+pub trait AuthorityLiveness {
+	/// The most weight noting an agreement takes, at most `MaxAuthoritiesNoted` authorities are.
+	fn max_liveness_weight() -> Weight;
+}
+
+impl<T: Config> Pallet<T> {
+	/// Notes that `agreeing` agreed on the task `id`, before its votes are cleared.
+	pub(crate) fn note_agreement(id: &T::Hash, agreeing: &[T::AccountId]) {
+		let limit = T::MaxAuthoritiesNoted::get() as usize;
+		let agreeing = &agreeing[..agreeing.len().min(limit)];
+		for authority in agreeing {
+			Self::note_completed(authority);
+		}
+
+		let outlived_a_round =
+			TaskLifetimes::<T>::get(id).map_or(false, |lifetime| lifetime.reschedules > 0);
+		if !outlived_a_round || Self::is_restricted(id) {
+			return;
+		}
+
+		// authorities that voted for another output aren't absent
+		let absent: Vec<_> = Authorities::<T>::iter_keys()
+			.take(limit)
+			.filter(|authority| {
+				!agreeing.contains(authority) && !TaskVotes::<T>::contains_key(id, authority)
+			})
+			.collect();
+		for authority in absent {
+			Self::note_missed(&authority);
+		}
+	}
+
+	pub(crate) fn note_completed(authority: &T::AccountId) {
+		let now = frame_system::Pallet::<T>::block_number();
+		// it has submitted since, so its offchain worker no longer needs the old nonce
+		RotatedFrom::<T>::remove(authority);
+		AuthorityStats::<T>::mutate(authority, |stats| {
+			stats.completed.saturating_inc();
+			stats.consecutive_missed = 0;
+			stats.last_active = Some(now);
+		});
+	}
+
+	fn note_missed(authority: &T::AccountId) {
+		let consecutive_missed = AuthorityStats::<T>::mutate(authority, |stats| {
+			stats.missed.saturating_inc();
+			stats.consecutive_missed.saturating_inc();
+			stats.consecutive_missed
+		});
+
+		let suspend_after = T::MissedTasksBeforeSuspension::get();
+		if suspend_after != 0 && consecutive_missed >= suspend_after && Self::can_suspend() {
+			Self::suspend(authority);
+		} else if consecutive_missed == T::MissedTasksBeforeWarning::get() {
+			Self::deposit_event(Event::<T>::AuthorityUnresponsive(
+				authority.clone(),
+				consecutive_missed,
+			));
+		}
+	}
+
+	/// Suspending an authority mustn't leave too few authorities to reach the attestation
+	/// threshold, tasks would never complete again.
+	fn can_suspend() -> bool {
+		Authorities::<T>::count() > AttestationThreshold::<T>::get().max(1)
+	}
+
+	fn suspend(authority: &T::AccountId) {
+		Authorities::<T>::remove(authority);
+		SessionAuthorities::<T>::remove(authority);
+		RotatedFrom::<T>::remove(authority);
+		SuspendedAuthorities::<T>::insert(authority, frame_system::Pallet::<T>::block_number());
+		Self::deposit_event(Event::<T>::AuthoritySuspended(authority.clone()));
+	}
+
+	/// Lets a suspended authority start over, done when it is added back.
+	pub(crate) fn reinstate(authority: &T::AccountId) {
+		SuspendedAuthorities::<T>::remove(authority);
+		AuthorityStats::<T>::mutate(authority, |stats| stats.consecutive_missed = 0);
+	}
+}
+
+impl<Runtime: Config> AuthorityLiveness for Pallet<Runtime> {
+	fn max_liveness_weight() -> Weight {
+		// the task's lifetime and restriction, the stats and rotation record of the agreeing
+		// authorities, then per authority checked its vote and stats, and a suspension
+		let noted = u64::from(Runtime::MaxAuthoritiesNoted::get());
+		Runtime::DbWeight::get().reads_writes(
+			noted.saturating_mul(4).saturating_add(4),
+			noted.saturating_mul(3).saturating_add(4),
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Liveness;
+	use crate::attestation::{Attestation, Attestations};
+	use crate::authority::AuthorityController;
+	use crate::mock::runtime::{
+		AccountId, MissedTasksBeforeSuspension, MissedTasksBeforeWarning, Runtime, System,
+		TaskScheduler,
+	};
+	use crate::mocked_task::MockTask;
+	use crate::tasks::{TaskScheduler as _, TaskV2};
+	use runtime_utils::{ExtBuilder, RollTo, Trivial};
+	use sp_core::H256;
+
+	fn authorities(seeds: [u8; 3]) -> [AccountId; 3] {
+		seeds.map(|seed| {
+			let authority = AccountId::new([seed; 32]);
+			TaskScheduler::insert_authority(&authority);
+			authority
+		})
+	}
+
+	/// Schedules a task and lets it miss its first deadline.
+	fn schedule_past_a_round(n: u32) -> H256 {
+		let task = MockTask::Remark(n);
+		let id = TaskV2::<Runtime>::to_id(&task);
+		TaskScheduler::insert(&TaskScheduler::deadline(), &id, task);
+		Trivial::<TaskScheduler, Runtime>::roll_to(TaskScheduler::deadline());
+		assert!(TaskScheduler::is_pending(&id));
+		id
+	}
+
+	fn agree(id: &H256, authorities: &[&AccountId]) {
+		for authority in authorities {
+			TaskScheduler::attest(id, authority, H256::repeat_byte(1));
+		}
+	}
+
+	#[test]
+	fn agreement_credits_the_agreeing_and_counts_a_miss_against_the_absent() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			Trivial::<TaskScheduler, Runtime>::roll_to(1);
+			let [a, b, c] = authorities([1, 2, 3]);
+			TaskScheduler::set_attestation_threshold(2);
+
+			let id = schedule_past_a_round(0);
+			TaskScheduler::attest(&id, &b, H256::repeat_byte(2));
+			agree(&id, &[&a, &c]);
+
+			for agreeing in [&a, &c] {
+				let stats = TaskScheduler::authority_stats(agreeing);
+				assert_eq!((stats.completed, stats.missed), (1, 0));
+				assert_eq!(stats.last_active, Some(System::block_number()));
+			}
+			// a conflicting vote is no miss
+			assert_eq!(TaskScheduler::authority_stats(&b), Liveness::default());
+
+			let id = schedule_past_a_round(1);
+			agree(&id, &[&a, &c]);
+			let stats = TaskScheduler::authority_stats(&b);
+			assert_eq!((stats.completed, stats.missed, stats.consecutive_missed), (0, 1, 1));
+		});
+	}
+
+	#[test]
+	fn pending_votes_and_fresh_tasks_count_for_nobody() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			Trivial::<TaskScheduler, Runtime>::roll_to(1);
+			let [a, b, c] = authorities([1, 2, 3]);
+			TaskScheduler::set_attestation_threshold(2);
+
+			// a task the others leave pending isn't blamed on them
+			let id = schedule_past_a_round(0);
+			assert_eq!(
+				TaskScheduler::attest(&id, &a, H256::repeat_byte(1)),
+				Attestation::Pending { votes: 1, threshold: 2 }
+			);
+			Trivial::<TaskScheduler, Runtime>::roll_to(TaskScheduler::deadline() + 10);
+			for authority in [&a, &b, &c] {
+				assert_eq!(TaskScheduler::authority_stats(authority), Liveness::default());
+			}
+
+			// nor one agreed on in its first round
+			let task = MockTask::Remark(1);
+			let id = TaskV2::<Runtime>::to_id(&task);
+			TaskScheduler::insert(&TaskScheduler::deadline(), &id, task);
+			agree(&id, &[&a, &b]);
+			assert_eq!(TaskScheduler::authority_stats(&c), Liveness::default());
+		});
+	}
+
+	#[test]
+	fn unresponsive_authorities_are_warned_then_suspended() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			Trivial::<TaskScheduler, Runtime>::roll_to(1);
+			MissedTasksBeforeWarning::set(&1);
+			MissedTasksBeforeSuspension::set(&2);
+			let [a, b, c] = authorities([1, 2, 3]);
+			TaskScheduler::set_attestation_threshold(2);
+
+			let id = schedule_past_a_round(0);
+			agree(&id, &[&a, &b]);
+			System::assert_has_event(
+				crate::Event::<Runtime>::AuthorityUnresponsive(c.clone(), 1).into(),
+			);
+			assert!(TaskScheduler::is_authority(&c));
+
+			let id = schedule_past_a_round(1);
+			agree(&id, &[&a, &b]);
+			System::assert_has_event(crate::Event::<Runtime>::AuthoritySuspended(c.clone()).into());
+			assert!(TaskScheduler::suspended_authorities(&c).is_some());
+			assert!(!TaskScheduler::is_authority(&c));
+			assert_eq!(TaskScheduler::authority_stats(&a).completed, 2);
+
+			TaskScheduler::insert_authority(&c);
+			assert!(TaskScheduler::suspended_authorities(&c).is_none());
+			assert_eq!(TaskScheduler::authority_stats(&c).consecutive_missed, 0);
+		});
+	}
+}
@@ -0,0 +1,171 @@
+use crate::incentives::AuthorityIncentives;
+use crate::{Authorities, Bonds, Config, Pallet, PendingTasks, TaskLifetime, TaskLifetimes};
+use frame_support::traits::{Get, GetStorageVersion, StorageVersion};
+use frame_support::weights::Weight;
+use sp_runtime::traits::{Saturating, Zero};
+use tracing as log;
+
+pub const STORAGE_VERSION: StorageVersion = StorageVersion::new(4);
+
+pub(crate) fn migrate<T: Config>() -> Weight {
+	let mut weight = T::DbWeight::get().reads(1);
+	if Pallet::<T>::on_chain_storage_version() < 1 {
+		weight.saturating_accrue(v1::migrate::<T>());
+		StorageVersion::new(1).put::<Pallet<T>>();
+		weight.saturating_accrue(T::DbWeight::get().writes(1));
+	}
+	if Pallet::<T>::on_chain_storage_version() < 2 {
+		weight.saturating_accrue(v2::migrate::<T>());
+		StorageVersion::new(2).put::<Pallet<T>>();
+		weight.saturating_accrue(T::DbWeight::get().writes(1));
+	}
+	if Pallet::<T>::on_chain_storage_version() < 3 {
+		weight.saturating_accrue(v3::migrate::<T>());
+		StorageVersion::new(3).put::<Pallet<T>>();
+		weight.saturating_accrue(T::DbWeight::get().writes(1));
+	}
+	if Pallet::<T>::on_chain_storage_version() < 4 {
+		StorageVersion::new(4).put::<Pallet<T>>();
+		weight.saturating_accrue(T::DbWeight::get().writes(1));
+	}
+	weight
+}
+
+/// Tasks scheduled before their lifetime was tracked get one, so that they can be found by id.
+/// They count as scheduled a timeout before their deadline, like tasks that reach their deadline
+/// without a lifetime.
+mod v1 {
+	use super::*;
+
+	pub(super) fn migrate<T: Config>() -> Weight {
+		let mut weight = Weight::zero();
+		for (deadline, id, task) in PendingTasks::<T>::iter() {
+			weight.saturating_accrue(T::DbWeight::get().reads(2));
+			if TaskLifetimes::<T>::contains_key(id) {
+				continue;
+			}
+			let scheduled_at = deadline.saturating_sub(Pallet::<T>::timeout_of(&task));
+			TaskLifetimes::<T>::insert(id, TaskLifetime { scheduled_at, deadline, reschedules: 0 });
+			weight.saturating_accrue(T::DbWeight::get().writes(1));
+		}
+		weight
+	}
+}
+
+/// `Authorities` became a counted map, so that the number of authorities can be read without
+/// iterating over them. Its counter starts out empty.
+mod v2 {
+	use super::*;
+
+	pub(super) fn migrate<T: Config>() -> Weight {
+		let authorities = Authorities::<T>::initialize_counter();
+		T::DbWeight::get().reads_writes(u64::from(authorities).saturating_add(1), 1)
+	}
+}
+
+/// Ships with `AuthorityBond` raised from zero. The authorities bond the missing amount out of
+/// their free balance, those that can't afford it can't submit task outputs until they bond.
+mod v3 {
+	use super::*;
+
+	pub(super) fn migrate<T: Config>() -> Weight {
+		let required = T::AuthorityBond::get();
+		let mut weight = Weight::zero();
+		for authority in Authorities::<T>::iter_keys() {
+			weight.saturating_accrue(T::DbWeight::get().reads(2));
+			let missing = required.saturating_sub(Bonds::<T>::get(&authority));
+			if missing.is_zero() {
+				continue;
+			}
+			match Pallet::<T>::bond(&authority, missing) {
+				Ok(()) => weight.saturating_accrue(T::DbWeight::get().reads_writes(1, 2)),
+				Err(e) => log::warn!("Authority {authority:?} couldn't bond {missing:?}: {e:?}"),
+			}
+		}
+		weight
+	}
+}
+
+/// The `gots` key was added to the session keys. The runtime upgrades its session keys before
+/// the pallet's storage reaches version 4, giving every validator its `im_online` key as its
+/// `gots` key with [`v4::inherit_key`]. Only the validator holds that key's secret, but it has to
+/// set a `gots` key of its own to become an authority.
+pub mod v4 {
+	use super::*;
+	use crate::{crypto::Public as AuthorityKey, InheritedKeys};
+
+	pub fn inherit_key<T: Config>(validator: &T::AccountId, key: AuthorityKey) -> AuthorityKey {
+		InheritedKeys::<T>::insert(validator, &key);
+		key
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::authority::AuthorityController;
+	use crate::mock::runtime::{AccountId, AuthorityBond, Balances, Runtime};
+	use crate::mocked_task::MockTask;
+	use crate::tasks::{TaskScheduler, TaskV2};
+	use frame_support::traits::{Currency, ReservableCurrency};
+	use runtime_utils::ExtBuilder;
+
+	#[test]
+	fn legacy_tasks_get_a_lifetime() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			let task = MockTask::Remark(0);
+			let id = TaskV2::<Runtime>::to_id(&task);
+			PendingTasks::<Runtime>::insert(10, id, task);
+			StorageVersion::new(0).put::<Pallet<Runtime>>();
+
+			migrate::<Runtime>();
+
+			// the mock's timeout is 5 blocks
+			assert_eq!(
+				TaskLifetimes::<Runtime>::get(id),
+				Some(TaskLifetime { scheduled_at: 5, deadline: 10, reschedules: 0 })
+			);
+			assert!(Pallet::<Runtime>::is_pending(&id));
+			assert_eq!(Pallet::<Runtime>::on_chain_storage_version(), STORAGE_VERSION);
+		});
+	}
+
+	#[test]
+	fn authorities_are_counted() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			// authorities stored before the map was counted
+			for authority in [AccountId::new([1; 32]), AccountId::new([2; 32])] {
+				let key = Authorities::<Runtime>::hashed_key_for(authority);
+				frame_support::storage::unhashed::put(&key, &());
+			}
+			assert_eq!(Authorities::<Runtime>::count(), 0);
+			StorageVersion::new(1).put::<Pallet<Runtime>>();
+
+			migrate::<Runtime>();
+
+			assert_eq!(Authorities::<Runtime>::count(), 2);
+			assert_eq!(Pallet::<Runtime>::on_chain_storage_version(), STORAGE_VERSION);
+		});
+	}
+
+	#[test]
+	fn authorities_bond_the_required_amount() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			AuthorityBond::set(&1_000);
+			let [funded, broke] = [AccountId::new([1; 32]), AccountId::new([2; 32])];
+			for authority in [&funded, &broke] {
+				Pallet::<Runtime>::insert_authority(authority);
+			}
+			Balances::make_free_balance_be(&funded, 10_000);
+			StorageVersion::new(2).put::<Pallet<Runtime>>();
+
+			migrate::<Runtime>();
+
+			assert_eq!(Bonds::<Runtime>::get(&funded), 1_000);
+			assert_eq!(Balances::reserved_balance(&funded), 1_000);
+			assert!(Pallet::<Runtime>::is_bonded(&funded));
+			assert_eq!(Bonds::<Runtime>::get(&broke), 0);
+			assert_eq!(Pallet::<Runtime>::on_chain_storage_version(), STORAGE_VERSION);
+		});
+	}
+}
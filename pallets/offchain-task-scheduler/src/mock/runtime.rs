@@ -1,6 +1,6 @@
 use frame_support::{
 	self as support, parameter_types,
-	traits::{ConstU32, ConstU64},
+	traits::{ConstU128, ConstU32, ConstU64},
 };
 use frame_system::{
 	self as system,
@@ -52,6 +52,17 @@ impl system::Config for Runtime {
 	type MaxConsumers = ConstU32<{ u32::MAX }>;
 }
 
+parameter_types! {
+	pub storage TaskSampleSize: sp_runtime::Perquintill = sp_runtime::Perquintill::one();
+	pub storage AuthorityBond: Balance = 0;
+	pub storage MissedTasksBeforeWarning: u32 = 0;
+	pub storage MissedTasksBeforeSuspension: u32 = 0;
+	pub storage OffchainTimeBudget: u64 = 10_000;
+	pub storage OffchainRequestBudget: u32 = 100;
+	pub storage Submission: crate::submission::SubmissionMode = Default::default();
+	pub const TaskSchedulerPalletId: support::PalletId = support::PalletId(*b"cc/tasks");
+}
+
 parameter_types! {
 	pub const ExistentialDeposit: u128 = 500;
 	pub const MaxLocks: u32 = 50;
@@ -109,11 +120,51 @@ where
 	}
 }
 
+/// A mock task's output doesn't name its task, it completes the pending task with the same nonce.
+pub struct MockTaskOutputs;
+
+impl crate::submission::TaskOutputs<RuntimeCall, BlockNumber, Hash> for MockTaskOutputs {
+	fn task_of(call: &RuntimeCall) -> Option<crate::submission::CompletedTask<BlockNumber, Hash>> {
+		use crate::mocked_task::MockTask;
+		use parity_scale_codec::Encode;
+
+		let RuntimeCall::System(system::Call::remark_with_event { remark }) = call else {
+			return None;
+		};
+		crate::PendingTasks::<Runtime>::iter().find_map(|(deadline, task_id, task)| {
+			let (MockTask::Remark(nonce) | MockTask::Urgent(nonce)) = task else {
+				return None;
+			};
+			(nonce.encode() == *remark).then_some(crate::submission::CompletedTask {
+				deadline,
+				task_id,
+				assignment: None,
+			})
+		})
+	}
+}
+
 impl crate::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type UnverifiedTaskTimeout = ConstU64<5>;
+	type MaxTaskLifetime = ConstU64<20>;
+	type MaxExpiriesPerBlock = ConstU32<3>;
+	type OnTaskExpired = ();
+	type TaskSampleSize = TaskSampleSize;
+	type Currency = Balances;
+	type PalletId = TaskSchedulerPalletId;
+	type TaskReward = ConstU128<10>;
+	type AuthorityBond = AuthorityBond;
+	type MissedTasksBeforeWarning = MissedTasksBeforeWarning;
+	type MissedTasksBeforeSuspension = MissedTasksBeforeSuspension;
+	type MaxAuthoritiesNoted = ConstU32<3>;
+	type OffchainTimeBudget = OffchainTimeBudget;
+	type OffchainRequestBudget = OffchainRequestBudget;
 	type AuthorityId = crate::crypto::AuthorityId;
 	type TaskCall = RuntimeCall;
+	type SubmissionMode = Submission;
+	type TaskOutputs = MockTaskOutputs;
+	type UnsignedPriority = ConstU64<{ u64::MAX / 2 }>;
 	type WeightInfo = crate::weights::WeightInfo<Self>;
 	type Task = crate::mocked_task::MockTask<u32>;
 	type Authorship = TaskScheduler;
@@ -129,6 +180,6 @@ frame_support::construct_runtime!(
 		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
 		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
 		Timestamp: pallet_timestamp::{Pallet, Call, Storage},
-		TaskScheduler: crate::{Pallet, Storage, Event<T>},
+		TaskScheduler: crate::{Pallet, Call, Storage, Event<T>, ValidateUnsigned},
 	}
 );
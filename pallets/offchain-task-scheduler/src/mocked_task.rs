@@ -1,5 +1,7 @@
 #![cfg(feature = "mocked-task")]
-use crate::tasks::{error::TaskError, ForwardTask, TaskV2};
+use crate::assignment::VrfAssignment;
+use crate::registry::{TaskKind, TaskKindIndex, TaskRegistry};
+use crate::tasks::{error::TaskError, ForwardTask, TaskPriority, TaskV2};
 use crate::Config;
 use core::cell::Cell;
 use frame_system::pallet::Call as SystemCall;
@@ -16,10 +18,12 @@ pub(crate) fn is_persisted_replace(new: bool) -> bool {
 	PERSISTED.with(|cell| cell.replace(new))
 }
 
-#[derive(Debug, MaxEncodedLen, Encode, TypeInfo, Decode, Clone)]
+#[derive(Debug, MaxEncodedLen, Encode, TypeInfo, Decode, Clone, PartialEq, Eq)]
 /// The task's result depends on the variant.
 pub enum MockTask<T> {
 	Remark(T),
+	/// A high priority `Remark`.
+	Urgent(T),
 	Evaluation,
 	Scheduler,
 }
@@ -31,14 +35,42 @@ where
 	type Call = T::TaskCall;
 	type EvaluationError = ();
 	type SchedulerError = ();
+	type Prefetched = ();
+	fn priority(&self) -> TaskPriority {
+		match self {
+			MockTask::Urgent(_) => TaskPriority::High,
+			_ => TaskPriority::Normal,
+		}
+	}
+	fn is_persisted(&self, id: &T::Hash) -> bool {
+		<Self as TaskV2<T>>::is_persisted(id)
+	}
 	fn forward_task(
 		&self,
 		deadline: T::BlockNumber,
+		_assignment: Option<VrfAssignment>,
+		_prefetched: &(),
 	) -> Result<Self::Call, TaskError<Self::EvaluationError, Self::SchedulerError>> {
 		TaskV2::<T>::forward_task(self, deadline).map(|c| c.into())
 	}
 }
 
+impl<T: Config, Nonce: Encode + 'static> TaskKind<T> for MockTask<Nonce> where
+	T::TaskCall: From<SystemCall<T>>
+{
+}
+
+/// Every `MockTask` is of the same kind.
+impl<T> TaskRegistry for MockTask<T> {
+	fn kind(&self) -> TaskKindIndex {
+		0
+	}
+
+	fn kind_name(&self) -> &'static str {
+		"Mock"
+	}
+}
+
 impl<Runtime: Config, Nonce: Encode> TaskV2<Runtime> for MockTask<Nonce> {
 	type Call = SystemCall<Runtime>;
 	type EvaluationError = ();
@@ -57,7 +89,7 @@ impl<Runtime: Config, Nonce: Encode> TaskV2<Runtime> for MockTask<Nonce> {
 		_id: &Runtime::Hash,
 	) -> Result<SystemCall<Runtime>, TaskError<(), ()>> {
 		match self {
-			MockTask::Remark(nonce) => {
+			MockTask::Remark(nonce) | MockTask::Urgent(nonce) => {
 				Ok(frame_system::pallet::Call::remark_with_event { remark: nonce.encode() })
 			},
 			MockTask::Evaluation => Err(TaskError::Evaluation(())),
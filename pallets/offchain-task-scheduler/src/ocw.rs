@@ -2,15 +2,16 @@ pub(crate) mod nonce;
 
 use super::authorship::Authorship;
 use super::Error;
-use super::{log, Config, Pallet};
+use super::{log, Config, Pallet, RotatedFrom};
 use alloc::vec;
 use frame_support::dispatch::Vec;
+use frame_support::traits::Get;
 use frame_system::offchain::{Account, SendSignedTransaction, Signer};
 use frame_system::offchain::{AppCrypto, SigningTypes};
 use frame_system::Pallet as System;
-use nonce::lock_key;
-pub use nonce::nonce_key;
+pub use nonce::{lock_key, nonce_key, LOCK_DEADLINE};
 use sp_runtime::offchain::storage::StorageValueRef;
+use sp_runtime::offchain::{Duration, Timestamp};
 use sp_runtime::traits::IdentifyAccount;
 use sp_runtime::traits::One;
 use sp_runtime::traits::Saturating;
@@ -75,6 +76,13 @@ impl<T: Config> Pallet<T> {
 		let n = System::<T>::block_number();
 		log::trace!(target: "task", "@{n:?} Offnonce {synced_nonce:?} Onnonce {:?}", account_data.nonce);
 
+		if synced_nonce.is_none() {
+			if let Some(old) = RotatedFrom::<T>::get(auth_id) {
+				log::debug!(target: "task", "Dropping the nonce of rotated authority {old:?}");
+				StorageValueRef::persistent(&nonce_key(&old)).clear();
+			}
+		}
+
 		if let Some(nonce) = synced_nonce {
 			if nonce > account_data.nonce {
 				account_data.nonce = nonce;
@@ -87,4 +95,34 @@ impl<T: Config> Pallet<T> {
 	}
 }
 
+/// Limits the offchain work started in a single block, so that the most urgent tasks aren't
+/// held up by a flood of others.
+pub(crate) struct WorkBudget {
+	until: Timestamp,
+	requests_left: u32,
+}
+
+impl WorkBudget {
+	pub(crate) fn new<T: Config>() -> Self {
+		Self {
+			until: sp_io::offchain::timestamp()
+				.add(Duration::from_millis(T::OffchainTimeBudget::get())),
+			requests_left: T::OffchainRequestBudget::get(),
+		}
+	}
+
+	pub(crate) fn until(&self) -> Timestamp {
+		self.until
+	}
+
+	/// Spends `requests` if they fit in what is left and there is time left.
+	pub(crate) fn try_spend(&mut self, requests: u32) -> bool {
+		if sp_io::offchain::timestamp() >= self.until || requests > self.requests_left {
+			return false;
+		}
+		self.requests_left -= requests;
+		true
+	}
+}
+
 pub(crate) mod tests;
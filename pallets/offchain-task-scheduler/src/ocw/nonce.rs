@@ -6,9 +6,10 @@ use sp_runtime::offchain::Duration;
 
 const SYNCED_NONCE: &[u8] = b"creditcoin/OCW/nonce/nonce/";
 const SYNCED_NONCE_LOCK: &[u8] = b"creditcoin/OCW/nonce/lock/";
-const LOCK_DEADLINE: u64 = 50_000;
+/// How long, in milliseconds, a holder of the nonce lock may keep it.
+pub const LOCK_DEADLINE: u64 = 50_000;
 
-pub(super) fn lock_key<Id: Encode>(id: &Id) -> Vec<u8> {
+pub fn lock_key<Id: Encode>(id: &Id) -> Vec<u8> {
 	id.using_encoded(|encoded_id| SYNCED_NONCE_LOCK.iter().chain(encoded_id).copied().collect())
 }
 
@@ -156,7 +157,7 @@ mod tests {
 				let execute = || {
 					Trivial::<TaskScheduler, Runtime>::roll_to(1);
 					let call: RuntimeCall = MockTask::Remark(0)
-						.forward_task(TaskScheduler::deadline())
+						.forward_task(TaskScheduler::deadline(), None, &())
 						.expect("call")
 						.into();
 
@@ -4,19 +4,23 @@ use crate::{
 	mock::{
 		generate_authority,
 		runtime::{
-			AccountId, Extrinsic, Runtime, RuntimeCall, RuntimeOrigin, System, TaskScheduler,
+			AccountId, Extrinsic, OffchainRequestBudget, OffchainTimeBudget, Runtime, RuntimeCall,
+			RuntimeOrigin, System, TaskScheduler,
 		},
 	},
 	mocked_task::MockTask,
 	ocw::StorageValueRef,
-	tasks::{storage_key, task_lock, TaskScheduler as TaskSchedulerT, TaskV2},
-	Pallet,
+	tasks::{
+		attempts_key, storage_key, task_lock, TaskAttempts, TaskScheduler as TaskSchedulerT, TaskV2,
+	},
+	Pallet, TaskLifetime,
 };
 use assert_matches::assert_matches;
 use frame_support::assert_ok;
 use frame_support::dispatch::Dispatchable;
 use runtime_utils::{
-	pool::with_failing_submit_transaction, ExtBuilder, RollTo, Trivial, WithWorkerHook,
+	pool::{with_failing_submit_transaction, PoolState},
+	ExtBuilder, RollTo, Trivial, WithWorkerHook,
 };
 use sp_io::offchain::sleep_until;
 use sp_runtime::codec::{Decode, Encode};
@@ -26,6 +30,11 @@ use sp_runtime::traits::IdentifyAccount;
 
 type GuardDeadline = <BlockAndTime<System> as Lockable>::Deadline;
 
+/// The key of the lock on the task's current registration.
+fn lock_key(id: &<Runtime as frame_system::Config>::Hash) -> Vec<u8> {
+	storage_key(id, &TaskScheduler::task_lifetimes(id).map(|lifetime| lifetime.scheduled_at))
+}
+
 #[test]
 fn completed_oversubscribed_tasks_are_skipped() {
 	let mut ext_builder = ExtBuilder::default().with_keystore();
@@ -47,6 +56,7 @@ fn completed_oversubscribed_tasks_are_skipped() {
 
 		let deadline_2 = TaskScheduler::deadline();
 		TaskScheduler::insert(&deadline_2, &id, task);
+		let key = lock_key(&id);
 
 		WithWorkerHook::<TaskScheduler, Runtime>::roll_to(3);
 
@@ -69,7 +79,6 @@ fn completed_oversubscribed_tasks_are_skipped() {
 		//task expires without yielding txns.
 		assert!(pool.read().transactions.is_empty());
 
-		let key = storage_key(&id);
 		//lock set
 		assert!(StorageValueRef::persistent(key.as_ref())
 			.get::<GuardDeadline>()
@@ -127,16 +136,182 @@ fn evaluation_error_is_retried() {
 		// It failed Evaluation and remains scheduled.
 		assert!(TaskScheduler::is_scheduled(&deadline, &id));
 
-		let key = storage_key(&id);
+		let key = lock_key(&id);
 		assert!(StorageValueRef::persistent(key.as_ref())
 			.get::<GuardDeadline>()
 			.expect("decoded")
 			.is_none());
+
+		let attempts = StorageValueRef::persistent(attempts_key(&id).as_ref())
+			.get::<TaskAttempts<u64>>()
+			.expect("decoded")
+			.expect("recorded");
+		assert_eq!(attempts.attempts, 1);
+		assert_eq!(attempts.last_attempt, 2);
+		assert!(!attempts.last_error.is_empty());
+	});
+}
+
+#[test]
+fn pending_tasks_can_be_listed_and_found() {
+	let ext_builder = ExtBuilder::default().with_keystore();
+	ext_builder.build::<Runtime>().execute_with(|| {
+		Trivial::<TaskScheduler, Runtime>::roll_to(1);
+
+		let deadline = TaskScheduler::deadline();
+		let task = MockTask::Evaluation;
+		let id = TaskV2::<Runtime>::to_id(&task);
+		TaskScheduler::insert(&deadline, &id, task);
+
+		let lifetime = Some(TaskLifetime { scheduled_at: 1, deadline, reschedules: 0 });
+		let listed = TaskScheduler::pending_task_list();
+		assert_matches!(&listed[..], [(d, i, MockTask::Evaluation, l)] if *d == deadline && *i == id && *l == lifetime);
+		assert_matches!(
+			TaskScheduler::find_pending_task(&id),
+			Some((d, MockTask::Evaluation, l)) if d == deadline && Some(l) == lifetime
+		);
+		assert!(TaskScheduler::find_pending_task(&Default::default()).is_none());
+	});
+}
+
+#[test]
+fn pending_task_is_rescheduled_with_backoff_until_it_expires() {
+	let ext_builder = ExtBuilder::default().with_keystore();
+	ext_builder.build::<Runtime>().execute_with(|| {
+		Trivial::<TaskScheduler, Runtime>::roll_to(1);
+
+		let deadline = TaskScheduler::deadline();
+		let task = MockTask::Evaluation;
+		let id = TaskV2::<Runtime>::to_id(&task);
+		TaskScheduler::insert(&deadline, &id, task);
+
+		// timeout is 5 blocks, max lifetime 20
+		Trivial::<TaskScheduler, Runtime>::roll_to(deadline);
+		assert!(!TaskScheduler::is_scheduled(&deadline, &id));
+		assert!(TaskScheduler::is_scheduled(&11, &id));
+
+		Trivial::<TaskScheduler, Runtime>::roll_to(11);
+		// 11 + 10 is capped by the end of the task's lifetime
+		assert!(TaskScheduler::is_scheduled(&21, &id));
+		assert_eq!(
+			TaskScheduler::task_lifetimes(id),
+			Some(TaskLifetime { scheduled_at: 1, deadline: 21, reschedules: 2 })
+		);
+
+		Trivial::<TaskScheduler, Runtime>::roll_to(21);
+		assert!(!TaskScheduler::is_scheduled(&21, &id));
+		assert!(TaskScheduler::task_lifetimes(id).is_none());
+		System::assert_last_event(crate::Event::<Runtime>::TaskExpired(id, 1).into());
+	});
+}
+
+#[test]
+fn expired_tasks_over_the_block_limit_are_carried_over() {
+	let ext_builder = ExtBuilder::default().with_keystore();
+	ext_builder.build::<Runtime>().execute_with(|| {
+		Trivial::<TaskScheduler, Runtime>::roll_to(1);
+
+		let deadline = TaskScheduler::deadline();
+		let ids: Vec<_> = (0..5)
+			.map(|n| {
+				let task = MockTask::Remark(n);
+				let id = TaskV2::<Runtime>::to_id(&task);
+				TaskScheduler::insert(&deadline, &id, task);
+				id
+			})
+			.collect();
+
+		// at most 3 a block
+		Trivial::<TaskScheduler, Runtime>::roll_to(deadline);
+		let left = ids.iter().filter(|id| TaskScheduler::is_scheduled(&deadline, id)).count();
+		assert_eq!(left, 2);
+		assert_eq!(crate::ExpiryCursor::<Runtime>::get(), Some(deadline));
+
+		Trivial::<TaskScheduler, Runtime>::roll_to(deadline + 1);
+		assert!(ids.iter().all(|id| !TaskScheduler::is_scheduled(&deadline, id)));
+		assert_eq!(crate::ExpiryCursor::<Runtime>::get(), None);
+		// the late ones are rescheduled from when they were handled
+		let late: Vec<_> = ids.iter().filter(|id| TaskScheduler::is_scheduled(&12, id)).collect();
+		assert_eq!(late.len(), 2);
+		for id in late {
+			assert_eq!(
+				TaskScheduler::task_lifetimes(id),
+				Some(TaskLifetime { scheduled_at: 1, deadline: 12, reschedules: 1 })
+			);
+		}
+	});
+}
+
+#[test]
+fn scheduling_and_completing_a_task_emits_events() {
+	let ext_builder = ExtBuilder::default().with_keystore();
+	ext_builder.build::<Runtime>().execute_with(|| {
+		Trivial::<TaskScheduler, Runtime>::roll_to(1);
+
+		let deadline = TaskScheduler::deadline();
+		let task = MockTask::Remark(0);
+		let id = TaskV2::<Runtime>::to_id(&task);
+		TaskScheduler::insert(&deadline, &id, task);
+		System::assert_last_event(crate::Event::<Runtime>::TaskScheduled(id, deadline).into());
+
+		TaskScheduler::remove(&deadline, &id);
+		System::assert_last_event(crate::Event::<Runtime>::TaskCompleted(id).into());
+
+		// nothing left to complete
+		System::reset_events();
+		TaskScheduler::remove(&deadline, &id);
+		assert!(System::events().is_empty());
+	});
+}
+
+#[test]
+fn cancelled_tasks_are_not_completed() {
+	let ext_builder = ExtBuilder::default().with_keystore();
+	ext_builder.build::<Runtime>().execute_with(|| {
+		Trivial::<TaskScheduler, Runtime>::roll_to(1);
+
+		let deadline = TaskScheduler::deadline();
+		let task = MockTask::Evaluation;
+		let id = TaskV2::<Runtime>::to_id(&task);
+		TaskScheduler::insert(&deadline, &id, task.clone());
+
+		// rescheduled once, found at its new deadline
+		Trivial::<TaskScheduler, Runtime>::roll_to(deadline);
+		let (rescheduled, _) = TaskScheduler::find(&id).unwrap();
+		assert!(rescheduled > deadline);
+
+		assert_eq!(TaskScheduler::cancel(&id), Some(task));
+		System::assert_last_event(crate::Event::<Runtime>::TaskCancelled(id).into());
+		assert!(!TaskScheduler::is_scheduled(&rescheduled, &id));
+		assert!(TaskScheduler::task_lifetimes(id).is_none());
+		assert_eq!(TaskScheduler::task_kind_stats(0).completed, 0);
+
+		assert_eq!(TaskScheduler::find(&id), None);
+		assert_eq!(TaskScheduler::cancel(&id), None);
+	});
+}
+
+#[test]
+fn removing_a_rescheduled_task_uses_its_current_deadline() {
+	let ext_builder = ExtBuilder::default().with_keystore();
+	ext_builder.build::<Runtime>().execute_with(|| {
+		Trivial::<TaskScheduler, Runtime>::roll_to(1);
+
+		let deadline = TaskScheduler::deadline();
+		let task = MockTask::Evaluation;
+		let id = TaskV2::<Runtime>::to_id(&task);
+		TaskScheduler::insert(&deadline, &id, task);
+
+		Trivial::<TaskScheduler, Runtime>::roll_to(deadline);
+		TaskScheduler::remove(&deadline, &id);
+
+		assert!(!TaskScheduler::is_scheduled(&11, &id));
+		assert!(TaskScheduler::task_lifetimes(id).is_none());
 	});
 }
 
 #[test]
-fn forget_task_guard_when_task_has_been_persisted() {
+fn persisted_tasks_are_skipped_without_locking_them() {
 	let logs = traced_test::trace();
 	let mut ext_builder = ExtBuilder::default().with_keystore();
 	generate_authority(&mut ext_builder, 0);
@@ -151,13 +326,13 @@ fn forget_task_guard_when_task_has_been_persisted() {
 		TaskScheduler::insert(&deadline, &id, task.clone());
 
 		WithWorkerHook::<TaskScheduler, Runtime>::roll_to(2);
-		let key = crate::tasks::storage_key(&id);
-		let mut lock = crate::tasks::task_lock::<Runtime>(&key);
+		let key = lock_key(&id);
+		let mut lock = crate::tasks::task_lock::<Runtime>(&key, TaskScheduler::timeout_of(&task));
 		let lock_deadline = lock.try_lock().map(|_| ()).expect_err("deadline");
 		sleep_until(lock_deadline.timestamp.add(Duration::from_millis(1)));
 
 		let deadline = TaskScheduler::deadline();
-		TaskScheduler::insert(&deadline, &id, task);
+		TaskScheduler::insert(&deadline, &id, task.clone());
 
 		//fake a task being in storage.
 		crate::mocked_task::is_persisted_replace(true);
@@ -165,11 +340,11 @@ fn forget_task_guard_when_task_has_been_persisted() {
 
 		assert!(logs.contain("Already handled Task"));
 
-		let key = storage_key(&id);
-		let mut lock = task_lock::<Runtime>(&key);
+		let key = lock_key(&id);
+		let mut lock = task_lock::<Runtime>(&key, TaskScheduler::timeout_of(&task));
 
 		let guard = lock.try_lock();
-		assert!(guard.is_err());
+		assert!(guard.is_ok());
 
 		//revert thread_local
 		crate::mocked_task::is_persisted_replace(false);
@@ -210,7 +385,7 @@ fn effective_guard_lifetime_until_task_expiration() {
 		let deadline = TaskScheduler::deadline();
 		let task = MockTask::Remark(0);
 		let id = TaskV2::<Runtime>::to_id(&task);
-		TaskScheduler::insert(&deadline, &id, task);
+		TaskScheduler::insert(&deadline, &id, task.clone());
 
 		WithWorkerHook::<TaskScheduler, Runtime>::roll_to(2);
 
@@ -225,9 +400,9 @@ fn effective_guard_lifetime_until_task_expiration() {
 			})
 		);
 
-		let key = storage_key(&id);
+		let key = lock_key(&id);
 
-		let mut lock = crate::tasks::task_lock::<Runtime>(&key);
+		let mut lock = crate::tasks::task_lock::<Runtime>(&key, TaskScheduler::timeout_of(&task));
 		let lock_deadline = lock.try_lock().map(|_| ()).expect_err("deadline");
 
 		// The task is cleaned up at the deadline even though the lock is acquirable.
@@ -288,3 +463,112 @@ fn offchain_worker_should_log_when_authority_is_missing() {
 		assert!(logs.contain("Not an authority, skipping offchain work"));
 	});
 }
+
+fn submitted_remarks(pool: &PoolState) -> Vec<u32> {
+	pool.transactions
+		.iter()
+		.map(|tx| match Extrinsic::decode(&mut &**tx).unwrap().call {
+			RuntimeCall::System(frame_system::pallet::Call::remark_with_event { remark }) => {
+				u32::decode(&mut &*remark).unwrap()
+			},
+			call => panic!("unexpected call {call:?}"),
+		})
+		.collect()
+}
+
+#[test]
+fn urgent_and_earliest_tasks_are_worked_on_first() {
+	let mut ext_builder = ExtBuilder::default().with_keystore();
+	generate_authority(&mut ext_builder, 0);
+	ext_builder.with_offchain();
+	let pool = ext_builder.with_pool();
+	ext_builder.build::<Runtime>().execute_with(|| {
+		Trivial::<TaskScheduler, Runtime>::roll_to(1);
+		OffchainRequestBudget::set(&2);
+
+		let deadline = TaskScheduler::deadline();
+		for (deadline, task) in [
+			(deadline, MockTask::Remark(0)),
+			(deadline + 2, MockTask::Urgent(1)),
+			(deadline - 1, MockTask::Remark(2)),
+		] {
+			TaskScheduler::insert(&deadline, &TaskV2::<Runtime>::to_id(&task), task);
+		}
+
+		WithWorkerHook::<TaskScheduler, Runtime>::roll_to(2);
+
+		assert_eq!(submitted_remarks(&pool.read()), vec![1, 2]);
+	});
+}
+
+#[test]
+fn locked_tasks_cost_no_budget() {
+	let mut ext_builder = ExtBuilder::default().with_keystore();
+	generate_authority(&mut ext_builder, 0);
+	ext_builder.with_offchain();
+	let pool = ext_builder.with_pool();
+	ext_builder.build::<Runtime>().execute_with(|| {
+		Trivial::<TaskScheduler, Runtime>::roll_to(1);
+		OffchainRequestBudget::set(&1);
+
+		let deadline = TaskScheduler::deadline();
+		let locked = MockTask::Remark(0);
+		let locked_id = TaskV2::<Runtime>::to_id(&locked);
+		TaskScheduler::insert(&(deadline - 1), &locked_id, locked.clone());
+		let free = MockTask::Remark(1);
+		TaskScheduler::insert(&deadline, &TaskV2::<Runtime>::to_id(&free), free);
+
+		// an earlier run is still working on the first task
+		let key = lock_key(&locked_id);
+		let mut lock = task_lock::<Runtime>(&key, TaskScheduler::timeout_of(&locked));
+		lock.try_lock().expect("unlocked").forget();
+
+		WithWorkerHook::<TaskScheduler, Runtime>::roll_to(2);
+
+		assert_eq!(submitted_remarks(&pool.read()), vec![1]);
+	});
+}
+
+#[test]
+fn tasks_registered_again_after_a_cancellation_are_not_held_up_by_its_lock() {
+	let mut ext_builder = ExtBuilder::default().with_keystore();
+	generate_authority(&mut ext_builder, 0);
+	ext_builder.with_offchain();
+	let pool = ext_builder.with_pool();
+	ext_builder.build::<Runtime>().execute_with(|| {
+		Trivial::<TaskScheduler, Runtime>::roll_to(1);
+
+		let task = MockTask::Remark(0);
+		let id = TaskV2::<Runtime>::to_id(&task);
+		TaskScheduler::insert(&TaskScheduler::deadline(), &id, task.clone());
+		WithWorkerHook::<TaskScheduler, Runtime>::roll_to(2);
+		assert_eq!(submitted_remarks(&pool.read()), vec![0]);
+
+		TaskScheduler::cancel(&id);
+		TaskScheduler::insert(&TaskScheduler::deadline(), &id, task);
+		WithWorkerHook::<TaskScheduler, Runtime>::roll_to(3);
+
+		assert_eq!(submitted_remarks(&pool.read()), vec![0, 0]);
+	});
+}
+
+#[test]
+fn no_tasks_are_started_without_time_budget() {
+	let logs = traced_test::trace();
+	let mut ext_builder = ExtBuilder::default().with_keystore();
+	generate_authority(&mut ext_builder, 0);
+	ext_builder.with_offchain();
+	let pool = ext_builder.with_pool();
+	ext_builder.build::<Runtime>().execute_with(|| {
+		Trivial::<TaskScheduler, Runtime>::roll_to(1);
+		OffchainTimeBudget::set(&0);
+
+		let task = MockTask::Remark(0);
+		TaskScheduler::insert(&TaskScheduler::deadline(), &TaskV2::<Runtime>::to_id(&task), task);
+
+		WithWorkerHook::<TaskScheduler, Runtime>::roll_to(2);
+
+		assert!(pool.read().transactions.is_empty());
+		assert!(logs.contain("Out of budget"));
+	});
+}
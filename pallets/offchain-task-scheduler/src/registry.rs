@@ -0,0 +1,371 @@
+//! Pallets define their own kinds of tasks and the runtime collects them into the scheduler's
+//! task type with [`construct_tasks!`](crate::construct_tasks), so adding a kind doesn't touch
+//! the other pallets' tasks.
+
+use crate::tasks::ForwardTask;
+use crate::Config;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use core::any::Any;
+use core::fmt::Debug;
+use frame_support::pallet_prelude::{Decode, Encode, MaxEncodedLen, RuntimeDebug, TypeInfo};
+use frame_support::weights::Weight;
+
+/// Identifies a kind of task in the runtime. It is also the SCALE index of the kind's variant,
+/// so it can't change while tasks of the kind are pending.
+pub type TaskKindIndex = u8;
+
+/// A kind of task a pallet registers with [`construct_tasks!`](crate::construct_tasks).
+pub trait TaskKind<Runtime: Config>:
+	ForwardTask<Runtime, Call = Runtime::TaskCall> + Sized + 'static
+{
+	/// Reacts to a task of this kind expiring without being completed. Returns the weight
+	/// consumed.
+	fn on_expired(self, _id: &Runtime::Hash) -> Weight {
+		Weight::zero()
+	}
+}
+
+/// Tells which registered kind a task is.
+pub trait TaskRegistry {
+	fn kind(&self) -> TaskKindIndex;
+	/// The name of the kind's variant, e.g. `VerifyTransfer`.
+	fn kind_name(&self) -> &'static str;
+}
+
+/// How tasks of one kind fared in `on_initialize` and since they were scheduled.
+#[derive(
+	Clone, Copy, Default, PartialEq, Eq, RuntimeDebug, Encode, Decode, TypeInfo, MaxEncodedLen,
+)]
+pub struct KindStats {
+	pub scheduled: u32,
+	pub completed: u32,
+	pub rescheduled: u32,
+	pub expired: u32,
+	/// Weight consumed rescheduling and expiring tasks of the kind.
+	pub weight: Weight,
+}
+
+/// What each kind returned from [`ForwardTask::prefetch`].
+#[derive(Default)]
+pub struct Prefetched(BTreeMap<TaskKindIndex, Box<dyn Any>>);
+
+impl Prefetched {
+	pub fn insert<P: 'static>(&mut self, kind: TaskKindIndex, prefetched: P) {
+		self.0.insert(kind, Box::new(prefetched));
+	}
+
+	pub fn get<P: 'static>(&self, kind: TaskKindIndex) -> Option<&P> {
+		self.0.get(&kind)?.downcast_ref()
+	}
+}
+
+/// An error of a registered kind. Its `Debug` is the kind's error, which is what ends up in
+/// [`TaskAttempts`](crate::tasks::TaskAttempts).
+pub struct KindError {
+	pub kind: TaskKindIndex,
+	error: Box<dyn Debug>,
+}
+
+impl KindError {
+	pub fn new(kind: TaskKindIndex, error: impl Debug + 'static) -> Self {
+		Self { kind, error: Box::new(error) }
+	}
+}
+
+impl Debug for KindError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		self.error.fmt(f)
+	}
+}
+
+#[doc(hidden)]
+pub mod __private {
+	pub use crate::assignment::VrfAssignment;
+	pub use frame_support::weights::Weight;
+	pub use frame_support::{codec, scale_info, RuntimeDebug};
+	pub use frame_system::Config as SystemConfig;
+	pub use sp_core::offchain::Timestamp;
+	pub use sp_std::vec::Vec;
+}
+
+/// Declares the runtime's task type, an enum with a variant for each registered [`TaskKind`].
+/// The index of a variant is its [`TaskKindIndex`] and the types of the variants must be
+/// distinct. The enum is the scheduler's `Task` and `OnTaskExpired`, both dispatch to the kind
+/// of the task. Pallets get their tasks back out of it with `TryFrom`.
+///
+/// ```ignore
+/// pallet_offchain_task_scheduler::construct_tasks! {
+/// 	pub enum Task for Runtime {
+/// 		VerifyTransfer(pallet_creditcoin::UnverifiedTransferFor<Runtime>) = 0,
+/// 		VerifyContractOwnership(pallet_creditcoin::UnverifiedContractOwnershipFor<Runtime>) = 1,
+/// 	}
+/// }
+/// ```
+#[macro_export]
+macro_rules! construct_tasks {
+	(
+		$(#[$attr:meta])*
+		$vis:vis enum $name:ident for $runtime:ty {
+			$($kind:ident($task:ty) = $index:literal),+ $(,)?
+		}
+	) => {
+		$(#[$attr])*
+		#[derive(
+			Clone,
+			PartialEq,
+			Eq,
+			$crate::registry::__private::codec::Encode,
+			$crate::registry::__private::codec::Decode,
+			$crate::registry::__private::codec::MaxEncodedLen,
+			$crate::registry::__private::scale_info::TypeInfo,
+			$crate::registry::__private::RuntimeDebug,
+		)]
+		#[codec(crate = $crate::registry::__private::codec)]
+		#[scale_info(crate = $crate::registry::__private::scale_info)]
+		$vis enum $name {
+			$(
+				#[codec(index = $index)]
+				$kind($task),
+			)+
+		}
+
+		$(
+			impl From<$task> for $name {
+				fn from(task: $task) -> Self {
+					Self::$kind(task)
+				}
+			}
+
+			impl TryFrom<$name> for $task {
+				type Error = $name;
+
+				fn try_from(task: $name) -> Result<Self, $name> {
+					match task {
+						$name::$kind(task) => Ok(task),
+						#[allow(unreachable_patterns)]
+						task => Err(task),
+					}
+				}
+			}
+		)+
+
+		impl $crate::registry::TaskRegistry for $name {
+			fn kind(&self) -> $crate::registry::TaskKindIndex {
+				match self {
+					$(Self::$kind(_) => $index,)+
+				}
+			}
+
+			fn kind_name(&self) -> &'static str {
+				match self {
+					$(Self::$kind(_) => stringify!($kind),)+
+				}
+			}
+		}
+
+		impl $crate::tasks::ForwardTask<$runtime> for $name {
+			type Call = <$runtime as $crate::Config>::TaskCall;
+			type EvaluationError = $crate::registry::KindError;
+			type SchedulerError = $crate::registry::KindError;
+			type Prefetched = $crate::registry::Prefetched;
+
+			fn priority(&self) -> $crate::tasks::TaskPriority {
+				match self {
+					$(Self::$kind(task) => $crate::tasks::ForwardTask::<$runtime>::priority(task),)+
+				}
+			}
+
+			fn timeout(
+				&self,
+			) -> Option<<$runtime as $crate::registry::__private::SystemConfig>::BlockNumber> {
+				match self {
+					$(Self::$kind(task) => $crate::tasks::ForwardTask::<$runtime>::timeout(task),)+
+				}
+			}
+
+			fn http_requests(&self) -> u32 {
+				match self {
+					$(
+						Self::$kind(task) => {
+							$crate::tasks::ForwardTask::<$runtime>::http_requests(task)
+						},
+					)+
+				}
+			}
+
+			fn set_deadline(
+				&mut self,
+				deadline: <$runtime as $crate::registry::__private::SystemConfig>::BlockNumber,
+			) {
+				match self {
+					$(
+						Self::$kind(task) => {
+							$crate::tasks::ForwardTask::<$runtime>::set_deadline(task, deadline)
+						},
+					)+
+				}
+			}
+
+			fn is_persisted(
+				&self,
+				id: &<$runtime as $crate::registry::__private::SystemConfig>::Hash,
+			) -> bool {
+				match self {
+					$(
+						Self::$kind(task) => {
+							$crate::tasks::ForwardTask::<$runtime>::is_persisted(task, id)
+						},
+					)+
+				}
+			}
+
+			fn prefetch(
+				tasks: &[(&<$runtime as $crate::registry::__private::SystemConfig>::Hash, &Self)],
+				until: $crate::registry::__private::Timestamp,
+			) -> Self::Prefetched {
+				let mut prefetched = $crate::registry::Prefetched::default();
+				$(
+					let of_kind: $crate::registry::__private::Vec<_> = tasks
+						.iter()
+						.filter_map(|&(id, task)| match task {
+							Self::$kind(task) => Some((id, task)),
+							#[allow(unreachable_patterns)]
+							_ => None,
+						})
+						.collect();
+					if !of_kind.is_empty() {
+						prefetched.insert(
+							$index,
+							<$task as $crate::tasks::ForwardTask<$runtime>>::prefetch(
+								&of_kind, until,
+							),
+						);
+					}
+				)+
+				prefetched
+			}
+
+			fn forward_task(
+				&self,
+				deadline: <$runtime as $crate::registry::__private::SystemConfig>::BlockNumber,
+				assignment: Option<$crate::registry::__private::VrfAssignment>,
+				prefetched: &Self::Prefetched,
+			) -> Result<
+				Self::Call,
+				$crate::tasks::error::TaskError<
+					$crate::registry::KindError,
+					$crate::registry::KindError,
+				>,
+			> {
+				match self {
+					$(
+						Self::$kind(task) => {
+							let nothing: <$task as $crate::tasks::ForwardTask<$runtime>>::Prefetched =
+								Default::default();
+							let prefetched = prefetched.get($index).unwrap_or(&nothing);
+							$crate::tasks::ForwardTask::<$runtime>::forward_task(
+								task, deadline, assignment, prefetched,
+							)
+							.map_err(|error| {
+								error.map(
+									|e| $crate::registry::KindError::new($index, e),
+									|e| $crate::registry::KindError::new($index, e),
+								)
+							})
+						},
+					)+
+				}
+			}
+		}
+
+		impl
+			$crate::tasks::OnTaskExpired<
+				<$runtime as $crate::registry::__private::SystemConfig>::Hash,
+				$name,
+			> for $name
+		{
+			fn on_task_expired(
+				id: &<$runtime as $crate::registry::__private::SystemConfig>::Hash,
+				task: $name,
+			) -> $crate::registry::__private::Weight {
+				match task {
+					$(
+						$name::$kind(task) => {
+							$crate::registry::TaskKind::<$runtime>::on_expired(task, id)
+						},
+					)+
+				}
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{KindStats, TaskRegistry};
+	use crate::mock::runtime::{Runtime, RuntimeCall, TaskScheduler};
+	use crate::mocked_task::MockTask;
+	use crate::tasks::{error::TaskError, ForwardTask, OnTaskExpired, TaskScheduler as _, TaskV2};
+	use frame_support::weights::Weight;
+	use parity_scale_codec::{Decode, Encode};
+	use runtime_utils::{ExtBuilder, RollTo, Trivial};
+
+	crate::construct_tasks! {
+		pub enum Tasks for Runtime {
+			Remark(MockTask<u32>) = 0,
+			Wide(MockTask<u64>) = 3,
+		}
+	}
+
+	#[test]
+	fn variants_are_encoded_at_their_kind_index() {
+		let task = Tasks::from(MockTask::Remark(7u64));
+		assert_eq!(task.kind(), 3);
+		assert_eq!(task.encode()[0], 3);
+		assert_eq!(task.encode()[1..], MockTask::Remark(7u64).encode());
+		assert_eq!(Tasks::decode(&mut &*task.encode()).unwrap(), task);
+
+		assert_eq!(Tasks::from(MockTask::<u32>::Evaluation).kind(), 0);
+		assert_eq!(MockTask::<u64>::try_from(task), Ok(MockTask::Remark(7u64)));
+		assert!(MockTask::<u64>::try_from(Tasks::from(MockTask::<u32>::Evaluation)).is_err());
+	}
+
+	#[test]
+	fn tasks_are_forwarded_by_their_kind() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			let task = Tasks::from(MockTask::Remark(1u32));
+			let call = task.forward_task(1, None, &Default::default()).unwrap();
+			assert!(matches!(call, RuntimeCall::System(_)));
+
+			let error = Tasks::from(MockTask::<u64>::Scheduler)
+				.forward_task(1, None, &Default::default())
+				.unwrap_err();
+			assert!(matches!(error, TaskError::Scheduler(ref e) if e.kind == 3));
+			assert_eq!(Tasks::on_task_expired(&Default::default(), task), Weight::zero());
+		});
+	}
+
+	#[test]
+	fn stats_are_kept_per_kind() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			Trivial::<TaskScheduler, Runtime>::roll_to(1);
+
+			let completed = MockTask::Remark(0);
+			let completed_id = TaskV2::<Runtime>::to_id(&completed);
+			TaskScheduler::insert(&TaskScheduler::deadline(), &completed_id, completed);
+			let expiring = MockTask::Evaluation;
+			let expiring_id = TaskV2::<Runtime>::to_id(&expiring);
+			TaskScheduler::insert(&TaskScheduler::deadline(), &expiring_id, expiring);
+
+			TaskScheduler::remove(&TaskScheduler::deadline(), &completed_id);
+			// timeout is 5 blocks, max lifetime 20
+			Trivial::<TaskScheduler, Runtime>::roll_to(21);
+
+			let stats = TaskScheduler::task_kind_stats(0);
+			assert_eq!((stats.scheduled, stats.completed), (2, 1));
+			assert_eq!((stats.rescheduled, stats.expired), (2, 1));
+			assert_eq!(TaskScheduler::task_kind_stats(1), KindStats::default());
+		});
+	}
+}
@@ -0,0 +1,149 @@
+use super::crypto::Public as AuthorityKey;
+use super::pallet::{Authorities, InheritedKeys, Pallet, SessionAuthorities, SuspendedAuthorities};
+use super::Config;
+use frame_support::traits::OneSessionHandler;
+use sp_core::sr25519;
+use sp_runtime::traits::IdentifyAccount;
+use sp_runtime::BoundToRuntimeAppPublic;
+use sp_std::collections::btree_set::BTreeSet;
+use sp_std::vec::Vec;
+
+impl<T: Config> Pallet<T>
+where
+	T::Public: From<sr25519::Public>,
+{
+	/// Makes the validators with a `gots` key of their own the session-derived authorities.
+	/// Authorities added by root are left alone and suspended ones stay suspended.
+	fn sync_authorities<'a>(validators: impl Iterator<Item = (&'a T::AccountId, AuthorityKey)>)
+	where
+		T::AccountId: 'a,
+	{
+		// authorities sign with their `gots` key, their account id is the public key
+		let registered: BTreeSet<T::AccountId> = validators
+			.filter(|&(validator, ref key)| match InheritedKeys::<T>::get(validator) {
+				Some(inherited) if inherited == *key => false,
+				Some(_) => {
+					InheritedKeys::<T>::remove(validator);
+					true
+				},
+				None => true,
+			})
+			.map(|(_, key)| T::Public::from(sr25519::Public::from(key)).into_account())
+			.collect();
+
+		let retired: Vec<_> = SessionAuthorities::<T>::iter_keys()
+			.filter(|a| !registered.contains(a))
+			.collect();
+		for authority in retired {
+			SessionAuthorities::<T>::remove(&authority);
+			Authorities::<T>::remove(&authority);
+		}
+
+		for authority in registered {
+			if !Authorities::<T>::contains_key(&authority)
+				&& !SuspendedAuthorities::<T>::contains_key(&authority)
+			{
+				Authorities::<T>::insert(&authority, ());
+				SessionAuthorities::<T>::insert(&authority, ());
+			}
+		}
+	}
+}
+
+impl<T: Config> BoundToRuntimeAppPublic for Pallet<T> {
+	type Public = AuthorityKey;
+}
+
+impl<T: Config> OneSessionHandler<T::AccountId> for Pallet<T>
+where
+	T::Public: From<sr25519::Public>,
+{
+	type Key = AuthorityKey;
+
+	fn on_genesis_session<'a, I: 'a>(validators: I)
+	where
+		I: Iterator<Item = (&'a T::AccountId, AuthorityKey)>,
+		T::AccountId: 'a,
+	{
+		Self::sync_authorities(validators);
+	}
+
+	fn on_new_session<'a, I: 'a>(changed: bool, validators: I, _queued_validators: I)
+	where
+		I: Iterator<Item = (&'a T::AccountId, AuthorityKey)>,
+		T::AccountId: 'a,
+	{
+		if changed {
+			Self::sync_authorities(validators);
+		}
+	}
+
+	fn on_disabled(_validator_index: u32) {}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::AuthorityKey;
+	use crate::authority::AuthorityController;
+	use crate::mock::runtime::{AccountId, Runtime, TaskScheduler};
+	use crate::{Authorities, InheritedKeys};
+	use frame_support::traits::OneSessionHandler;
+	use runtime_utils::ExtBuilder;
+	use sp_core::sr25519;
+
+	fn key(seed: u8) -> AuthorityKey {
+		sr25519::Public::from_raw([seed; 32]).into()
+	}
+
+	fn account(key: &AuthorityKey) -> AccountId {
+		AccountId::new(sr25519::Public::from(key.clone()).0)
+	}
+
+	fn new_session(validators: &[(AccountId, AuthorityKey)]) {
+		let keys = || validators.iter().map(|(v, k)| (v, k.clone()));
+		<TaskScheduler as OneSessionHandler<AccountId>>::on_new_session(true, keys(), keys());
+	}
+
+	#[test]
+	fn validators_with_a_key_become_authorities() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			let registered = (AccountId::new([1; 32]), key(1));
+			let unregistered = (AccountId::new([2; 32]), key(2));
+			InheritedKeys::<Runtime>::insert(&unregistered.0, &unregistered.1);
+
+			new_session(&[registered.clone(), unregistered.clone()]);
+
+			assert!(TaskScheduler::is_authority(&account(&registered.1)));
+			assert!(!TaskScheduler::is_authority(&account(&unregistered.1)));
+			assert_eq!(Authorities::<Runtime>::iter_keys().count(), 1);
+
+			// until it sets a key of its own
+			let rotated = (unregistered.0.clone(), key(3));
+			new_session(&[registered.clone(), rotated.clone()]);
+			assert!(TaskScheduler::is_authority(&account(&rotated.1)));
+			assert!(InheritedKeys::<Runtime>::get(&rotated.0).is_none());
+		});
+	}
+
+	#[test]
+	fn authorities_follow_the_validator_set() {
+		ExtBuilder::default().build::<Runtime>().execute_with(|| {
+			let root_added = AccountId::new([9; 32]);
+			TaskScheduler::insert_authority(&root_added);
+			let a = (AccountId::new([1; 32]), key(1));
+			let b = (AccountId::new([2; 32]), key(2));
+
+			new_session(&[a.clone(), b.clone()]);
+			assert!(TaskScheduler::is_authority(&account(&a.1)));
+			assert!(TaskScheduler::is_authority(&account(&b.1)));
+
+			// `a` left the validator set and `b` rotated its key
+			let rotated = (b.0.clone(), key(3));
+			new_session(&[rotated.clone()]);
+			assert!(!TaskScheduler::is_authority(&account(&a.1)));
+			assert!(!TaskScheduler::is_authority(&account(&b.1)));
+			assert!(TaskScheduler::is_authority(&account(&rotated.1)));
+			assert!(TaskScheduler::is_authority(&root_added));
+		});
+	}
+}
@@ -0,0 +1,340 @@
+use super::pallet::{Call, Error, PendingTasks, SuspendedAuthorities, TaskVotes};
+use super::{log, Config, Pallet};
+use crate::assignment::{TaskAssignment, VrfAssignment};
+use crate::authority::AuthorityController;
+use crate::incentives::AuthorityIncentives;
+use alloc::vec;
+use frame_support::pallet_prelude::{Decode, Encode, RuntimeDebug, TypeInfo};
+use frame_support::traits::Get;
+use frame_system::offchain::{SendUnsignedTransaction, SignedPayload, Signer, SigningTypes};
+use parity_scale_codec::Output;
+use sp_runtime::traits::{IdentifyAccount, Saturating, UniqueSaturatedInto, Zero};
+use sp_runtime::transaction_validity::{InvalidTransaction, TransactionValidity, ValidTransaction};
+
+/// How authorities submit the calls completing their tasks.
+#[derive(Clone, Copy, PartialEq, Eq, Default, RuntimeDebug, Encode, Decode)]
+pub enum SubmissionMode {
+	/// Signed by the authority's account, which needs the offchain nonce tracking.
+	#[default]
+	Signed,
+	/// Unsigned, with the call in a [`TaskSubmission`] signed by the authority's `gots` key.
+	/// The pool deduplicates them by task and authority, there are no nonces involved.
+	Unsigned,
+}
+
+/// A task output sent in an unsigned transaction. The deadline and id of the task it completes
+/// are read from the output, so the signature covers them too.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug, Encode, Decode, TypeInfo)]
+pub struct TaskSubmission<Public, Call> {
+	/// The authority's `gots` key, the call is dispatched from its account.
+	pub public: Public,
+	pub output: Call,
+}
+
+pub type TaskSubmissionFor<T> =
+	TaskSubmission<<T as SigningTypes>::Public, <T as Config>::TaskCall>;
+
+/// Separates the signatures over task submissions from anything else signed with a `gots` key.
+/// The suffix is the version of the payload and must be bumped whenever its layout changes.
+pub const SUBMISSION_SIGNING_DOMAIN: &[u8] = b"offchain-task-scheduler/task-submission/v1";
+
+/// What an authority signs for a [`TaskSubmission`]: it's encoded as
+/// `(SUBMISSION_SIGNING_DOMAIN, genesis_hash, submission)`, so the signature can't be replayed
+/// as another payload or on another chain.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct SubmissionPayload<Hash, Public, Call> {
+	pub genesis_hash: Hash,
+	pub submission: TaskSubmission<Public, Call>,
+}
+
+impl<Hash: Encode, Public: Encode, Call: Encode> Encode for SubmissionPayload<Hash, Public, Call> {
+	fn encode_to<O: Output + ?Sized>(&self, dest: &mut O) {
+		(SUBMISSION_SIGNING_DOMAIN, &self.genesis_hash, &self.submission).encode_to(dest)
+	}
+}
+
+pub type SubmissionPayloadFor<T> = SubmissionPayload<
+	<T as frame_system::Config>::Hash,
+	<T as SigningTypes>::Public,
+	<T as Config>::TaskCall,
+>;
+
+/// The task a task output completes.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct CompletedTask<BlockNumber, Hash> {
+	pub deadline: BlockNumber,
+	pub task_id: Hash,
+	/// The authority's proof that it was selected for the task, if the output carries one.
+	pub assignment: Option<VrfAssignment>,
+}
+
+/// The calls authorities may submit unsigned.
+pub trait TaskOutputs<Call, BlockNumber, Hash> {
+	/// The task `call` completes, `None` if it isn't a task output that may be sent unsigned.
+	fn task_of(call: &Call) -> Option<CompletedTask<BlockNumber, Hash>>;
+}
+
+impl<T: Config> SignedPayload<T> for SubmissionPayloadFor<T> {
+	fn public(&self) -> T::Public {
+		self.submission.public.clone()
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// The payload signed for `submission` on this chain.
+	pub fn submission_payload(submission: TaskSubmissionFor<T>) -> SubmissionPayloadFor<T> {
+		let genesis_hash = frame_system::Pallet::<T>::block_hash(T::BlockNumber::zero());
+		SubmissionPayload { genesis_hash, submission }
+	}
+
+	/// Sends `output` unsigned, signed by the local authority `pubkey` instead.
+	pub fn offchain_unsigned_tx(pubkey: T::Public, output: T::TaskCall) -> Result<(), Error<T>> {
+		let signer = Signer::<T, T::AuthorityId>::any_account().with_filter(vec![pubkey]);
+		let result = signer.send_unsigned_transaction(
+			|account| {
+				Self::submission_payload(TaskSubmission {
+					public: account.public.clone(),
+					output: output.clone(),
+				})
+			},
+			|payload, signature| {
+				Call::submit_task_output { submission: payload.submission, signature }.into()
+			},
+		);
+
+		match result {
+			Some((_, Ok(()))) => Ok(()),
+			Some((acc, Err(()))) => {
+				log::error!("failure: offchain_unsigned_tx: tx sent: {:?}", acc.id);
+				Err(Error::OffchainUnsignedTxFailed)
+			},
+			None => {
+				log::error!("No local account available");
+				Err(Error::NoLocalAcctForSignedTx)
+			},
+		}
+	}
+
+	/// Accepts a submission of a bonded authority that hasn't voted on the pending task its
+	/// output completes yet and may work on it, the checks the output's call makes for signed
+	/// submissions. It is valid until the task's deadline.
+	pub(crate) fn validate_submission(
+		submission: &TaskSubmissionFor<T>,
+		signature: &T::Signature,
+	) -> TransactionValidity {
+		if T::SubmissionMode::get() != SubmissionMode::Unsigned {
+			return InvalidTransaction::Call.into();
+		}
+		let Some(CompletedTask { deadline, task_id, assignment }) =
+			T::TaskOutputs::task_of(&submission.output)
+		else {
+			return InvalidTransaction::Call.into();
+		};
+		let payload = Self::submission_payload(submission.clone());
+		if !SignedPayload::<T>::verify::<T::AuthorityId>(&payload, signature.clone()) {
+			return InvalidTransaction::BadProof.into();
+		}
+
+		let authority = submission.public.clone().into_account();
+		if !Self::is_authority(&authority)
+			|| SuspendedAuthorities::<T>::contains_key(&authority)
+			|| !Self::is_bonded(&authority)
+		{
+			return InvalidTransaction::BadSigner.into();
+		}
+
+		let now = frame_system::Pallet::<T>::block_number();
+		// an assignment only holds for the task's current and previous round, it goes stale
+		// like the task does
+		if deadline <= now
+			|| !PendingTasks::<T>::contains_key(deadline, task_id)
+			|| TaskVotes::<T>::contains_key(task_id, &authority)
+			|| !Self::is_assigned(&task_id, &authority, assignment.as_ref())
+		{
+			return InvalidTransaction::Stale.into();
+		}
+
+		ValidTransaction::with_tag_prefix("TaskScheduler")
+			.priority(T::UnsignedPriority::get())
+			.and_provides((task_id, authority))
+			.longevity(deadline.saturating_sub(now).unique_saturated_into())
+			.propagate(true)
+			.build()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{SubmissionMode, TaskSubmission, TaskSubmissionFor};
+	use crate::mock::generate_authority;
+	use crate::mock::runtime::{
+		AuthorityBond, Extrinsic, Runtime, RuntimeCall, Submission, TaskSampleSize, TaskScheduler,
+	};
+	use crate::mocked_task::MockTask;
+	use crate::tasks::{TaskScheduler as _, TaskV2};
+	use crate::SuspendedAuthorities;
+	use assert_matches::assert_matches;
+	use frame_support::pallet_prelude::{InvalidTransaction, TransactionSource};
+	use frame_support::unsigned::ValidateUnsigned;
+	use frame_support::{assert_ok, dispatch::Dispatchable};
+	use frame_system::RawOrigin;
+	use parity_scale_codec::{Decode, Encode};
+	use runtime_utils::{ExtBuilder, RollTo, Trivial, WithWorkerHook};
+	use sp_runtime::traits::IdentifyAccount;
+	use sp_runtime::{MultiSignature, MultiSigner, Perquintill};
+
+	fn submission_of(tx: &[u8]) -> crate::Call<Runtime> {
+		match Extrinsic::decode(&mut &*tx).unwrap().call {
+			RuntimeCall::TaskScheduler(call) => call,
+			call => panic!("unexpected call {call:?}"),
+		}
+	}
+
+	#[test]
+	fn task_output_is_submitted_unsigned_and_dispatched_from_the_authority() {
+		let mut ext_builder = ExtBuilder::default().with_keystore();
+		let pubkey = generate_authority(&mut ext_builder, 0);
+		ext_builder.with_offchain();
+		let pool = ext_builder.with_pool();
+		ext_builder.build::<Runtime>().execute_with(|| {
+			Trivial::<TaskScheduler, Runtime>::roll_to(1);
+			Submission::set(&SubmissionMode::Unsigned);
+
+			let deadline = TaskScheduler::deadline();
+			let task = MockTask::Remark(0);
+			let id = TaskV2::<Runtime>::to_id(&task);
+			TaskScheduler::insert(&deadline, &id, task);
+
+			WithWorkerHook::<TaskScheduler, Runtime>::roll_to(2);
+
+			let tx = pool.write().transactions.pop().expect("a submission");
+			assert_eq!(Extrinsic::decode(&mut &*tx).unwrap().signature, None);
+			let call = submission_of(&tx);
+			assert_matches!(&call, crate::Call::submit_task_output { submission, .. } => {
+				assert_eq!(
+					submission.output,
+					RuntimeCall::System(frame_system::Call::remark_with_event {
+						remark: 0u32.encode()
+					})
+				);
+			});
+
+			let valid = TaskScheduler::validate_unsigned(TransactionSource::Local, &call).unwrap();
+			let authority = MultiSigner::from(pubkey).into_account();
+			assert_eq!(valid.provides, vec![("TaskScheduler", (id, authority)).encode()]);
+			assert_eq!(valid.longevity, deadline - 2);
+
+			assert_ok!(RuntimeCall::TaskScheduler(call).dispatch(RawOrigin::None.into()));
+		});
+	}
+
+	fn signed_submission(
+		pubkey: &sp_core::sr25519::Public,
+		nonce: u32,
+	) -> (TaskSubmissionFor<Runtime>, MultiSignature) {
+		let submission = TaskSubmission {
+			public: (*pubkey).into(),
+			output: RuntimeCall::System(frame_system::Call::remark_with_event {
+				remark: nonce.encode(),
+			}),
+		};
+		let payload = TaskScheduler::submission_payload(submission.clone()).encode();
+		let signature: MultiSignature =
+			sp_io::crypto::sr25519_sign(crate::KEY_TYPE, pubkey, &payload).unwrap().into();
+		(submission, signature)
+	}
+
+	#[test]
+	fn submissions_are_only_valid_in_unsigned_mode_and_with_the_authority_signature() {
+		let mut ext_builder = ExtBuilder::default().with_keystore();
+		let pubkey = generate_authority(&mut ext_builder, 0);
+		ext_builder.build::<Runtime>().execute_with(|| {
+			Trivial::<TaskScheduler, Runtime>::roll_to(1);
+
+			let deadline = TaskScheduler::deadline();
+			for nonce in [0, 1] {
+				let task = MockTask::Remark(nonce);
+				TaskScheduler::insert(&deadline, &TaskV2::<Runtime>::to_id(&task), task);
+			}
+			let (submission, signature) = signed_submission(&pubkey, 0);
+			let call = crate::Call::submit_task_output { submission, signature: signature.clone() };
+
+			assert_eq!(
+				TaskScheduler::validate_unsigned(TransactionSource::External, &call),
+				InvalidTransaction::Call.into()
+			);
+
+			Submission::set(&SubmissionMode::Unsigned);
+			assert_ok!(TaskScheduler::validate_unsigned(TransactionSource::External, &call));
+
+			// the signature covers the output and with it the task it completes
+			let (submission, _) = signed_submission(&pubkey, 1);
+			let forged = crate::Call::submit_task_output { submission, signature };
+			assert_eq!(
+				TaskScheduler::validate_unsigned(TransactionSource::External, &forged),
+				InvalidTransaction::BadProof.into()
+			);
+
+			// and is made over the domain-separated payload, not the bare submission
+			let (submission, _) = signed_submission(&pubkey, 0);
+			let signature: MultiSignature =
+				sp_io::crypto::sr25519_sign(crate::KEY_TYPE, &pubkey, &submission.encode())
+					.unwrap()
+					.into();
+			let undomained = crate::Call::submit_task_output { submission, signature };
+			assert_eq!(
+				TaskScheduler::validate_unsigned(TransactionSource::External, &undomained),
+				InvalidTransaction::BadProof.into()
+			);
+
+			TaskScheduler::remove(&deadline, &TaskV2::<Runtime>::to_id(&MockTask::Remark(0u32)));
+			assert_eq!(
+				TaskScheduler::validate_unsigned(TransactionSource::External, &call),
+				InvalidTransaction::Call.into()
+			);
+		});
+	}
+
+	#[test]
+	fn submissions_need_a_bonded_unsuspended_and_assigned_authority() {
+		let mut ext_builder = ExtBuilder::default().with_keystore();
+		let pubkey = generate_authority(&mut ext_builder, 0);
+		ext_builder.build::<Runtime>().execute_with(|| {
+			Trivial::<TaskScheduler, Runtime>::roll_to(1);
+			Submission::set(&SubmissionMode::Unsigned);
+
+			let task = MockTask::Remark(0);
+			TaskScheduler::insert(
+				&TaskScheduler::deadline(),
+				&TaskV2::<Runtime>::to_id(&task),
+				task,
+			);
+			let (submission, signature) = signed_submission(&pubkey, 0);
+			let call = crate::Call::submit_task_output { submission, signature };
+			let authority = MultiSigner::from(pubkey).into_account();
+			assert_ok!(TaskScheduler::validate_unsigned(TransactionSource::External, &call));
+
+			AuthorityBond::set(&1);
+			assert_eq!(
+				TaskScheduler::validate_unsigned(TransactionSource::External, &call),
+				InvalidTransaction::BadSigner.into()
+			);
+			AuthorityBond::set(&0);
+
+			SuspendedAuthorities::<Runtime>::insert(&authority, 1);
+			assert_eq!(
+				TaskScheduler::validate_unsigned(TransactionSource::External, &call),
+				InvalidTransaction::BadSigner.into()
+			);
+			SuspendedAuthorities::<Runtime>::remove(&authority);
+
+			// the mock's outputs carry no assignment, once the selection is restricted they
+			// aren't accepted anymore
+			TaskSampleSize::set(&Perquintill::from_percent(50));
+			assert_eq!(
+				TaskScheduler::validate_unsigned(TransactionSource::External, &call),
+				InvalidTransaction::Stale.into()
+			);
+		});
+	}
+}
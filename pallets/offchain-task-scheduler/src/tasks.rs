@@ -2,33 +2,80 @@ pub mod error;
 pub mod macros;
 
 pub use super::pallet::Config;
+use crate::assignment::VrfAssignment;
 use crate::SystemConfig;
 use alloc::fmt::Debug;
 use error::TaskError;
 use frame_support::dispatch::Vec;
-use frame_support::traits::Get;
-use sp_core::offchain::Duration;
-use sp_core::Encode;
+use frame_support::weights::Weight;
+use sp_core::offchain::{Duration, Timestamp};
+use sp_core::{Decode, Encode};
+use sp_runtime::offchain::storage::StorageValueRef;
 use sp_runtime::offchain::storage_lock::BlockAndTime;
 use sp_runtime::offchain::storage_lock::StorageLock;
 use sp_runtime::traits::BlockNumberProvider;
 use sp_runtime::SaturatedConversion;
 
+/// Offchain storage key of the lock an authority holds while it works on a task. The lock is
+/// on the task's registration, the block it was `scheduled_at`, so a task cancelled and
+/// registered again under the same id isn't held up by the locks taken on the cancelled one.
 #[inline]
-pub(crate) fn storage_key<Id: Encode>(id: &Id) -> Vec<u8> {
+pub fn storage_key<Id: Encode, BlockNumber: Encode>(
+	id: &Id,
+	scheduled_at: &Option<BlockNumber>,
+) -> Vec<u8> {
 	const TASK_GUARD: &[u8] = b"task-scheduler/task/guard/";
-	id.using_encoded(|encoded_id| TASK_GUARD.iter().chain(encoded_id).copied().collect())
+	(id, scheduled_at)
+		.using_encoded(|encoded_id| TASK_GUARD.iter().chain(encoded_id).copied().collect())
+}
+
+/// Offchain storage key of the [`TaskAttempts`] of a task.
+#[inline]
+pub fn attempts_key<Id: Encode>(id: &Id) -> Vec<u8> {
+	const TASK_ATTEMPTS: &[u8] = b"task-scheduler/task/attempts/";
+	id.using_encoded(|encoded_id| TASK_ATTEMPTS.iter().chain(encoded_id).copied().collect())
+}
+
+/// The local authority's record of its failed attempts at a task. It is only kept for
+/// diagnostics and doesn't affect scheduling.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Encode, Decode)]
+pub struct TaskAttempts<BlockNumber> {
+	pub attempts: u32,
+	pub last_attempt: BlockNumber,
+	/// `Debug` representation of the last error.
+	pub last_error: Vec<u8>,
+}
+
+pub(crate) fn record_failed_attempt<Id: Encode, BlockNumber: Encode + Decode + Default>(
+	id: &Id,
+	block_number: BlockNumber,
+	error: impl Debug,
+) {
+	let storage = StorageValueRef::persistent(&attempts_key(id));
+	let previous: TaskAttempts<BlockNumber> = storage.get().ok().flatten().unwrap_or_default();
+	storage.set(&TaskAttempts {
+		attempts: previous.attempts.saturating_add(1),
+		last_attempt: block_number,
+		last_error: alloc::format!("{error:?}").into_bytes(),
+	});
+}
+
+pub(crate) fn clear_attempts<Id: Encode>(id: &Id) {
+	StorageValueRef::persistent(&attempts_key(id)).clear();
 }
 
 type Lock<'a, BlockNumberProvider> = StorageLock<'a, BlockAndTime<BlockNumberProvider>>;
 
-pub(crate) fn task_lock<Runtime: Config>(storage_key: &[u8]) -> Lock<frame_system::Pallet<Runtime>>
+/// The lock is held for most of the task's `timeout`, so that it can be retried before its
+/// deadline.
+pub(crate) fn task_lock<Runtime: Config>(
+	storage_key: &[u8],
+	timeout: Runtime::BlockNumber,
+) -> Lock<frame_system::Pallet<Runtime>>
 where
 	frame_system::Pallet<Runtime>: BlockNumberProvider,
 {
-	let offset = Runtime::UnverifiedTaskTimeout::get()
-		.saturated_into::<u32>()
-		.saturating_sub(2u32);
+	let offset = timeout.saturated_into::<u32>().saturating_sub(2u32);
 	Lock::<frame_system::Pallet<Runtime>>::with_block_and_time_deadline(
 		storage_key,
 		offset,
@@ -36,13 +83,60 @@ where
 	)
 }
 
+/// How urgently a task is worked on. The offchain worker takes higher priorities first and,
+/// within a priority, the earliest deadline first.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Encode, Decode)]
+pub enum TaskPriority {
+	Low,
+	#[default]
+	Normal,
+	High,
+}
+
 pub trait ForwardTask<Runtime: SystemConfig> {
 	type Call;
 	type EvaluationError: Debug;
 	type SchedulerError: Debug;
+	/// What [`ForwardTask::prefetch`] fetched for the tasks of a worker run.
+	type Prefetched: Default;
+	fn priority(&self) -> TaskPriority {
+		TaskPriority::default()
+	}
+	/// Blocks the task has until its first deadline, `None` for the scheduler's
+	/// `UnverifiedTaskTimeout`. Rescheduling backs off from it.
+	fn timeout(&self) -> Option<Runtime::BlockNumber> {
+		None
+	}
+	/// HTTP requests the task is expected to make, counted against the offchain worker's
+	/// `OffchainRequestBudget`.
+	fn http_requests(&self) -> u32 {
+		1
+	}
+	/// Called with the new deadline when the task is rescheduled, for tasks that carry their
+	/// own deadline.
+	fn set_deadline(&mut self, _deadline: Runtime::BlockNumber) {}
+	/// Whether the task's output is already on chain, so the offchain worker can skip it
+	/// before spending any of its budget on it.
+	fn is_persisted(&self, _id: &Runtime::Hash) -> bool {
+		false
+	}
+	/// Called once per offchain worker run with every task the worker is about to forward, so
+	/// that their requests can be made concurrently instead of one task after another. Whatever
+	/// is returned is handed to each [`ForwardTask::forward_task`], which should still work
+	/// when it has nothing for the task. `until` is when the worker's time budget runs out.
+	fn prefetch(_tasks: &[(&Runtime::Hash, &Self)], _until: Timestamp) -> Self::Prefetched
+	where
+		Self: Sized,
+	{
+		Default::default()
+	}
+	/// `assignment` proves the local authority was selected for the task, it should be
+	/// forwarded to whatever checks [`TaskAssignment`](crate::assignment::TaskAssignment).
 	fn forward_task(
 		&self,
 		deadline: Runtime::BlockNumber,
+		assignment: Option<VrfAssignment>,
+		prefetched: &Self::Prefetched,
 	) -> Result<Self::Call, TaskError<Self::EvaluationError, Self::SchedulerError>>;
 }
 
@@ -75,14 +169,36 @@ pub trait TaskV2<Runtime: SystemConfig> {
 	}
 }
 
+/// Lets the pallet that scheduled a task react to it expiring without being completed.
+pub trait OnTaskExpired<Hash, Task> {
+	/// Returns the weight consumed by the reaction.
+	fn on_task_expired(id: &Hash, task: Task) -> Weight;
+}
+
+impl<Hash, Task> OnTaskExpired<Hash, Task> for () {
+	fn on_task_expired(_id: &Hash, _task: Task) -> Weight {
+		Weight::zero()
+	}
+}
+
 pub trait TaskScheduler {
 	type BlockNumber;
 	type Hash;
 	type Task;
 	fn deadline() -> Self::BlockNumber;
+	/// The first deadline of `task`, using its own timeout when it has one.
+	fn deadline_for(task: &Self::Task) -> Self::BlockNumber;
 	fn is_scheduled(deadline: &Self::BlockNumber, id: &Self::Hash) -> bool;
+	/// Whether the task is pending under any deadline, including after being rescheduled.
+	fn is_pending(id: &Self::Hash) -> bool;
 	fn insert(deadline: &Self::BlockNumber, id: &Self::Hash, task: Self::Task);
 	fn remove(deadline: &Self::BlockNumber, id: &Self::Hash);
+	/// The pending task and its current deadline.
+	fn find(id: &Self::Hash) -> Option<(Self::BlockNumber, Self::Task)>;
+	/// Drops a pending task without completing it. Locks authorities took on it expire on
+	/// their own and don't hold up the task registered again under its id, outputs they still
+	/// submit are for the scheduling pallet to reject.
+	fn cancel(id: &Self::Hash) -> Option<Self::Task>;
 }
 
 mod tests;
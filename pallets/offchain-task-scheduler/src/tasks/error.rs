@@ -5,3 +5,18 @@ pub enum TaskError<E, S> {
 	/// the task id has been found in onchain storage, this is most likely a dupe.
 	FinishedTask,
 }
+
+impl<E, S> TaskError<E, S> {
+	/// Converts the errors, keeping the variant.
+	pub fn map<E2, S2>(
+		self,
+		evaluation: impl FnOnce(E) -> E2,
+		scheduler: impl FnOnce(S) -> S2,
+	) -> TaskError<E2, S2> {
+		match self {
+			TaskError::Evaluation(e) => TaskError::Evaluation(evaluation(e)),
+			TaskError::Scheduler(e) => TaskError::Scheduler(scheduler(e)),
+			TaskError::FinishedTask => TaskError::FinishedTask,
+		}
+	}
+}
@@ -1,9 +1,7 @@
 use merlin::Transcript;
-use num::rational::BigRational;
-use num::ToPrimitive;
 use parity_scale_codec::{Decode, Encode};
 use schnorrkel::vrf::VRFInOut;
-use sp_arithmetic::per_things::{PerThing, Perbill, Perquintill};
+use sp_arithmetic::per_things::{PerThing, Perquintill};
 use sp_consensus_vrf::schnorrkel::{PublicKey, SignatureError, VRFOutput, VRFProof};
 use sp_core::crypto::KeyTypeId;
 use sp_core::sr25519::Public;
@@ -14,7 +12,6 @@ use sp_externalities::ExternalitiesExt;
 use sp_keystore::vrf::{VRFSignature, VRFTranscriptData, VRFTranscriptValue};
 #[cfg(feature = "std")]
 use sp_keystore::{KeystoreExt, SyncCryptoStore};
-use sp_runtime_interface::pass_by::PassByCodec;
 use sp_runtime_interface::runtime_interface;
 use sp_std::vec;
 use tracing as log;
@@ -24,111 +21,153 @@ const ENGINE_ID: &[u8; 4] = b"COTS";
 mod model {
 	use super::*;
 
+	const ONE: u128 = Perquintill::ACCURACY as u128;
+
 	/// S is a hyperparameter representing participation rate.
 	/// The higher the value, the higher the chances of being sampled.
-	/// R is the prover's relative stake. the output is proportional to the stake.
-	pub fn model(s: Perquintill, r: Perquintill) -> Perquintill {
-		use pdf::probability_density_function;
-		probability_density_function(s.into(), r.into()).0
-	}
+	/// The prover's relative stake is `stake / total_stake`. the output is proportional to the stake.
+	///
+	/// Computes `1 - (1 - s)^(stake / total_stake)` in fixed point, so that every node agrees on it.
+	pub fn model(s: Perquintill, stake: u128, total_stake: u128) -> Perquintill {
+		if total_stake == 0 || stake >= total_stake {
+			return s;
+		}
+		let divisor = gcd(stake, total_stake);
+		let (mut stake, mut total_stake) = (stake / divisor, total_stake / divisor);
+		// keeps the root's intermediate products within u128
+		while total_stake > u128::from(u64::MAX) {
+			stake >>= 1;
+			total_stake >>= 1;
+		}
+		if stake == 0 {
+			return Perquintill::zero();
+		}
 
-	#[derive(Encode, Decode, PassByCodec)]
-	pub struct Wrap(Perquintill);
+		let complement = ONE - u128::from(s.deconstruct());
+		let f = pow(root(complement, total_stake), stake);
+		Perquintill::from_parts((ONE - f) as u64)
+	}
 
-	impl From<Wrap> for f64 {
-		fn from(w: Wrap) -> Self {
-			w.0.to_sub_1_float()
+	fn gcd(mut a: u128, mut b: u128) -> u128 {
+		while b != 0 {
+			(a, b) = (b, a % b);
 		}
+		a
 	}
 
-	impl From<Perquintill> for Wrap {
-		fn from(p: Perquintill) -> Self {
-			Wrap(p)
-		}
+	// `a` and `b` are at most ONE, so their product fits in u128.
+	fn mul(a: u128, b: u128) -> u128 {
+		a * b / ONE
 	}
 
-	#[runtime_interface]
-	trait Pdf {
-		fn probability_density_function(sample: Wrap, weight: Wrap) -> Wrap {
-			let complement = 1f64 - sample.0.to_sub_1_float();
-			let r: f64 = weight.into();
-			let f: f64 = complement.powf(r);
-			Perquintill::from_float(1f64 - f).into()
+	fn pow(mut base: u128, mut exp: u128) -> u128 {
+		let mut acc = ONE;
+		while exp > 0 {
+			if exp & 1 == 1 {
+				acc = mul(acc, base);
+			}
+			base = mul(base, base);
+			exp >>= 1;
 		}
+		acc
 	}
 
-	// Convert fixed point to f64, Accuracy depends on [PerThing::Inner]
-	pub(super) trait ToFloat: PerThing {
-		fn to_sub_1_float(&self) -> f64
-		where
-			<Self as PerThing>::Inner: Into<u64>,
-		{
-			let c = self.deconstruct();
-			c.into() as f64 / Self::ACCURACY.into() as f64
+	/// The `n`th root of `x`, rounded down. Newton's method approaches it from above, so it stops
+	/// once an iteration no longer decreases.
+	fn root(x: u128, n: u128) -> u128 {
+		if n == 1 || x == 0 || x == ONE {
+			return x;
+		}
+		let mut y = ONE;
+		loop {
+			let p = pow(y, n - 1);
+			if p == 0 {
+				return y;
+			}
+			let next = ((n - 1) * y + x * ONE / p) / n;
+			if next >= y {
+				return y;
+			}
+			y = next;
 		}
 	}
 
-	impl ToFloat for Perquintill {}
-	impl ToFloat for Perbill {}
-
 	#[cfg(test)]
 	mod tests {
 		use super::*;
 		use proptest::prelude::*;
+		use sp_arithmetic::Perbill;
+
+		// Convert fixed point to f64, Accuracy depends on [PerThing::Inner]
+		trait ToFloat: PerThing {
+			fn to_sub_1_float(&self) -> f64
+			where
+				<Self as PerThing>::Inner: Into<u64>,
+			{
+				let c = self.deconstruct();
+				c.into() as f64 / Self::ACCURACY.into() as f64
+			}
+		}
+
+		impl ToFloat for Perquintill {}
+		impl ToFloat for Perbill {}
 
 		#[test]
 		fn model_should_return_expected_distribution() {
-			let r = Perquintill::from_float(0.000_001);
 			let c = Perquintill::from_float(0.5);
 
-			assert!((model(c, r).to_sub_1_float() - 6.931_469_403_334_939E-7).abs() < f64::EPSILON);
+			assert!(
+				(model(c, 1, 1_000_000).to_sub_1_float() - 6.931_469_403_334_939E-7).abs() < 1E-15
+			);
+		}
+
+		#[test]
+		fn model_should_match_exact_roots() {
+			let s = Perquintill::from_percent(75);
+
+			assert_eq!(model(s, 1, 2), Perquintill::from_percent(50));
+			assert_eq!(model(s, 2, 4), Perquintill::from_percent(50));
+			assert_eq!(model(s, 1, 1), s);
 		}
 
 		proptest! {
 			#[test]
 			fn model_does_not_crash(
 				sf in 0.0..=1.0,
-				rf in 0.0..=1.0
+				stake: u128,
+				total_stake: u128
 			) {
 				let s = Perquintill::from_float(sf);
-				let r = Perquintill::from_float(rf);
 
-				let result = model(s, r).to_sub_1_float();
-				assert!(0.0 <= result);
+				model(s, stake, total_stake);
 			}
 		}
 
 		#[test]
 		fn model_result_should_increase_when_argument_s_increases() {
-			let r = Perquintill::from_float(0.000_001);
 			let mut previous = -1.0;
 
 			for i in 0..=1_000_000 {
 				let sf = f64::from(i) * 0.000_001;
 				let s = Perquintill::from_float(sf);
-				let result = model(s, r).to_sub_1_float();
+				let result = model(s, 1, 1_000_000).to_sub_1_float();
 
-				assert!(
-					previous < result,
-					"previous={previous:?}, result={result:?}, s={s:?}, r={r:?}"
-				);
+				assert!(previous < result, "previous={previous:?}, result={result:?}, s={s:?}");
 				previous = result;
 			}
 		}
 
 		#[test]
-		fn model_result_should_increase_when_argument_r_increases() {
+		fn model_result_should_increase_when_argument_stake_increases() {
 			let s = Perquintill::from_float(0.5);
 			let mut previous = -1.0;
 
-			for i in 0..=1_000_000 {
-				let rf = f64::from(i) * 0.000_001;
-				let r = Perquintill::from_float(rf);
-				let result = model(s, r).to_sub_1_float();
+			for stake in 0..=1_000_000 {
+				let result = model(s, stake, 1_000_000).to_sub_1_float();
 
 				assert!(
 					previous < result,
-					"previous={previous:?}, result={result:?}, s={s:?}, r={r:?}"
+					"previous={previous:?}, result={result:?}, s={s:?}, stake={stake:?}"
 				);
 				previous = result;
 			}
@@ -139,14 +178,12 @@ mod model {
 			let mut previous = -1.0;
 
 			for i in 0..=1_000_000 {
-				let xf = f64::from(i) * 0.000_001;
-				let s = Perquintill::from_float(xf);
-				let r = Perquintill::from_float(xf);
-				let result = model(s, r).to_sub_1_float();
+				let s = Perquintill::from_float(f64::from(i) * 0.000_001);
+				let result = model(s, i.into(), 1_000_000).to_sub_1_float();
 
 				assert!(
 					previous < result,
-					"previous={previous:?}, result={result:?}, s={s:?}, r={r:?}"
+					"previous={previous:?}, result={result:?}, s={s:?}, stake={i:?}"
 				);
 				previous = result;
 			}
@@ -155,7 +192,6 @@ mod model {
 		#[test]
 		fn converting_floats_forth_and_back_stays_within_epsilon_precision() {
 			use rand::Rng;
-			use sp_arithmetic::Perbill;
 			let mut rng = rand::thread_rng();
 			for _ in 0..1000 {
 				let r: f64 = rng.gen();
@@ -172,14 +208,9 @@ mod model {
 
 pub mod sortition {
 	use super::*;
-	use model::ToFloat;
 
 	pub fn threshold(sample_size: Perquintill, stake: u128, total_stake: u128) -> u128 {
-		let ratio = Perquintill::from_rational(stake, total_stake);
-		let level = model::model(sample_size, ratio).to_sub_1_float();
-		let level = BigRational::from_float(level).expect("Model codomain [0,1)");
-		let threshold = u128::MAX / level.denom() * level.numer();
-		threshold.to_u128().expect("n * X, X ~ [0,1), n: u128; qed.")
+		model::model(sample_size, stake, total_stake).mul_floor(u128::MAX)
 	}
 
 	pub fn is_selected(inout: &VRFInOut, threshold: u128) -> bool {
@@ -293,6 +324,29 @@ pub fn prove_vrf(
 		.map(|(inout, _proofbatchable)| inout)
 }
 
+/// Verifies the VRF `output` and `proof` that `public` produced for a task and checks whether
+/// they select it under `threshold`. Malformed keys or proofs are never selected.
+pub fn verify_selection(
+	public: &Public,
+	pre_hash: H256,
+	epoch: u64,
+	task_id: H256,
+	output: &[u8],
+	proof: &[u8],
+	threshold: u128,
+) -> bool {
+	let (Ok(pubkey), Ok(output), Ok(proof)) = (
+		PublicKey::from_bytes(&public.0),
+		VRFOutput::decode(&mut &output[..]),
+		VRFProof::decode(&mut &proof[..]),
+	) else {
+		return false;
+	};
+	prove_vrf(pubkey, pre_hash, epoch, task_id, output, proof)
+		.map(|inout| sortition::is_selected(&inout, threshold))
+		.unwrap_or(false)
+}
+
 pub fn finalize_randomness(
 	public: &PublicKey,
 	transcript: Transcript,
@@ -310,6 +364,11 @@ pub fn finalize_randomness(
 	}
 }
 
+/// Host functions offchain workers need to generate VRF outputs with the node's keystore.
+/// Verifying outputs and computing selection thresholds happen entirely within the runtime.
+#[cfg(feature = "std")]
+pub type HostFunctions = vrf::HostFunctions;
+
 #[runtime_interface]
 pub trait Vrf {
 	fn generate_vrf(
@@ -441,4 +500,50 @@ mod tests {
 			assert_eq!(seed, expected_seed);
 		})
 	}
+
+	#[test]
+	fn verify_selection_works() {
+		let PublicData { key_type_id: keyring_id, pre_hash, epoch, task_id } = mocked_public_data();
+
+		let builder = ExtBuilder::default().with_keystore();
+		let pubkey = add_testing_key(keyring_id, &builder, "//fixed");
+		let other = add_testing_key(keyring_id, &builder, "//other");
+
+		builder.build_sans_config().execute_with(|| {
+			let (output, proof) =
+				generate_vrf(keyring_id, &pubkey, pre_hash, epoch, task_id).unwrap();
+			let (output, proof) = (output.encode(), proof.encode());
+
+			assert!(verify_selection(
+				&pubkey,
+				pre_hash,
+				epoch,
+				task_id,
+				&output,
+				&proof,
+				u128::MAX
+			));
+			assert!(!verify_selection(&pubkey, pre_hash, epoch, task_id, &output, &proof, 0));
+			// a proof only holds for the key and input it was made for
+			assert!(!verify_selection(
+				&other,
+				pre_hash,
+				epoch,
+				task_id,
+				&output,
+				&proof,
+				u128::MAX
+			));
+			assert!(!verify_selection(
+				&pubkey,
+				pre_hash,
+				epoch + 1,
+				task_id,
+				&output,
+				&proof,
+				u128::MAX
+			));
+			assert!(!verify_selection(&pubkey, pre_hash, epoch, task_id, &output, &[], u128::MAX));
+		})
+	}
 }
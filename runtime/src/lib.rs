@@ -11,7 +11,10 @@ use frame_election_provider_support::{
 };
 pub use frame_support::traits::EqualPrivilegeOnly;
 use frame_support::{
-	traits::{ConstU128, ConstU32, ConstU8, OnRuntimeUpgrade, U128CurrencyToVote},
+	traits::{
+		ConstU128, ConstU32, ConstU8, GetStorageVersion, Imbalance, OnRuntimeUpgrade, OnUnbalanced,
+		U128CurrencyToVote,
+	},
 	weights::{
 		ConstantMultiplier, WeightToFeeCoefficient, WeightToFeeCoefficients, WeightToFeePolynomial,
 	},
@@ -27,6 +30,10 @@ pub use pallet_grandpa::{
 };
 pub use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
 use pallet_offchain_task_scheduler::crypto::AuthorityId;
+pub use pallet_offchain_task_scheduler::crypto::Public as TaskSchedulerId;
+use pallet_offchain_task_scheduler::incentives::RewardPot;
+use pallet_offchain_task_scheduler::migrations as task_scheduler_migrations;
+use pallet_offchain_task_scheduler::submission::SubmissionMode;
 use pallet_session::historical as session_historical;
 pub use pallet_staking_substrate::{self, StakerStatus, UseValidatorsMap};
 use sp_api::impl_runtime_apis;
@@ -59,7 +66,9 @@ pub use frame_support::{
 	},
 	StorageValue,
 };
+pub use pallet_creditcoin::Blockchain;
 pub use pallet_pos_switch;
+use task_scheduler_runtime_api::{AuthorityLiveness, PendingTask, TaskKindStats};
 
 #[cfg(feature = "try-runtime")]
 use frame_support::traits::UpgradeCheckSelect;
@@ -70,7 +79,7 @@ pub use pallet_timestamp::Call as TimestampCall;
 use pallet_transaction_payment::{CurrencyAdapter, Multiplier, TargetedFeeAdjustment};
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
-pub use sp_runtime::{Perbill, Permill};
+pub use sp_runtime::{Perbill, Percent, Permill};
 
 #[cfg(test)]
 mod tests;
@@ -90,6 +99,25 @@ pub type Signer = <Signature as Verify>::Signer;
 
 pub type Moment = u64;
 
+pallet_offchain_task_scheduler::construct_tasks! {
+	/// A task run by the offchain task scheduler, one variant for each registered kind.
+	pub enum Task for Runtime {
+		VerifyTransfer(pallet_creditcoin::UnverifiedTransferFor<Runtime>) = 0,
+		VerifyContractOwnership(pallet_creditcoin::UnverifiedContractOwnershipFor<Runtime>) = 1,
+		ReverifyTransfer(pallet_creditcoin::TransferReverificationFor<Runtime>) = 2,
+		AttestHeader(pallet_creditcoin::HeaderAttestation) = 3,
+	}
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_offchain_task_scheduler::benchmarking::TaskDefault<Runtime> for Task {
+	fn generate_from_seed(seed: u32) -> Self {
+		Task::VerifyTransfer(pallet_offchain_task_scheduler::benchmarking::TaskDefault::<
+			Runtime,
+		>::generate_from_seed(seed))
+	}
+}
+
 /// Some way of identifying an account on the chain. We intentionally make it equivalent
 /// to the public key of our transaction signing scheme.
 pub type AccountId = <Signer as IdentifyAccount>::AccountId;
@@ -138,6 +166,15 @@ pub mod opaque {
 			pub grandpa: Grandpa,
 			pub babe: Babe,
 			pub im_online: ImOnline,
+			pub gots: TaskScheduler,
+		}
+	}
+
+	impl_opaque_keys! {
+		pub struct OldSessionKeys {
+			pub grandpa: Grandpa,
+			pub babe: Babe,
+			pub im_online: ImOnline,
 		}
 	}
 }
@@ -196,13 +233,47 @@ parameter_types! {
 
 }
 
+parameter_types! {
+	// The chance at least one authority is selected in a task's first round. Each one is with
+	// chance `1 - 0.25^(1/n)`, so about ln(4) ≈ 1.4 of the `n` authorities are expected, and a
+	// round nobody is selected in is widened in the next.
+	pub const TaskSampleSize: Perquintill = Perquintill::from_percent(75);
+	pub const TaskSchedulerPalletId: PalletId = PalletId(*b"cc/tasks");
+	pub const TaskReward: Balance = CTC / 10;
+	// The scheduler's v3 migration bonds it for the authorities registered before bonds were
+	// required.
+	pub const AuthorityBond: Balance = 1_000 * CTC;
+	// The share of transaction fees and tips that pays for task rewards, the rest is burned.
+	pub const TaskRewardFeeShare: Percent = Percent::from_percent(20);
+	pub const OffchainTimeBudget: u64 = MILLISECS_PER_BLOCK / 2;
+	pub const OffchainRequestBudget: u32 = 100;
+	pub const TaskSubmissionMode: SubmissionMode = SubmissionMode::Signed;
+	pub const TaskOutputUnsignedPriority: TransactionPriority = TransactionPriority::max_value() / 2;
+}
+
 impl pallet_offchain_task_scheduler::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type UnverifiedTaskTimeout = ConstU32<60>;
+	type MaxTaskLifetime = ConstU32<{ 2 * HOURS }>;
+	type MaxExpiriesPerBlock = ConstU32<256>;
+	type OnTaskExpired = Task;
+	type TaskSampleSize = TaskSampleSize;
+	type Currency = Balances;
+	type PalletId = TaskSchedulerPalletId;
+	type TaskReward = TaskReward;
+	type AuthorityBond = AuthorityBond;
+	type MissedTasksBeforeWarning = ConstU32<5>;
+	type MissedTasksBeforeSuspension = ConstU32<20>;
+	type MaxAuthoritiesNoted = ConstU32<64>;
+	type OffchainTimeBudget = OffchainTimeBudget;
+	type OffchainRequestBudget = OffchainRequestBudget;
 	type AuthorityId = AuthorityId;
 	type TaskCall = RuntimeCall;
+	type SubmissionMode = TaskSubmissionMode;
+	type TaskOutputs = pallet_creditcoin::TaskOutputCalls<Runtime>;
+	type UnsignedPriority = TaskOutputUnsignedPriority;
 	type WeightInfo = pallet_offchain_task_scheduler::weights::WeightInfo<Runtime>;
-	type Task = pallet_creditcoin::Task<AccountId, BlockNumber, Hash, Moment>;
+	type Task = Task;
 	type Authorship = TaskScheduler;
 }
 
@@ -452,6 +523,43 @@ impl OnRuntimeUpgrade for MigrateBags {
 	}
 }
 
+// NOTE: this should be removed once the runtime has been upgraded on mainnet
+/// Adds the task scheduler's `gots` key to the session keys, while the scheduler's storage is
+/// older than version 4. Migrations run before the pallets', which bump it.
+///
+/// Existing validators inherit their `im_online` key as their `gots` key. It's unique per
+/// validator as the session pallet's key ownership requires, and only they hold its secret, but
+/// they don't become authorities until they rotate to a `gots` key of their own with
+/// `session.set_keys`.
+pub struct UpgradeSessionKeys;
+
+impl OnRuntimeUpgrade for UpgradeSessionKeys {
+	fn on_runtime_upgrade() -> Weight {
+		if TaskScheduler::on_chain_storage_version() >= 4 {
+			return ParityDbWeight::get().reads(1);
+		}
+
+		let upgraded = core::cell::Cell::new(0u64);
+		Session::upgrade_keys::<opaque::OldSessionKeys, _>(|validator, old| {
+			upgraded.set(upgraded.get() + 1);
+			let inherited = sp_core::sr25519::Public::from(old.im_online.clone()).into();
+			opaque::SessionKeys {
+				grandpa: old.grandpa,
+				babe: old.babe,
+				im_online: old.im_online,
+				gots: task_scheduler_migrations::v4::inherit_key::<Runtime>(&validator, inherited),
+			}
+		});
+
+		// every `NextKeys` entry is rewritten, with the owners of its 3 old keys cleared and
+		// those of its 4 new keys set, and its inherited key is recorded. `QueuedKeys` and the
+		// storage version are read and `QueuedKeys` written once.
+		let upgraded = upgraded.get();
+		ParityDbWeight::get()
+			.reads_writes(upgraded.saturating_add(2), upgraded.saturating_mul(9).saturating_add(1))
+	}
+}
+
 const CTC_REWARD_PER_BLOCK: Balance = 2 * CTC;
 pub struct EraPayout;
 impl pallet_staking_substrate::EraPayout<Balance> for EraPayout {
@@ -576,8 +684,27 @@ pub type SlowAdjustingFeeUpdate<R> = TargetedFeeAdjustment<
 	MaximumMultiplier,
 >;
 
+type NegativeImbalance = pallet_balances::NegativeImbalance<Runtime>;
+
+/// Pays [`TaskRewardFeeShare`] of the fees and tips into the task scheduler's reward pot and
+/// burns the rest.
+pub struct DealWithFees;
+
+impl OnUnbalanced<NegativeImbalance> for DealWithFees {
+	fn on_unbalanceds<B>(mut fees_then_tips: impl Iterator<Item = NegativeImbalance>) {
+		let Some(mut fees) = fees_then_tips.next() else {
+			return;
+		};
+		if let Some(tips) = fees_then_tips.next() {
+			tips.merge_into(&mut fees);
+		}
+		let (rewards, _burned) = fees.split(TaskRewardFeeShare::get() * fees.peek());
+		RewardPot::<Runtime>::on_unbalanced(rewards);
+	}
+}
+
 impl pallet_transaction_payment::Config for Runtime {
-	type OnChargeTransaction = CurrencyAdapter<Balances, ()>;
+	type OnChargeTransaction = CurrencyAdapter<Balances, DealWithFees>;
 	type WeightToFee = WeightToCtcFee;
 	type FeeMultiplierUpdate = SlowAdjustingFeeUpdate<Self>;
 	type OperationalFeeMultiplier = ConstU8<1u8>;
@@ -610,8 +737,17 @@ impl pallet_creditcoin::Config for Runtime {
 	type HashIntoNonce = Hash;
 	type UnverifiedTaskTimeout = ConstU32<60>;
 	type WeightInfo = pallet_creditcoin::weights::WeightInfo<Runtime>;
+	type Task = Task;
 	type TaskScheduler = TaskScheduler;
 	type PerBlockCleanupLimit = ConstU32<PER_BLOCK_CLEANUP_LIMIT>;
+	type FailedTransferTtl = ConstU32<{ 7 * DAYS }>;
+	type MaxFailedTransfersPerBlock = ConstU32<64>;
+	type TransferReverificationWindow = ConstU32<{ 30 * MINUTES }>;
+	type AttestedHeaderTtl = ConstU32<{ 7 * DAYS }>;
+	type MaxAttestedHeadersPerBlock = ConstU32<64>;
+	// outlasts both the mortality of the authorities' transactions and `MaxTaskLifetime`
+	type CancelledTransferTtl = BlockHashCount;
+	type MaxCancelledTransfersPerBlock = ConstU32<64>;
 }
 
 impl pallet_difficulty::Config for Runtime {
@@ -709,7 +845,7 @@ pub type Executive = frame_executive::Executive<
 	frame_system::ChainContext<Runtime>,
 	Runtime,
 	AllPalletsWithSystem,
-	(MigrateBags,),
+	(MigrateBags, UpgradeSessionKeys),
 >;
 
 #[cfg(feature = "runtime-benchmarks")]
@@ -964,10 +1100,64 @@ impl_runtime_apis! {
 		}
 	}
 
-	impl task_scheduler_runtime_api::TaskApi<Block, AccountId> for Runtime{
+	impl task_scheduler_runtime_api::TaskApi<Block, AccountId, BlockNumber, Hash, Task> for Runtime{
 		fn offchain_nonce_key(acc: &AccountId) -> Vec<u8>{
 			pallet_offchain_task_scheduler::ocw::nonce_key(acc)
 		}
+
+		fn pending_tasks() -> Vec<PendingTask<BlockNumber, Hash, Task>> {
+			TaskScheduler::pending_task_list()
+				.into_iter()
+				.map(|(deadline, id, task, lifetime)| PendingTask {
+					id,
+					deadline,
+					scheduled_at: lifetime.map(|l| l.scheduled_at),
+					reschedules: lifetime.map_or(0, |l| l.reschedules),
+					task,
+				})
+				.collect()
+		}
+
+		fn pending_task(id: Hash) -> Option<PendingTask<BlockNumber, Hash, Task>> {
+			TaskScheduler::find_pending_task(&id).map(|(deadline, task, lifetime)| PendingTask {
+				id,
+				deadline,
+				scheduled_at: Some(lifetime.scheduled_at),
+				reschedules: lifetime.reschedules,
+				task,
+			})
+		}
+
+		fn authority_liveness(authority: AccountId) -> AuthorityLiveness<BlockNumber> {
+			let stats = TaskScheduler::authority_stats(&authority);
+			AuthorityLiveness {
+				is_authority: TaskScheduler::authorities(&authority).is_some(),
+				suspended_at: TaskScheduler::suspended_authorities(&authority),
+				completed: stats.completed,
+				missed: stats.missed,
+				consecutive_missed: stats.consecutive_missed,
+				last_active: stats.last_active,
+			}
+		}
+
+		fn task_kind_stats() -> Vec<TaskKindStats> {
+			pallet_offchain_task_scheduler::TaskKindStats::<Runtime>::iter()
+				.map(|(kind, stats)| TaskKindStats {
+					kind,
+					scheduled: stats.scheduled,
+					completed: stats.completed,
+					rescheduled: stats.rescheduled,
+					expired: stats.expired,
+					weight: stats.weight.ref_time(),
+				})
+				.collect()
+		}
+	}
+
+	impl creditcoin_runtime_api::CreditcoinApi<Block, Hash, pallet_creditcoin::FailedTransfer<AccountId, BlockNumber>> for Runtime {
+		fn failed_transfer(id: Hash) -> Option<pallet_creditcoin::FailedTransfer<AccountId, BlockNumber>> {
+			Creditcoin::failed_transfers(pallet_creditcoin::TransferId::from(id))
+		}
 	}
 
 	#[cfg(feature = "try-runtime")]